@@ -0,0 +1,226 @@
+use crate::{datetime, sha256};
+use crate::intern::Interned;
+use crate::theme::Theme;
+use crate::{should_ignore_message, Entry, EntryType, Test, TestPass, TestResult};
+use colored::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// how many test paths to list under each cluster before eliding the rest
+const MAX_LISTED_TESTS: usize = 5;
+
+// [normalization] picks what's stripped from messages before failures are grouped, the parts that change
+// between otherwise identical failures; everything is stripped by default
+#[derive(Debug, Deserialize)]
+pub struct NormalizationConfiguration {
+    #[serde(default = "default_enabled")]
+    pub guids: bool,
+    #[serde(default = "default_enabled")]
+    pub addresses: bool, // 0x0000021f5a3c8e40
+    #[serde(default = "default_enabled")]
+    pub instance_suffixes: bool, // BP_Enemy_C_7 is BP_Enemy_C
+    #[serde(default = "default_enabled")]
+    pub numbers: bool, // frame counts, ids, durations
+    #[serde(default)]
+    pub patterns: Vec<String>, // regexes for anything else, each match is replaced with #
+}
+
+pub(crate) fn default_enabled() -> bool {
+    true
+}
+
+pub struct Normalizer {
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl Normalizer {
+    // the user patterns go first so they can match text the built in rules would rewrite
+    pub fn new(config: Option<&NormalizationConfiguration>) -> Result<Normalizer, String> {
+        let mut rules = Vec::new();
+        for pattern in config.map_or(&[][..], |config| config.patterns.as_slice()) {
+            let regex = Regex::new(pattern.as_str()).map_err(|error| format!("normalization pattern {} is invalid: {}", pattern, error))?;
+            rules.push((regex, "#"));
+        }
+        let enabled = |rule: fn(&NormalizationConfiguration) -> bool| config.map_or(true, rule);
+        if enabled(|config| config.guids) {
+            rules.push((Regex::new(r"(?i)\b[0-9a-f]{8}-?[0-9a-f]{4}-?[0-9a-f]{4}-?[0-9a-f]{4}-?[0-9a-f]{12}\b").unwrap(), "<guid>"));
+        }
+        if enabled(|config| config.addresses) {
+            rules.push((Regex::new(r"\b0x[0-9a-fA-F]+\b").unwrap(), "<address>"));
+        }
+        if enabled(|config| config.instance_suffixes) {
+            rules.push((Regex::new(r"_C_\d+\b").unwrap(), "_C"));
+        }
+        if enabled(|config| config.numbers) {
+            rules.push((Regex::new(r"\d+").unwrap(), "#"));
+        }
+        Ok(Normalizer { rules })
+    }
+
+    pub fn normalize(&self, message: &str) -> String {
+        let mut normalized = message.trim().to_owned();
+        for (regex, replacement) in self.rules.iter() {
+            normalized = regex.replace_all(normalized.as_str(), *replacement).into_owned();
+        }
+        normalized
+    }
+}
+
+fn first_error<'a>(entries: &'a [Entry], ignore_regexes: &[String]) -> Option<&'a Entry> {
+    entries.iter().find(|entry| {
+        matches!(entry.event.entry_type, EntryType::Error)
+            && !entry.expected
+            && !should_ignore_message(entry.event.message.as_str(), ignore_regexes)
+    })
+}
+
+// identifies a failure across runs by the test and its normalized first error, so a test failing the same way
+// as yesterday can be told apart from one that now fails for another reason
+pub fn failure_signature(test: &Test, ignore_regexes: &[String], normalizer: &Normalizer) -> String {
+    let first_error = first_error(&test.entries, ignore_regexes).map_or_else(String::new, |entry| normalizer.normalize(entry.event.message.as_str()));
+    let mut hasher = sha256::Sha256::new();
+    hasher.update(test.full_test_path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(first_error.as_bytes());
+    hasher.finish()[..16].to_owned()
+}
+
+pub fn assign_failure_signatures(test_pass: &mut TestPass, ignore_regexes: &[String], normalizer: &Normalizer) {
+    for test in test_pass.tests.iter_mut().filter(|test| test.state == TestResult::Fail) {
+        test.signature = Some(failure_signature(test, ignore_regexes, normalizer));
+    }
+}
+
+// groups failed tests by their normalized first error, an infrastructure problem (missing asset,
+// crashed subsystem) usually shows up as dozens of failures sharing the same first error
+pub fn write_failure_clusters(out: &mut dyn Write, test_pass: &TestPass, ignore_regexes: &[String], normalizer: &Normalizer, min_tests: usize, theme: &Theme) -> io::Result<()> {
+    let mut clusters: Vec<(String, &Entry, Vec<&str>)> = Vec::new();
+    // the same interned message shows up in many failed tests, normalize each one only once
+    let mut signatures: HashMap<&Interned, String> = HashMap::new();
+    for test in test_pass.tests.iter().filter(|test| test.state == TestResult::Fail) {
+        let entry = match first_error(&test.entries, ignore_regexes) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let signature = signatures.entry(&entry.event.message).or_insert_with(|| normalizer.normalize(entry.event.message.as_str())).clone();
+        match clusters.iter_mut().find(|(cluster_signature, _, _)| *cluster_signature == signature) {
+            Some((_, _, tests)) => tests.push(test.full_test_path.as_str()),
+            None => clusters.push((signature, entry, vec![test.full_test_path.as_str()])),
+        }
+    }
+
+    clusters.retain(|(_, _, tests)| tests.len() >= min_tests);
+    if clusters.is_empty() {
+        return Ok(());
+    }
+    clusters.sort_by_key(|(_, _, tests)| std::cmp::Reverse(tests.len()));
+
+    let empty_spacer = theme.spacer();
+    writeln!(out, "{}", theme.fail.paint("suspected root causes"))?;
+    for (_, representative, tests) in clusters.iter() {
+        writeln!(out, "{}{}", empty_spacer, theme.fail.paint(format!("{} tests failed with the same root error", tests.len()).as_str()))?;
+        writeln!(out, "{}{}{}", empty_spacer, empty_spacer, representative.event.message)?;
+        writeln!(out, "{}{}{}:{}", empty_spacer, empty_spacer, representative.filename, representative.line_number)?;
+        for test_path in tests.iter().take(MAX_LISTED_TESTS) {
+            writeln!(out, "{}{}{}", empty_spacer, empty_spacer, test_path.dimmed())?;
+        }
+        if tests.len() > MAX_LISTED_TESTS {
+            writeln!(out, "{}{}{}", empty_spacer, empty_spacer, format!("and {} more", tests.len() - MAX_LISTED_TESTS).dimmed())?;
+        }
+    }
+    Ok(())
+}
+
+// the test the engine was most likely in when it crashed: one the report still marks as in process,
+// otherwise the test that logged the latest entry
+pub fn last_running_test(test_pass: &TestPass) -> Option<&Test> {
+    if let Some(test) = test_pass.tests.iter().find(|test| test.state == TestResult::InProcess) {
+        return Some(test);
+    }
+    test_pass.tests.iter()
+        .filter_map(|test| test.entries.iter().filter_map(|entry| datetime::parse_timestamp(entry.timestamp.as_str())).max().map(|latest| (latest, test)))
+        .max_by_key(|(latest, _)| *latest)
+        .map(|(_, test)| test)
+}
+
+// the engine exited while these were still running, the report's inProcess count can be higher than the tests
+// it still marks as in process when the engine didn't get to write them
+pub fn in_flight_tests(test_pass: &TestPass) -> Vec<&Test> {
+    test_pass.tests.iter().filter(|test| test.state == TestResult::InProcess).collect()
+}
+
+// a report with tests still in process is the engine's state when it exited, not the result of a finished run
+pub fn in_process_count(test_pass: &TestPass) -> usize {
+    let reported = test_pass.in_process.map_or(0, |in_process| in_process.max(0) as usize);
+    reported.max(in_flight_tests(test_pass).len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_do_not_split_a_cluster() {
+        let normalizer = Normalizer::new(None).unwrap();
+        assert_eq!(normalizer.normalize("  Actor BP_Door_C_12 leaked after 300 frames "), "Actor BP_Door_C leaked after # frames");
+        assert_eq!(normalizer.normalize("Actor BP_Door_C_7 leaked after 2 frames"), normalizer.normalize("Actor BP_Door_C_12 leaked after 300 frames"));
+        assert_eq!(normalizer.normalize("Object 0x0000021F5A3C8E40 of 3F2504E0-4F89-11D3-9A0C-0305E82C3301 is pending kill"), "Object <address> of <guid> is pending kill");
+
+        let config: NormalizationConfiguration = toml::from_str(r#"numbers = false
+            patterns = ["took [\\d.]+ms"]"#).unwrap();
+        let normalizer = Normalizer::new(Some(&config)).unwrap();
+        assert_eq!(normalizer.normalize("BP_Enemy_C_7 spawned 2 times, took 3.5ms"), "BP_Enemy_C spawned 2 times, #");
+        let config: NormalizationConfiguration = toml::from_str(r#"patterns = ["("]"#).unwrap();
+        assert!(Normalizer::new(Some(&config)).is_err());
+    }
+
+    #[test]
+    fn signatures_ignore_what_normalization_strips() {
+        let mut test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Enemy.Spawns", "state": "Fail", "warnings": 0, "errors": 1, "artifacts": [], "entries": [
+                    {"event": {"type": "Error", "message": "Actor BP_Enemy_C_7 failed", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]},
+                {"testDisplayName": "", "fullTestPath": "Project.Enemy.Spawns", "state": "Fail", "warnings": 0, "errors": 1, "artifacts": [], "entries": [
+                    {"event": {"type": "Error", "message": "Actor BP_Enemy_C_12 failed", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]},
+                {"testDisplayName": "", "fullTestPath": "Project.Enemy.Spawns", "state": "Fail", "warnings": 0, "errors": 1, "artifacts": [], "entries": [
+                    {"event": {"type": "Error", "message": "Actor BP_Enemy_C_12 was not found", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]}"#);
+        assign_failure_signatures(&mut test_pass, &[], &Normalizer::new(None).unwrap());
+        let signatures: Vec<&str> = test_pass.tests.iter().map(|test| test.signature.as_deref().unwrap()).collect();
+        assert_eq!(signatures[0], signatures[1]);
+        assert_ne!(signatures[1], signatures[2]);
+    }
+
+    fn test_pass(states_and_timestamps: &[(&str, &str)]) -> TestPass {
+        let tests: Vec<String> = states_and_timestamps.iter().enumerate()
+            .map(|(index, (state, timestamp))| format!(r#"{{"testDisplayName": "", "fullTestPath": "Project.Test{}", "state": "{}",
+                "warnings": 0, "errors": 0, "artifacts": [], "entries": [{{"event": {{"type": "Info", "message": "", "context": "", "artifact": ""}},
+                "filename": "", "lineNumber": 0, "timestamp": "{}"}}]}}"#, index, state, timestamp))
+            .collect();
+        crate::test_pass_from_tests(tests.join(",").as_str())
+    }
+
+    #[test]
+    fn crash_blamed_on_the_test_in_process() {
+        let test_pass = test_pass(&[("Success", "2023-10-01T12:00:05Z"), ("InProcess", "2023-10-01T12:00:01Z"), ("NotRun", "")]);
+        assert_eq!(last_running_test(&test_pass).unwrap().full_test_path, "Project.Test1");
+    }
+
+    #[test]
+    fn crash_blamed_on_the_latest_entry() {
+        let test_pass = test_pass(&[("Success", "2023-10-01T12:00:01Z"), ("Fail", "2023-10-01T12:00:05Z"), ("NotRun", "")]);
+        assert_eq!(last_running_test(&test_pass).unwrap().full_test_path, "Project.Test1");
+        assert!(last_running_test(&self::test_pass(&[("NotRun", "")])).is_none());
+    }
+
+    #[test]
+    fn tests_in_process_make_the_run_incomplete() {
+        let mut incomplete = test_pass(&[("Success", ""), ("InProcess", ""), ("NotRun", "")]);
+        let in_flight: Vec<&str> = in_flight_tests(&incomplete).iter().map(|test| test.full_test_path.as_str()).collect();
+        assert_eq!(in_flight, ["Project.Test1"]);
+        assert_eq!(in_process_count(&incomplete), 1);
+        incomplete.in_process = Some(2);
+        assert_eq!(in_process_count(&incomplete), 2);
+        assert_eq!(in_process_count(&test_pass(&[("Success", ""), ("Fail", "")])), 0);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::comparisons::ComparisonExport;
+use crate::{pipeline, sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveManifest {
+    pub report_file: String,
+    pub changelist: Option<String>,
+    pub commit: Option<String>,
+    pub engine_version: Option<String>,
+    pub session_name: Option<String>,
+    pub meta: BTreeMap<String, String>,
+    pub succeeded: i32,
+    pub failed: i32,
+    pub not_run: i32,
+    pub files: Vec<ArchivedFile>,
+}
+
+// sizes and hashes let `parse` notice a truncated or corrupted download instead of silently missing screenshots
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Deserialize)]
+struct ManifestFiles {
+    files: Vec<ArchivedFile>,
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for dir_entry in fs::read_dir(directory)? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn add_file(writer: &mut zip::ZipWriter<File>, options: FileOptions, name: &str, path: &Path) -> Result<ArchivedFile, String> {
+    let mut file = File::open(path).map_err(|error| format!("failed to open {}: {}", path.display(), error))?;
+    writer.start_file(name, options).map_err(|error| format!("failed to add {}: {}", name, error))?;
+    let (size, sha256) = sha256::copy_hashed(&mut file, writer).map_err(|error| format!("failed to add {}: {}", name, error))?;
+    Ok(ArchivedFile { name: name.to_owned(), size, sha256 })
+}
+
+fn archive_name(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+// packs the report directory (index.json, per-test json, screenshots) plus the engine log and exported
+// comparisons into one zip, manifest.json at the root lists everything that was packed
+pub fn write_archive(archive_path: &Path, report_directory: &Path, engine_log: Option<&Path>, comparison_export: Option<&ComparisonExport>, mut manifest: ArchiveManifest) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(report_directory, &mut files).map_err(|error| format!("failed to read {}: {}", report_directory.display(), error))?;
+
+    let archive_file = File::create(archive_path).map_err(|error| format!("failed to create {}: {}", archive_path.display(), error))?;
+    let mut writer = zip::ZipWriter::new(archive_file);
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for path in files.iter() {
+        let name = archive_name(path, report_directory);
+        manifest.files.push(add_file(&mut writer, options, name.as_str(), path)?);
+    }
+    if let Some(engine_log) = engine_log {
+        if engine_log.is_file() {
+            let name = format!("Logs/{}", engine_log.file_name().map_or_else(|| "engine.log".into(), |name| name.to_string_lossy()));
+            manifest.files.push(add_file(&mut writer, options, name.as_str(), engine_log)?);
+        }
+    }
+    // an export inside the report directory was packed with it already
+    if let Some(comparison_export) = comparison_export.filter(|comparison_export| !comparison_export.directory.starts_with(report_directory)) {
+        for file in comparison_export.files.iter() {
+            let name = format!("Comparisons/{}", file.to_string_lossy().replace('\\', "/"));
+            manifest.files.push(add_file(&mut writer, options, name.as_str(), &comparison_export.directory.join(file))?);
+        }
+    }
+
+    let manifest_json = serde_json::to_string_pretty(&manifest).expect("failed to serialize manifest");
+    writer.start_file("manifest.json", options).map_err(|error| format!("failed to add manifest.json: {}", error))?;
+    io::Write::write_all(&mut writer, manifest_json.as_bytes()).map_err(|error| format!("failed to add manifest.json: {}", error))?;
+    writer.finish().map_err(|error| format!("failed to finish {}: {}", archive_path.display(), error))?;
+    Ok(())
+}
+
+// checks an extracted archive against its manifest.json, returns one line per missing or mismatched file
+pub fn verify_extracted(directory: &Path) -> Result<Vec<String>, String> {
+    let manifest_path = directory.join("manifest.json");
+    let manifest_json = fs::read_to_string(&manifest_path).map_err(|error| format!("failed to read {}: {}", manifest_path.display(), error))?;
+    let manifest: ManifestFiles = serde_json::from_str(manifest_json.as_str())
+        .map_err(|error| format!("{} has no file hashes, it may predate them: {}", manifest_path.display(), error))?;
+
+    // large runs archive thousands of screenshots, they're hashed on one thread per core
+    let problems = pipeline::map_bounded(&manifest.files, pipeline::worker_count(None), |archived| {
+        let path = directory.join(archived.name.as_str());
+        match sha256::hash_file(&path) {
+            Err(error) => Some(format!("{}: {}", archived.name, error)),
+            Ok((size, _)) if size != archived.size => Some(format!("{}: {} bytes, expected {}", archived.name, size, archived.size)),
+            Ok((_, sha256)) if sha256 != archived.sha256 => Some(format!("{}: sha256 {}, expected {}", archived.name, sha256, archived.sha256)),
+            Ok(_) => None,
+        }
+    });
+    Ok(problems.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("runtests-archive-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn archive_packs_report_log_and_manifest() {
+        let directory = temp_directory("write");
+        let report_directory = directory.join("Reports");
+        fs::create_dir_all(report_directory.join("Screenshots")).unwrap();
+        fs::write(report_directory.join("index.json"), "{}").unwrap();
+        fs::write(report_directory.join("Screenshots").join("shot.png"), "png").unwrap();
+        let engine_log = directory.join("Castle.log");
+        fs::write(&engine_log, "log").unwrap();
+        let manifest = ArchiveManifest { report_file: "index.json".to_owned(), changelist: Some("42".to_owned()), commit: None, engine_version: None, session_name: None, meta: Default::default(), succeeded: 1, failed: 0, not_run: 0, files: Vec::new() };
+
+        let archive_path = directory.join("report.zip");
+        let comparison_export = ComparisonExport { directory: directory.join("Comparisons"), files: vec![PathBuf::from("Gate").join("Delta.png")] };
+        fs::create_dir_all(comparison_export.directory.join("Gate")).unwrap();
+        fs::write(comparison_export.directory.join("Gate").join("Delta.png"), "png").unwrap();
+        write_archive(&archive_path, &report_directory, Some(&engine_log), Some(&comparison_export), manifest).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(|name| name.to_owned()).collect();
+        let mut manifest_json = String::new();
+        io::Read::read_to_string(&mut archive.by_name("manifest.json").unwrap(), &mut manifest_json).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        names.sort();
+        assert_eq!(names, ["Comparisons/Gate/Delta.png", "Logs/Castle.log", "Screenshots/shot.png", "index.json", "manifest.json"]);
+        let manifest: serde_json::Value = serde_json::from_str(&manifest_json).unwrap();
+        assert_eq!(manifest["changelist"], "42");
+        assert_eq!(manifest["files"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn verify_extracted_reports_changed_and_missing_files() {
+        let directory = temp_directory("verify");
+        fs::write(directory.join("index.json"), "{}").unwrap();
+        fs::write(directory.join("truncated.png"), "png").unwrap();
+        fs::write(directory.join("corrupted.png"), "png").unwrap();
+        let files: Vec<ArchivedFile> = ["index.json", "truncated.png", "corrupted.png"].iter()
+            .map(|name| {
+                let (size, sha256) = sha256::hash_file(&directory.join(name)).unwrap();
+                ArchivedFile { name: name.to_string(), size, sha256 }
+            })
+            .chain(std::iter::once(ArchivedFile { name: "missing.png".to_owned(), size: 0, sha256: String::new() }))
+            .collect();
+        fs::write(directory.join("manifest.json"), serde_json::json!({ "files": files }).to_string()).unwrap();
+        fs::write(directory.join("truncated.png"), "pn").unwrap();
+        fs::write(directory.join("corrupted.png"), "pnh").unwrap();
+
+        let problems = verify_extracted(&directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].starts_with("truncated.png: 2 bytes, expected 3"));
+        assert!(problems[1].starts_with("corrupted.png: sha256 "));
+        assert!(problems[2].starts_with("missing.png: "));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{runner, TestPass, TestResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Inconclusive, // the sync, build or run failed, the change is left out like git bisect skip
+}
+
+// changes are oldest first, the last one is the known bad change and the known good one isn't among them
+pub struct Bisection {
+    pub changes: Vec<String>,
+    last_good: Option<usize>,
+    first_bad: usize,
+    skipped: Vec<bool>,
+}
+
+impl Bisection {
+    pub fn new(changes: Vec<String>) -> Bisection {
+        let skipped = vec![false; changes.len()];
+        Bisection { first_bad: changes.len().saturating_sub(1), changes, last_good: None, skipped }
+    }
+
+    fn untried(&self) -> Vec<usize> {
+        let start = self.last_good.map_or(0, |last_good| last_good + 1);
+        (start..self.first_bad).filter(|index| !self.skipped[*index]).collect()
+    }
+
+    // the change in the middle of the ones not yet known to pass or fail, None once the culprit is found
+    pub fn next_change(&self) -> Option<usize> {
+        let untried = self.untried();
+        untried.get(untried.len() / 2).copied()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.untried().len()
+    }
+
+    pub fn record(&mut self, index: usize, outcome: Outcome) {
+        match outcome {
+            Outcome::Pass => self.last_good = Some(index),
+            Outcome::Fail => self.first_bad = index,
+            Outcome::Inconclusive => self.skipped[index] = true,
+        }
+    }
+
+    pub fn culprit(&self) -> &str {
+        self.changes[self.first_bad].as_str()
+    }
+
+    // changes right before the culprit that couldn't be tested, any of them may have broken the test instead
+    pub fn suspects(&self) -> Vec<&str> {
+        let start = self.last_good.map_or(0, |last_good| last_good + 1);
+        (start..self.first_bad).filter(|index| self.skipped[*index]).map(|index| self.changes[index].as_str()).collect()
+    }
+}
+
+// {change} in the template is replaced with the changelist or commit, ie p4 sync //depot/Castle/...@{change}
+pub fn sync(template: &str, change: &str) -> Result<(), String> {
+    let command_line = template.replace("{change}", change);
+    eprintln!("{}", command_line);
+    match runner::shell_command(command_line.as_str()).stdout(std::io::stderr()).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", command_line, status)),
+        Err(error) => Err(format!("failed to run {}: {}", command_line, error)),
+    }
+}
+
+pub fn test_outcome(test_pass: &TestPass, test: &str) -> Outcome {
+    match test_pass.tests.iter().find(|reported| reported.full_test_path == test).map(|reported| &reported.state) {
+        Some(TestResult::Success) => Outcome::Pass,
+        Some(TestResult::Fail) => Outcome::Fail,
+        _ => Outcome::Inconclusive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(count: usize) -> Vec<String> {
+        (1..=count).map(|change| format!("{}", 12340 + change)).collect()
+    }
+
+    #[test]
+    fn the_first_failing_change_is_found() {
+        let mut bisection = Bisection::new(changes(10));
+        let mut tried = Vec::new();
+        while let Some(index) = bisection.next_change() {
+            tried.push(index);
+            bisection.record(index, if index >= 6 { Outcome::Fail } else { Outcome::Pass });
+        }
+        assert_eq!(tried, [4, 7, 6, 5]);
+        assert_eq!(bisection.culprit(), "12347");
+        assert!(bisection.suspects().is_empty());
+    }
+
+    #[test]
+    fn skipped_changes_are_suspects() {
+        let mut bisection = Bisection::new(changes(4));
+        while let Some(index) = bisection.next_change() {
+            bisection.record(index, match index {
+                0 => Outcome::Pass,
+                1 => Outcome::Inconclusive,
+                _ => Outcome::Fail,
+            });
+        }
+        assert_eq!(bisection.culprit(), "12343");
+        assert_eq!(bisection.suspects(), ["12342"]);
+        assert_eq!(Bisection::new(changes(1)).next_change(), None);
+    }
+}
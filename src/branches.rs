@@ -0,0 +1,95 @@
+use crate::vcs;
+use std::path::Path;
+
+// [branch."release/*"] tables override the top-level settings when the git branch or p4 stream matches, so
+// release branches can run stricter suites than mainline from the same config; * matches any run of characters
+// and the longest matching pattern wins. Tables are merged key by key, anything else is replaced
+fn pattern_matches(pattern: &str, branch: &str) -> bool {
+    let expression = format!("^{}$", pattern.split('*').map(regex::escape).collect::<Vec<String>>().join(".*"));
+    regex::Regex::new(expression.as_str()).is_ok_and(|regex| regex.is_match(branch))
+}
+
+fn merge(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key.as_str()) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+// the git branch of the project, or the p4 stream of the workspace when it isn't in a git repo
+pub fn detect_branch(config: &toml::Value) -> Option<String> {
+    let path_to_project = config.get("path_to_project").and_then(toml::Value::as_str).unwrap_or_default();
+    let project_directory = Path::new(path_to_project).parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    if let Some(git_info) = vcs::current_git_info(project_directory).filter(|git_info| git_info.branch != "HEAD") {
+        return Some(git_info.branch);
+    }
+    let p4_config: vcs::P4Configuration = config.get("p4")?.clone().try_into().ok()?;
+    vcs::current_stream(&p4_config)
+}
+
+// takes the [branch] tables out of the config and applies the one matching branch, returning its pattern
+pub fn apply_branch_profile(config: &mut toml::Value, branch: Option<&str>) -> Result<Option<String>, String> {
+    let profiles = match config.as_table_mut().and_then(|table| table.remove("branch")) {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err("branch must be a table of [branch.\"pattern\"] sections".to_owned()),
+        None => return Ok(None),
+    };
+    let branch = match branch {
+        Some(branch) => branch,
+        None => return Ok(None),
+    };
+    let matching = profiles.into_iter()
+        .filter(|(pattern, _)| pattern_matches(pattern.as_str(), branch))
+        .max_by_key(|(pattern, _)| pattern.len());
+    match matching {
+        Some((pattern, overrides @ toml::Value::Table(_))) => {
+            merge(config, overrides);
+            Ok(Some(pattern))
+        }
+        Some((pattern, _)) => Err(format!("[branch.\"{}\"] must be a table", pattern)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_longest_matching_branch_profile_is_merged() {
+        let config = r#"
+            run_tests = "Project."
+            ignore_regexes = ["LogViewport:"]
+            [duration_regressions]
+            threshold_percent = 50.0
+            window = 10
+            [branch."release/*"]
+            run_tests = "Project. Release."
+            [branch."release/1.*"]
+            ignore_regexes = []
+            [branch."release/1.*".duration_regressions]
+            threshold_percent = 20.0"#;
+        let mut release: toml::Value = toml::from_str(config).unwrap();
+        assert_eq!(apply_branch_profile(&mut release, Some("release/1.2")), Ok(Some("release/1.*".to_owned())));
+        assert_eq!(release["run_tests"].as_str(), Some("Project."));
+        assert!(release["ignore_regexes"].as_array().unwrap().is_empty());
+        assert_eq!(release["duration_regressions"]["threshold_percent"].as_float(), Some(20.0));
+        assert_eq!(release["duration_regressions"]["window"].as_integer(), Some(10));
+        assert!(release.get("branch").is_none());
+
+        let mut main: toml::Value = toml::from_str(config).unwrap();
+        assert_eq!(apply_branch_profile(&mut main, Some("main")), Ok(None));
+        assert_eq!(main["ignore_regexes"].as_array().unwrap().len(), 1);
+        assert!(main.get("branch").is_none());
+        assert!(pattern_matches("release/*", "release/2.0/hotfix"));
+    }
+}
@@ -0,0 +1,68 @@
+use colored::*;
+use serde::Deserialize;
+use std::process::Command;
+
+#[derive(Debug, Deserialize)]
+pub struct BuildConfiguration {
+    pub path_to_build_tool: String, // Build.bat / RunUAT.bat
+    pub target: String,
+    pub platform: String,
+    pub configuration: String,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+// compiles the target before any tests run so a stale binary can't produce results, returns true if the build succeeded
+pub fn run_build(config: &BuildConfiguration, path_to_project: &str) -> bool {
+    eprintln!("building {} {} {}", config.target, config.platform, config.configuration);
+    let build_command = Command::new(&config.path_to_build_tool)
+        .args([
+            config.target.as_str(),
+            config.platform.as_str(),
+            config.configuration.as_str(),
+            format!("-Project={}", path_to_project).as_str(),
+            "-WaitMutex",
+        ])
+        .args(&config.extra_args)
+        .stdout(std::io::stderr())
+        .spawn();
+    // a wrong or non-executable path_to_build_tool is a failed build, not a reason to panic
+    let build_exit_code = match build_command.and_then(|mut build_command| build_command.wait()) {
+        Ok(build_exit_code) => build_exit_code,
+        Err(error) => {
+            eprintln!("{}{}: {}", "failed to run the build tool ".red(), config.path_to_build_tool, error);
+            return false;
+        }
+    };
+    if !build_exit_code.success() {
+        match build_exit_code.code() {
+            Some(code) => eprintln!("{}{}", "build exited with status code: ".red(), code),
+            None => eprintln!("{}", "build process terminated by signal".red()),
+        }
+        return false;
+    }
+    eprintln!("build finished");
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn build_tool(path_to_build_tool: &str) -> BuildConfiguration {
+        BuildConfiguration {
+            path_to_build_tool: path_to_build_tool.to_owned(),
+            target: "CastleEditor".to_owned(),
+            platform: "Linux".to_owned(),
+            configuration: "Development".to_owned(),
+            extra_args: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn build_exit_code_decides_success() {
+        assert!(run_build(&build_tool("true"), "Castle.uproject"));
+        assert!(!run_build(&build_tool("false"), "Castle.uproject"));
+        assert!(!run_build(&build_tool("/nonexistent/RunUAT.sh"), "Castle.uproject"));
+    }
+}
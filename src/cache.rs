@@ -0,0 +1,108 @@
+use crate::runner::TestSelection;
+use crate::{instances, sha256, TestPass, TestResult};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// --cache remembers, per test, a hash of the inputs of the run it last passed in; a test is skipped while the
+// inputs hash hasn't changed since. The inputs are the synced change, the engine and project binaries and the
+// config; content edits in a dirty workspace aren't seen, --cache is for iterating on code
+pub type ResultCache = BTreeMap<String, String>;
+
+// newest modification time, size and count of the files under a directory, rebuilding anything changes it
+pub fn fingerprint(path: &Path) -> String {
+    fn visit(path: &Path, newest: &mut u64, size: &mut u64, count: &mut u64) {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return,
+        };
+        if metadata.is_dir() {
+            for dir_entry in fs::read_dir(path).into_iter().flatten().flatten() {
+                visit(&dir_entry.path(), newest, size, count);
+            }
+            return;
+        }
+        let modified = metadata.modified().ok().and_then(|modified| modified.duration_since(UNIX_EPOCH).ok()).map_or(0, |since| since.as_secs());
+        *newest = (*newest).max(modified);
+        *size += metadata.len();
+        *count += 1;
+    }
+    let (mut newest, mut size, mut count) = (0, 0, 0);
+    visit(path, &mut newest, &mut size, &mut count);
+    format!("{}:{}:{}", newest, size, count)
+}
+
+pub fn inputs_hash(inputs: &[String]) -> String {
+    let mut hasher = sha256::Sha256::new();
+    for input in inputs {
+        hasher.update(input.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finish()
+}
+
+// a missing or unreadable cache is an empty one, every test runs
+pub fn load_cache(path: &Path) -> ResultCache {
+    fs::read(path).ok().and_then(|contents| serde_json::from_slice(&contents).ok()).unwrap_or_default()
+}
+
+pub fn save_cache(path: &Path, cache: &ResultCache) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(cache).expect("failed to serialize the result cache"))
+}
+
+// (to run, cached passes), prefixes and anything else not known by its full path always run
+pub fn split_cached(tests: Vec<String>, cache: &ResultCache, inputs_hash: &str) -> (Vec<String>, Vec<String>) {
+    tests.into_iter().partition(|test| cache.get(test).map(String::as_str) != Some(inputs_hash))
+}
+
+// one engine's share of the tests without the cached ones, None when nothing is left for it to run
+pub fn without_cached(selection: TestSelection, cached: &[String]) -> Option<TestSelection> {
+    match selection {
+        TestSelection::Tests(run_tests) => {
+            let tests: Vec<String> = instances::individual_tests(&run_tests).into_iter().filter(|test| !cached.contains(test)).collect();
+            if tests.is_empty() { None } else { Some(TestSelection::Tests(tests.join(" "))) }
+        }
+        filter => Some(filter),
+    }
+}
+
+// passes are remembered with this run's inputs, anything else has to run again next time
+pub fn record(cache: &mut ResultCache, test_pass: &TestPass, inputs_hash: &str) {
+    for test in test_pass.tests.iter() {
+        if test.state == TestResult::Success {
+            cache.insert(test.full_test_path.clone(), inputs_hash.to_owned());
+        } else {
+            cache.remove(&test.full_test_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_are_cached_until_the_inputs_change() {
+        let test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "entries": [], "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Fail", "entries": [], "warnings": 0, "errors": 1, "artifacts": []}"#);
+        let inputs = inputs_hash(&["12345".to_owned(), "1696161600:1024:3".to_owned()]);
+        let mut cache = ResultCache::new();
+        cache.insert("Project.Door.Closes".to_owned(), inputs.clone());
+        record(&mut cache, &test_pass, inputs.as_str());
+
+        let tests = vec!["Project.Door.Opens".to_owned(), "Project.Door.Closes".to_owned(), "Project.Gate.".to_owned()];
+        assert_eq!(split_cached(tests.clone(), &cache, inputs.as_str()), (vec!["Project.Door.Closes".to_owned(), "Project.Gate.".to_owned()], vec!["Project.Door.Opens".to_owned()]));
+        let rebuilt = inputs_hash(&["12345".to_owned(), "1696165200:1032:3".to_owned()]);
+        assert_eq!(split_cached(tests.clone(), &cache, rebuilt.as_str()).0, tests);
+
+        let cached = ["Project.Door.Opens".to_owned()];
+        assert!(without_cached(TestSelection::Tests("Project.Door.Opens".to_owned()), &cached).is_none());
+        assert!(matches!(without_cached(TestSelection::Tests("Project.Door.Opens+Project.Gate.".to_owned()), &cached), Some(TestSelection::Tests(run_tests)) if run_tests == "Project.Gate."));
+    }
+}
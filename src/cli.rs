@@ -0,0 +1,1427 @@
+use crate::{
+    analysis, archive, bisect, build, cache, comparisons, completions, confighelp, crash, critical, csvprofile, datetime,
+    details, discord, display, diskspace, editor, engine, expected, filters, find, formats, gitlab, history, instances, jenkins,
+    lang, lock, logexcerpts, manifest, paths, perf, pipeline, plugins, remote, report, rerun, retention, runner, schedule,
+    screenshots, selfupdate, shuffle, telemetry, testinfo, testmap, theme, validate, vcs, warmup, width,
+    EXIT_BUILD_FAILED, EXIT_REPORT_NOT_FOUND, EXIT_ARCHIVE_FAILED, EXIT_DURATION_REGRESSION, EXIT_INVALID_ARGUMENTS,
+    EXIT_INVALID_CONFIG, EXIT_ENGINE_CRASHED, EXIT_PERF_REGRESSION, EXIT_NO_TESTS, EXIT_TOO_FEW_TESTS, EXIT_LOCKED,
+    EXIT_LOW_DISK_SPACE, EXIT_UPDATE_FAILED, EXIT_CRITICAL_TESTS_FAILED, EXIT_INCOMPLETE_RUN, EXIT_INVALID_REPORT,
+    EXIT_ENGINE_LAUNCH_FAILED,
+    local_reports_directory, parse_config, project_directory, should_ignore_message, utf_from_bytes, EntryType, RunMetadata,
+    TestConfiguration, TestPass, TestResult,
+};
+use clap_v3::{App, Arg, ArgMatches};
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::IsTerminal;
+
+pub(crate) fn app() -> App<'static> {
+    App::new("runtests")
+        .arg(Arg::with_name("tests")
+            .help("Sets the tests to run")
+            .required(false)
+            .multiple(true))
+        .arg(Arg::new("config")
+            .short('c')
+            .long("config")
+            .value_name("FILE")
+            .default_value("testconfig.toml")
+            .help("Sets a custom config file")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("chdir")
+            .long("chdir")
+            .value_name("DIR")
+            .help("Changes to this directory before doing anything else, the config and relative paths on the command line are found from there")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("branch")
+            .long("branch")
+            .value_name("BRANCH")
+            .help("Picks the [branch] profile for this branch instead of the project's git branch or p4 stream")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("tests-list")
+            .long("tests")
+            .value_name("TESTS")
+            .help("Sets the tests to run, separated by spaces or +; - reads them from stdin, one or more per line")
+            .conflicts_with("filter")
+            .takes_value(true))
+        .arg(Arg::new("filter")
+            .long("filter")
+            .value_name("FLAG")
+            .help("Runs every test with this automation flag instead of named tests")
+            .possible_values(runner::FILTER_NAMES)
+            .conflicts_with("tests")
+            .takes_value(true))
+        .arg(Arg::new("shuffle")
+            .long("shuffle")
+            .value_name("SEED")
+            .help("Runs the tests given on the command line in random order, --shuffle=SEED reproduces an earlier order")
+            .takes_value(true)
+            .min_values(0)
+            .require_equals(true))
+        .arg(Arg::new("order")
+            .long("order")
+            .value_name("ORDER")
+            .help("Orders the tests given on the command line by their durations or recent failures in history_file")
+            .possible_values(history::ORDER_NAMES)
+            .conflicts_with("shuffle")
+            .takes_value(true))
+        .arg(Arg::new("isolate")
+            .long("isolate")
+            .help("Runs every named test in its own engine process (--jobs or max_parallel_engines at a time) and merges the reports"))
+        .arg(Arg::new("jobs")
+            .long("jobs")
+            .short('j')
+            .value_name("N")
+            .help("Splits the named tests across N engine processes running at once and merges the reports")
+            .takes_value(true))
+        .arg(Arg::new("skip-build")
+            .long("skip-build")
+            .help("Skips the [build] step even if it is configured"))
+        .arg(Arg::new("cache")
+            .long("cache")
+            .help("Skips named tests that passed before with the same changelist or commit, binaries and config, and remembers this run's passes"))
+        .arg(Arg::new("skip-warmup")
+            .long("skip-warmup")
+            .help("Skips the warmup_tests launch even if it is configured"))
+        .arg(Arg::new("force")
+            .long("force")
+            .help("Takes over the report directory's lock even if another run seems to hold it"))
+        .arg(Arg::new("open-in")
+            .long("open-in")
+            .value_name("EDITOR")
+            .help("Opens the locations of failing errors in an editor (vscode, rider, clion, sublime, notepad++)")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("plain")
+            .long("plain")
+            .help("Deterministic output without colors, durations or run metadata, suitable for diffing")
+            .global(true))
+        .arg(Arg::new("width")
+            .long("width")
+            .value_name("COLUMNS")
+            .help("Wraps console messages to this width instead of the terminal's, 0 turns wrapping off")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("lang")
+            .long("lang")
+            .value_name("LANG")
+            .help("Language of the report summaries (en, ja)")
+            .possible_values(lang::LANG_NAMES)
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("durations")
+            .long("durations")
+            .help("Prints the elapsed time even with --plain")
+            .global(true))
+        .arg(Arg::new("report-file")
+            .long("report-file")
+            .value_name("FILE")
+            .help("Reads this index.json instead of searching path_to_reports for the latest one")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("archive")
+            .long("archive")
+            .value_name("ZIP")
+            .help("Packs the report directory and engine log into a zip with a manifest")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("html")
+            .long("html")
+            .value_name("FILE")
+            .help("Writes a standalone html report, including screenshot comparisons for failed tests")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("format")
+            .long("format")
+            .value_name("FORMAT[=FILE]")
+            .help("Output format (console, json, junit, markdown, html, gitlab, buildkite, ctrf), optionally written to a file; may be given several times")
+            .global(true)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::new("csv-profiles")
+            .long("csv-profiles")
+            .help("Summarizes csvprofile captures attached to tests (frame time percentiles, top stats) in the json and html reports")
+            .global(true))
+        .arg(Arg::new("details-file")
+            .long("details-file")
+            .value_name("FILE")
+            .help("Only prints test status lines and the summary, every entry (including info and ignored ones) goes to this file")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("split-logs")
+            .long("split-logs")
+            .value_name("DIR")
+            .help("Writes each failing test's complete entry list to its own file in this directory, for attaching to bug tickets")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("log-excerpts")
+            .long("log-excerpts")
+            .help("Shows the engine log lines around each error in the console and html reports")
+            .global(true))
+        .arg(Arg::new("show-ignored")
+            .long("show-ignored")
+            .help("Prints entries hidden by ignore_regexes, dimmed, with the pattern that matched and a count per pattern")
+            .global(true))
+        .arg(Arg::new("timestamps")
+            .long("timestamps")
+            .help("Prints each entry's timestamp")
+            .global(true))
+        .arg(Arg::new("timeline")
+            .long("timeline")
+            .help("Prints every entry across all tests in chronological order after the report")
+            .global(true))
+        .arg(Arg::new("warnings-by-file")
+            .long("warnings-by-file")
+            .help("Prints the files that logged warnings and errors across all tests, noisiest first, after the report")
+            .global(true))
+        .arg(Arg::new("strict-parse")
+            .long("strict-parse")
+            .help("Fails on anything in the report that doesn't fit its schema instead of leaving it out with a schema warning, including keys it doesn't know, for trying a new engine version")
+            .global(true))
+        .arg(Arg::new("session-name")
+            .long("session-name")
+            .value_name("NAME")
+            .help("Labels the run (e.g. nightly-win64) in the history file and exports")
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::new("meta")
+            .long("meta")
+            .value_name("KEY=VALUE")
+            .help("Records an extra label with the run in the history file and exports; may be given several times")
+            .global(true)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .subcommand(App::new("parse")
+            .about("Prints an existing report without running the engine")
+            .arg(Arg::new("input")
+                .help("A report directory, an index.json, a Gauntlet output folder, a zipped report, an http(s) url of an index.json or zip, or - for stdin")
+                .required(true)))
+        .subcommand(App::new("find")
+            .about("Searches test paths and entry messages in the most recent report")
+            .arg(Arg::new("query")
+                .help("Text to look for, test paths are matched fuzzily")
+                .required(true))
+            .arg(Arg::new("failed")
+                .long("failed")
+                .help("Only shows failed tests")))
+        .subcommand(App::new("list")
+            .about("Lists the known tests: the config's selections, the most recent run and the functional test maps")
+            .arg(Arg::new("functional")
+                .long("functional")
+                .help("Only lists the functional test maps, whose tests the engine only knows once the map is loaded")))
+        .subcommand(App::new("map-tests")
+            .about("Lists where tests are defined, from the automation macros under Source/ and the functional test maps under Content/")
+            .arg(Arg::new("for")
+                .long("for")
+                .value_name("FILE")
+                .help("Only lists the paths of tests defined in these files, ie the ones a change to them affects; may be given several times")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)))
+        .subcommand(App::new("prune")
+            .about("Deletes report directories and history runs older than [retention] allows")
+            .arg(Arg::new("dry-run")
+                .long("dry-run")
+                .help("Lists what would be deleted without deleting it")))
+        .subcommand(App::new("rerun")
+            .about("Lists the failed tests of the most recent report to pick from and runs the ones picked")
+            .arg(Arg::new("all")
+                .long("all")
+                .help("Runs every failed test without asking")))
+        .subcommand(App::new("bisect")
+            .about("Syncs, builds and runs one test change by change to find the change that broke it")
+            .arg(Arg::new("test")
+                .help("The full path of the failing test")
+                .required(true))
+            .arg(Arg::new("good")
+                .long("good")
+                .value_name("CHANGE")
+                .help("A changelist or commit the test passes at")
+                .required(true)
+                .takes_value(true))
+            .arg(Arg::new("bad")
+                .long("bad")
+                .value_name("CHANGE")
+                .help("A later changelist or commit the test fails at")
+                .required(true)
+                .takes_value(true))
+            .arg(Arg::new("sync")
+                .long("sync")
+                .value_name("COMMAND")
+                .help("Syncs the workspace to {change}, ie \"p4 sync //depot/Castle/...@{change}\" or \"git checkout {change}\"")
+                .required(true)
+                .takes_value(true))
+            .arg(Arg::new("skip-build")
+                .long("skip-build")
+                .help("Skips the [build] step at every change")))
+        .subcommand(App::new("failing-since")
+            .about("Lists the tests failing in the latest history run of the session with the change they began failing at and the one they last passed at"))
+        .subcommand(App::new("schedule")
+            .about("Stays running and starts a run whenever the [schedule] cron expression comes around"))
+        .subcommand(App::new("self-update")
+            .about("Replaces this runtests with the latest release once its sha256 checks out")
+            .arg(Arg::new("check")
+                .long("check")
+                .help("Only reports whether a newer release is out")))
+        .subcommand(App::new("validate")
+            .about("Checks the config, paths, regexes and engine version without running any tests"))
+        .subcommand(App::new("help-all")
+            .about("Prints every config key with its type, default and an example"))
+        .subcommand(App::new("completions")
+            .about("Prints a completion script for a shell, test names are completed from the most recent run")
+            .arg(Arg::new("shell")
+                .help("The shell to complete for")
+                .possible_values(completions::SHELL_NAMES)
+                .required_unless("list-tests"))
+            .arg(Arg::new("list-tests")
+                .long("list-tests")
+                .help("Prints the test names the scripts complete, one per line")))
+}
+
+pub fn main() {
+    let matches = app().get_matches();
+
+    if let Some(directory) = matches.value_of("chdir") {
+        if let Err(error) = std::env::set_current_dir(directory) {
+            eprintln!("{}{}: {}", "failed to change to ".red(), directory, error);
+            std::process::exit(EXIT_INVALID_ARGUMENTS);
+        }
+    }
+
+    let plain = matches.is_present("plain");
+    if plain {
+        colored::control::set_override(false);
+    }
+
+    for format_spec in format_specs(&matches) {
+        if formats::formatter(format_spec.name.as_str()).is_none() {
+            eprintln!("{}{} (expected one of {})", "unknown format: ".red(), format_spec.name, formats::FORMAT_NAMES.join(", "));
+            std::process::exit(EXIT_INVALID_ARGUMENTS);
+        }
+    }
+
+    let session_name = matches.value_of("session-name").map(str::to_owned);
+    let meta = match run_meta(&matches) {
+        Ok(meta) => meta,
+        Err(error) => {
+            eprintln!("{}{}", "invalid --meta: ".red(), error);
+            std::process::exit(EXIT_INVALID_ARGUMENTS);
+        }
+    };
+
+    let config_file_path = matches.value_of("config").expect("failed to get config file");
+
+    if matches.subcommand_matches("help-all").is_some() {
+        let mut help = String::new();
+        confighelp::write_config_help(&mut help);
+        print!("{}", help);
+        return;
+    }
+
+    // completion scripts are printed without a config, and a missing or broken one only means no test names
+    if let Some(completions_matches) = matches.subcommand_matches("completions") {
+        if completions_matches.is_present("list-tests") {
+            let config = std::fs::read(config_file_path).ok().and_then(|buffer| parse_config(utf_from_bytes(&buffer).as_str(), config_file_path, None).ok());
+            for test_name in config.as_ref().map_or_else(Vec::new, |(config, _)| completions::test_names(config)) {
+                println!("{}", test_name);
+            }
+        } else if let Some(script) = completions_matches.value_of("shell").and_then(completions::script) {
+            print!("{}", script);
+        }
+        return;
+    }
+
+    // self-update works without a config, [updates] only points it somewhere else
+    if let Some(self_update_matches) = matches.subcommand_matches("self-update") {
+        let config = std::fs::read(config_file_path).ok().and_then(|buffer| parse_config(utf_from_bytes(&buffer).as_str(), config_file_path, None).ok());
+        let updates_config = config.and_then(|(config, _)| config.updates).unwrap_or_default();
+        match selfupdate::self_update(&updates_config, self_update_matches.is_present("check")) {
+            Ok(message) => println!("{}", message),
+            Err(error) => {
+                eprintln!("{}{}", "failed to update: ".red(), error);
+                std::process::exit(EXIT_UPDATE_FAILED);
+            }
+        }
+        return;
+    }
+
+    // validate reads the config itself so a broken one is reported as a failed check instead of a panic
+    if matches.subcommand_matches("validate").is_some() {
+        let failures = validate::validate(config_file_path, &theme::Theme::from_config(None, selected_lang(&matches)));
+        if failures > 0 {
+            println!("{}", format!("{} checks failed", failures).red());
+            std::process::exit(EXIT_INVALID_CONFIG);
+        }
+        println!("{}", "all checks passed".green());
+        return;
+    }
+
+    let config_toml = load_file(config_file_path);
+    let (config, expanded_config) = parse_config(config_toml.as_str(), config_file_path, matches.value_of("branch")).unwrap_or_else(|error| {
+        eprintln!("{}{}", format!("failed to parse {}: ", config_file_path).red(), error);
+        std::process::exit(EXIT_INVALID_CONFIG);
+    });
+    if let Some((branch, pattern)) = &config.branch_profile {
+        eprintln!("branch {} uses [branch.\"{}\"]", branch, pattern);
+    }
+
+    if let Some(parse_matches) = matches.subcommand_matches("parse") {
+        begin_manifest(config_file_path, &expanded_config, &config);
+        let input = parse_matches.value_of("input").expect("failed to get input");
+        let headers: Vec<(&str, &str)> = config.report_headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+        let report_file = match report::fetch_report_input(input, &headers).unwrap_or_else(|| report::resolve_report_input(input)) {
+            Ok(report_file) => report_file,
+            Err(error) => {
+                eprintln!("{}{}", "failed to find a report: ".red(), error);
+                exit(EXIT_REPORT_NOT_FOUND);
+            }
+        };
+        eprintln!("reading report: {}", report_file.display());
+        let run_metadata = RunMetadata { session_name, meta, ..RunMetadata::default() };
+        process_report(&report_file, &config, &matches, &run_metadata, false);
+        return;
+    }
+
+    if let Some(find_matches) = matches.subcommand_matches("find") {
+        let test_pass = load_test_pass(&latest_report_file(&matches, &config), &config, matches.is_present("strict-parse"));
+        let query = find_matches.value_of("query").expect("failed to get query");
+        find::find(&test_pass, query, find_matches.is_present("failed"), &config.ignore_regexes, &theme::Theme::from_config(config.theme.as_ref(), selected_lang(&matches)));
+        return;
+    }
+
+    if let Some(list_matches) = matches.subcommand_matches("list") {
+        // with [remote] the project is on the remote machine, its maps can't be read from here
+        let functional_tests = if config.remote.is_none() { testmap::functional_test_maps(project_directory(&config), &config.functional_test_maps) } else { Vec::new() };
+        for warning in testmap::filter_warnings(&functional_tests, config.run_tests.as_str(), &config.include_tests, &config.exclude_tests) {
+            eprintln!("{}", warning.yellow());
+        }
+        let mut names: std::collections::BTreeSet<String> = if list_matches.is_present("functional") { Default::default() } else { completions::test_names(&config).into_iter().collect() };
+        names.extend(functional_tests.into_iter().map(|location| location.test));
+        for name in names {
+            println!("{}", name);
+        }
+        return;
+    }
+
+    if let Some(map_tests_matches) = matches.subcommand_matches("map-tests") {
+        let locations = testmap::map_tests(project_directory(&config));
+        if let Some(test_map_file) = &config.test_map_file {
+            match testmap::save_test_map(std::path::Path::new(test_map_file.as_str()), &locations) {
+                Ok(()) => eprintln!("wrote {} test locations to {}", locations.len(), test_map_file),
+                Err(error) => eprintln!("{}{}", "failed to write the test map: ".red(), error),
+            }
+        }
+        match map_tests_matches.values_of("for") {
+            Some(files) => {
+                let files: Vec<&str> = files.collect();
+                for location in testmap::tests_in_files(&locations, &files) {
+                    println!("{}", location.test);
+                }
+            }
+            None => {
+                for location in locations.iter() {
+                    match location.line {
+                        Some(line) => println!("{} {}:{}", location.test, location.file, line),
+                        None => println!("{} {}", location.test, location.file),
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    if let Some(prune_matches) = matches.subcommand_matches("prune") {
+        match &config.retention {
+            Some(retention_config) => prune(&config, retention_config, None, prune_matches.is_present("dry-run")),
+            None => eprintln!("{}", "nothing to prune without a [retention] section".yellow()),
+        }
+        return;
+    }
+
+    if matches.subcommand_matches("failing-since").is_some() {
+        match &config.history_file {
+            Some(history_file) => {
+                let history = history::load_history(std::path::Path::new(history_file.as_str()));
+                history::print_failing_since(&history::failing_since(&history, session_name.as_deref()), &theme::Theme::from_config(config.theme.as_ref(), selected_lang(&matches)));
+            }
+            None => eprintln!("{}", "failing-since needs a history_file to look back through".yellow()),
+        }
+        return;
+    }
+
+    if matches.subcommand_matches("schedule").is_some() {
+        run_schedule(config_file_path, &config);
+        return;
+    }
+
+    if let Some(bisect_matches) = matches.subcommand_matches("bisect") {
+        bisect(config_file_path, &config, bisect_matches);
+        return;
+    }
+
+    // rerun picks before anything is started, the picked tests are then run like ones given to --tests
+    let mut rerun_tests = Vec::new();
+    if let Some(rerun_matches) = matches.subcommand_matches("rerun") {
+        let test_pass = load_test_pass(&latest_report_file(&matches, &config), &config, matches.is_present("strict-parse"));
+        let failed: Vec<String> = test_pass.tests.iter().filter(|test| test.state == TestResult::Fail).map(|test| test.full_test_path.clone()).collect();
+        if failed.is_empty() {
+            eprintln!("{}", "no tests failed in the most recent report".green());
+            return;
+        }
+        rerun_tests = if rerun_matches.is_present("all") {
+            failed
+        } else if !std::io::stdin().is_terminal() {
+            eprintln!("{}", "rerun asks which tests to run, pass --all when stdin isn't a terminal".red());
+            std::process::exit(EXIT_INVALID_ARGUMENTS);
+        } else {
+            match rerun::pick(&failed, &mut std::io::stdin().lock(), &mut std::io::stderr()) {
+                Ok(Some(picked)) if !picked.is_empty() => picked,
+                Ok(_) => {
+                    eprintln!("no tests picked");
+                    return;
+                }
+                Err(error) => {
+                    eprintln!("{}{}", "failed to read the picked tests: ".red(), error);
+                    std::process::exit(EXIT_INVALID_ARGUMENTS);
+                }
+            }
+        };
+    }
+
+    if let Some(updates_config) = config.updates.as_ref().filter(|updates_config| updates_config.check) {
+        if let Some(notice) = selfupdate::newer_release_notice(updates_config) {
+            eprintln!("{}", notice.yellow());
+        }
+    }
+
+    begin_manifest(config_file_path, &expanded_config, &config);
+    let lock_file = local_reports_directory(&config).join("runtests.lock");
+    match lock::acquire(&lock_file, matches.is_present("force")) {
+        Ok(Some(owner)) => eprintln!("{}", format!("--force: took over {} from pid {} on {} (started {})", lock_file.display(), owner.pid, owner.host, owner.started_at).yellow()),
+        Ok(None) => {}
+        Err(lock::LockError::Held(owner)) => {
+            eprintln!("{}", format!("another run (pid {} on {}, started {}) holds {}", owner.pid, owner.host, owner.started_at, lock_file.display()).red());
+            eprintln!("wait for it to finish, or pass --force if it's gone");
+            exit(EXIT_LOCKED);
+        }
+        Err(lock::LockError::Stale(owner)) => {
+            eprintln!("{}", format!("{} was left behind by pid {}, which is no longer running (started {})", lock_file.display(), owner.pid, owner.started_at).red());
+            eprintln!("pass --force to take it over");
+            exit(EXIT_LOCKED);
+        }
+        Err(lock::LockError::Io(error)) => {
+            eprintln!("{}{}: {}", "failed to lock ".red(), lock_file.display(), error);
+            exit(EXIT_LOCKED);
+        }
+    }
+    if let Some(min_free_disk_gb) = config.min_free_disk_gb {
+        for directory in disk_space_directories(&config) {
+            if let Err(error) = diskspace::check_free_space(&directory, min_free_disk_gb) {
+                eprintln!("{}{}", "not enough disk space: ".red(), error);
+                exit(EXIT_LOW_DISK_SPACE);
+            }
+        }
+    }
+    match &config.engine_discovery {
+        Some(Ok(found)) => eprintln!("path_to_unrealengine is omitted, found {}", found),
+        Some(Err(error)) => {
+            eprintln!("{}{}", "path_to_unrealengine is omitted and the engine wasn't found: ".red(), error);
+            exit(EXIT_INVALID_CONFIG);
+        }
+        None => {}
+    }
+    let started_at = chrono::Utc::now();
+    let mut run_tests = config.run_tests.to_owned();
+
+    let mut shuffle_seed = None;
+    // - stands for the tests piped in, ie runtests completions --list-tests | grep Combat | runtests --tests -
+    let mut tests: Vec<String> = matches.values_of("tests").into_iter().flatten().map(str::to_owned).collect();
+    tests.extend(matches.value_of("tests-list").map_or_else(Vec::new, instances::individual_tests));
+    tests.extend(rerun_tests);
+    if let Some(stdin_position) = tests.iter().position(|test| test == "-") {
+        tests.retain(|test| test != "-");
+        let piped_tests = instances::read_tests(std::io::stdin().lock());
+        if piped_tests.is_empty() {
+            eprintln!("{}", "no tests were read from stdin".red());
+            exit(EXIT_INVALID_ARGUMENTS);
+        }
+        tests.splice(stdin_position..stdin_position, piped_tests);
+    }
+    if !tests.is_empty() {
+        if matches.is_present("shuffle") {
+            let seed = match matches.value_of("shuffle") {
+                Some(seed) => seed.parse().unwrap_or_else(|_| {
+                    eprintln!("{}{}", "invalid shuffle seed: ".red(), seed);
+                    exit(EXIT_INVALID_ARGUMENTS);
+                }),
+                None => shuffle::random_seed(),
+            };
+            shuffle::shuffle(&mut tests, seed);
+            shuffle_seed = Some(seed);
+        }
+        if let Some(order) = matches.value_of("order").and_then(history::parse_order) {
+            match &config.history_file {
+                Some(history_file) => history::order_tests(&mut tests, &history::load_history(std::path::Path::new(history_file.as_str())), order),
+                None => eprintln!("{}", "--order needs a history_file to know durations and failures, running in the given order".yellow()),
+            }
+        }
+        run_tests = tests.join(" ");
+    }
+    if matches.is_present("shuffle") && shuffle_seed.is_none() {
+        eprintln!("{}", "--shuffle only reorders tests given on the command line, running in the usual order".yellow());
+    }
+    if matches.is_present("order") && tests.is_empty() {
+        eprintln!("{}", "--order only reorders tests given on the command line, running in the usual order".yellow());
+    }
+
+    let mut selection = match matches.value_of("filter").and_then(runner::unreal_filter) {
+        Some(filter) => runner::TestSelection::Filter(filter),
+        None => runner::TestSelection::Tests(run_tests),
+    };
+
+    let test_runner = match runner::runner(&config) {
+        Ok(test_runner) => test_runner,
+        Err(error) => {
+            eprintln!("{}{}", "invalid [runner] configuration: ".red(), error);
+            exit(EXIT_INVALID_CONFIG);
+        }
+    };
+    if let runner::TestSelection::Filter(_) = selection {
+        if !test_runner.supports_filters() {
+            eprintln!("{}{}", "--filter isn't supported by the runner: ".red(), test_runner.name());
+            exit(EXIT_INVALID_ARGUMENTS);
+        }
+    }
+
+    // --isolate launches one engine per named test, --jobs spreads the named tests over that many engines
+    let jobs: Option<usize> = matches.value_of("jobs").map(|jobs| jobs.parse().ok().filter(|jobs| *jobs > 0).unwrap_or_else(|| {
+        eprintln!("{}{}", "invalid job count: ".red(), jobs);
+        exit(EXIT_INVALID_ARGUMENTS);
+    }));
+    let isolate = matches.is_present("isolate");
+    let mut instance_selections = instances::instance_selections(&selection, isolate, jobs, config.remote.is_some()).unwrap_or_else(|error| {
+        eprintln!("{}", error.as_str().red());
+        exit(EXIT_INVALID_ARGUMENTS);
+    });
+    let mut max_parallel_engines = jobs.unwrap_or(config.max_parallel_engines);
+    // a test list too long for one engine command line is run over several launches, one after another
+    match instance_selections.take() {
+        Some(selections) => {
+            instance_selections = Some(selections.into_iter().flat_map(|launched| instances::split_oversized(&config, &launched).unwrap_or_else(|| vec![launched])).collect());
+        }
+        None => {
+            if let Some(split) = instances::split_oversized(&config, &selection) {
+                if config.remote.is_some() {
+                    eprintln!("{}", format!("the test list is too long for one engine command line ({} characters at most) and [remote] runs a single engine, run fewer tests or a --filter", instances::MAX_EXEC_CMDS_LENGTH).red());
+                    exit(EXIT_INVALID_ARGUMENTS);
+                }
+                eprintln!("{}", format!("the test list is too long for one engine command line, running it in {} launches", split.len()).yellow());
+                instance_selections = Some(split);
+                max_parallel_engines = 1;
+            }
+        }
+    }
+
+    // screenshot comparisons can't render under -NullRHI, they'd fail for no fault of their own
+    let launched_selections = instance_selections.as_deref().unwrap_or(std::slice::from_ref(&selection));
+    let null_rhi_selections: Vec<&runner::TestSelection> = launched_selections.iter()
+        .filter(|launched| display::runs_null_rhi(&test_runner.command(&config, launched, &runner::Instance::primary(&config))))
+        .collect();
+    if !null_rhi_selections.is_empty() {
+        let mut screenshot_tests = config.screenshot_tests.clone();
+        if let Some(test_pass) = report::find_report_file(local_reports_directory(&config).display().to_string().as_str()).and_then(|report_file| report::read_test_pass(&report_file).ok()) {
+            screenshot_tests.extend(display::comparison_tests(&test_pass));
+        }
+        let mut null_rhi_screenshot_tests: Vec<String> = null_rhi_selections.iter().flat_map(|launched| display::selected_screenshot_tests(launched, &screenshot_tests)).collect();
+        null_rhi_screenshot_tests.sort();
+        null_rhi_screenshot_tests.dedup();
+        if !null_rhi_screenshot_tests.is_empty() {
+            let message = format!("screenshot tests run with -NullRHI and can't render: {} (see [display])", null_rhi_screenshot_tests.join(", "));
+            if config.fail_null_rhi_screenshots {
+                eprintln!("{}", message.red());
+                exit(EXIT_INVALID_CONFIG);
+            }
+            eprintln!("{}", message.yellow());
+        }
+    }
+
+    if let Some(build_config) = &config.build {
+        if !matches.is_present("skip-build") && !build::run_build(build_config, config.path_to_project.as_str()) {
+            eprintln!("{}", "build failed, not running tests".red());
+            exit(EXIT_BUILD_FAILED);
+        }
+    }
+
+    let changelist = config.p4.as_ref().and_then(vcs::current_changelist);
+    if let Some(changelist) = &changelist {
+        if !plain {
+            eprintln!("changelist: {}", changelist);
+        }
+    }
+
+    let git = vcs::current_git_info(project_directory(&config));
+    if let Some(git_info) = &git {
+        if !plain {
+            eprintln!("git: {}", git_info);
+        }
+    }
+
+    // after the build, the binaries it wrote are part of what a cached pass was run with
+    let cache_inputs = matches.is_present("cache").then(|| cache::inputs_hash(&[
+        changelist.clone().unwrap_or_default(),
+        git.as_ref().map_or_else(String::new, |git_info| format!("{} {}", git_info.commit, git_info.dirty)),
+        cache::fingerprint(std::path::Path::new(config.path_to_unrealengine.as_str())),
+        cache::fingerprint(&project_directory(&config).join("Binaries")),
+        expanded_config.to_string(),
+    ]));
+    let mut cached_tests = Vec::new();
+    if let Some(cache_inputs) = &cache_inputs {
+        if let runner::TestSelection::Tests(run_tests) = &selection {
+            let (to_run, cached) = cache::split_cached(instances::individual_tests(run_tests), &cache::load_cache(&cache_file(&config)), cache_inputs.as_str());
+            if !cached.is_empty() && to_run.is_empty() {
+                let lang = selected_lang(&matches);
+                for cached_test in cached.iter() {
+                    println!("{}", lang::format(lang, lang::Message::CachedPass, &[cached_test]));
+                }
+                eprintln!("{}", "every test passed with these inputs before, nothing to run".green());
+                exit(0);
+            }
+            selection = runner::TestSelection::Tests(to_run.join(" "));
+            instance_selections = instance_selections.map(|selections| selections.into_iter().filter_map(|launched| cache::without_cached(launched, &cached)).collect());
+            cached_tests = cached;
+        }
+    }
+
+    let mut launches = Vec::new();
+    if let Some(warmup_tests) = &config.warmup_tests {
+        if !matches.is_present("skip-warmup") {
+            // only the warm caches matter, a failing warmup still leaves them warmer than before
+            let warmup_started_at = chrono::Utc::now();
+            let warmup_exit_code = runner::run_warmup(test_runner.as_ref(), &config, warmup_tests.as_str()).unwrap_or_else(|error| {
+                eprintln!("{}", error.as_str().red());
+                exit(EXIT_ENGINE_LAUNCH_FAILED);
+            });
+            launches.push(telemetry::EngineLaunch { name: "warmup".to_owned(), started_at: warmup_started_at, finished_at: chrono::Utc::now(), exit_code: warmup_exit_code.code() });
+            if !warmup_exit_code.success() {
+                eprintln!("{}", format!("warmup exited with {}, continuing with the measured run", warmup_exit_code).yellow());
+            }
+        }
+    }
+
+    let engine_version = match engine::read_build_version(config.path_to_unrealengine.as_str()) {
+        Ok(engine_version) => {
+            manifest::update(|manifest| manifest.engine_version = Some(engine_version.to_string()));
+            if !plain {
+                eprintln!("engine: {}", engine_version);
+            }
+            for warning in engine::compatibility_warnings(&engine_version, &config) {
+                eprintln!("{}", warning.yellow());
+            }
+            Some(engine_version)
+        }
+        Err(error) => {
+            eprintln!("{}{}", "failed to detect the engine version: ".yellow(), error);
+            None
+        }
+    };
+    let mut run_metadata = RunMetadata { changelist, git, started_at: Some(started_at), engine_version, shuffle_seed, session_name, meta, crashed: false, callstack: Vec::new(), crash_context: None, warmup: None, requested_tests: Vec::new(), launches, cached_tests, cache_inputs };
+
+    if let runner::TestSelection::Tests(run_tests) = &selection {
+        run_metadata.requested_tests = instances::individual_tests(run_tests);
+    }
+
+    let report_file = match instance_selections {
+        Some(selections) => {
+            let stagger = std::time::Duration::from_secs_f32(config.engine_start_stagger.max(0.0));
+            let launched = instances::run_instances(test_runner.as_ref(), &config, &selections, max_parallel_engines, stagger).unwrap_or_else(|error| {
+                eprintln!("{}", error.as_str().red());
+                exit(EXIT_ENGINE_LAUNCH_FAILED);
+            });
+            run_metadata.launches.extend(launched.iter().map(|(_, _, launch)| launch.clone()));
+            let results: Vec<(runner::Instance, std::process::ExitStatus)> = launched.into_iter().map(|(instance, exit_status, _)| (instance, exit_status)).collect();
+            for (instance, exit_status) in results.iter() {
+                if engine_crashed(*exit_status, &config) {
+                    // the first crashed instance's callstack stands in for the run
+                    if !run_metadata.crashed {
+                        run_metadata.callstack = crash_callstack(&config, instance.log_name.as_str());
+                    }
+                    run_metadata.crashed = true;
+                }
+            }
+            // instances warm up side by side, the slowest one held the run up
+            run_metadata.warmup = results.iter()
+                .filter_map(|(instance, _)| warmup::read_warmup(&engine_log_path(&config).with_file_name(instance.log_name.as_str())))
+                .max_by(|first, second| first.seconds.total_cmp(&second.seconds));
+            match instances::merge_reports(&results, &config, started_at) {
+                Ok(merged_file) => merged_file,
+                Err(error) => {
+                    eprintln!("{}{}", "failed to merge instance reports: ".red(), error);
+                    exit(EXIT_REPORT_NOT_FOUND);
+                }
+            }
+        }
+        None => {
+            let engine_started_at = chrono::Utc::now();
+            let test_exit_code = runner::run(test_runner.as_ref(), &config, &selection).unwrap_or_else(|error| {
+                eprintln!("{}", error.as_str().red());
+                exit(EXIT_ENGINE_LAUNCH_FAILED);
+            });
+            run_metadata.launches.push(telemetry::EngineLaunch { name: "engine".to_owned(), started_at: engine_started_at, finished_at: chrono::Utc::now(), exit_code: test_exit_code.code() });
+            run_metadata.crashed = engine_crashed(test_exit_code, &config);
+            if run_metadata.crashed {
+                run_metadata.callstack = crash_callstack(&config, runner::Instance::primary(&config).log_name.as_str());
+            }
+            run_metadata.warmup = warmup::read_warmup(&engine_log_path(&config));
+
+            let report_file = match &config.remote {
+                Some(remote_config) => match remote::fetch_reports(remote_config, config.path_to_reports.as_str()) {
+                    Ok(local_reports) => report::find_report_file(local_reports.to_str().expect("invalid local report path")).unwrap_or_else(|| {
+                        eprintln!("{}{}", "no index.json found under ".red(), local_reports.display());
+                        exit(EXIT_REPORT_NOT_FOUND);
+                    }),
+                    Err(error) => {
+                        eprintln!("{}{}", "failed to fetch the remote report: ".red(), error);
+                        exit(EXIT_REPORT_NOT_FOUND);
+                    }
+                },
+                None => latest_report_file(&matches, &config),
+            };
+            // a failing exit code is only worth ignoring if the engine got as far as writing this run's report
+            if !test_exit_code.success() && !run_metadata.crashed && !report::written_since(&report_file, started_at) {
+                eprintln!("{}{}", "no report was written by this run, newest is ".red(), report_file.display());
+                exit(EXIT_REPORT_NOT_FOUND);
+            }
+            report_file
+        }
+    };
+    // after a crash partial (or even stale) results still beat nothing, the run exits with EXIT_ENGINE_CRASHED afterwards
+    if run_metadata.crashed {
+        eprintln!("{}", "the engine crashed, reading whatever report it left behind".yellow());
+        if let Some(context_file) = crash::find_crash_context(project_directory(&config), started_at) {
+            match crash::read_crash_context(&context_file) {
+                Ok((crash_context, callstack)) => {
+                    eprintln!("crash report: {}", crash_context.folder);
+                    // the crash reporter had the pdbs, its stack is usually better resolved than the log's
+                    let resolved = |frames: &[crash::StackFrame]| frames.iter().filter(|frame| frame.function.is_some()).count();
+                    if resolved(&callstack) > resolved(&run_metadata.callstack) {
+                        run_metadata.callstack = crash::clean(callstack);
+                    }
+                    run_metadata.crash_context = Some(crash_context);
+                }
+                Err(error) => eprintln!("{}{}", "failed to read the crash context: ".yellow(), error),
+            }
+        }
+    }
+    eprintln!("reading report: {}", report_file.display());
+
+    process_report(&report_file, &config, &matches, &run_metadata, true);
+}
+
+// prints why the engine exited unsuccessfully, exit codes listed in acceptable_exit_codes don't count as a crash
+fn engine_crashed(test_exit_code: std::process::ExitStatus, config: &TestConfiguration) -> bool {
+    if test_exit_code.success() {
+        return false;
+    }
+    match test_exit_code.code() {
+        Some(code) if config.acceptable_exit_codes.contains(&code) => {
+            eprintln!("{}{}", "exited with accepted status code: ".yellow(), code);
+            false
+        }
+        Some(code) => {
+            eprintln!("{}{}", "exited with status code: ".red(), code);
+            true
+        }
+        None => {
+            eprintln!("{}", "process terminated by signal".red());
+            true
+        }
+    }
+}
+
+fn load_file(file_path: &str) -> String {
+    let mut file = File::open(file_path).expect("failed to open file");
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).expect("failed to read file");
+    utf_from_bytes(&buffer)
+}
+
+// --cache's passes, next to the reports like the run manifest
+fn cache_file(config: &TestConfiguration) -> std::path::PathBuf {
+    local_reports_directory(config).join("runtests-cache.json")
+}
+
+// every exit after the config is loaded: releases the run's lock and writes the run manifest
+fn exit(exit_code: i32) -> ! {
+    lock::release();
+    manifest::exit(exit_code)
+}
+
+// parse and run leave a run-manifest.json next to the reports
+fn begin_manifest(config_file_path: &str, expanded_config: &toml::Value, config: &TestConfiguration) {
+    manifest::begin(local_reports_directory(config).join("run-manifest.json"), manifest::RunManifest {
+        tool_version: env!("CARGO_PKG_VERSION"),
+        command_line: std::env::args().collect(),
+        config_file: config_file_path.to_owned(),
+        config: serde_json::to_value(expanded_config).ok(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        ..manifest::RunManifest::default()
+    });
+}
+
+// --meta key=value pairs, a repeated key keeps the last value
+fn run_meta(matches: &ArgMatches) -> Result<BTreeMap<String, String>, String> {
+    let mut meta = BTreeMap::new();
+    for pair in matches.values_of("meta").into_iter().flatten() {
+        match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                meta.insert(key.trim().to_owned(), value.to_owned());
+            }
+            _ => return Err(format!("{} (expected KEY=VALUE)", pair)),
+        }
+    }
+    Ok(meta)
+}
+
+// every --format, console when none is given; --html is shorthand for --format html=FILE
+fn format_specs(matches: &ArgMatches) -> Vec<formats::FormatSpec> {
+    let mut format_specs: Vec<formats::FormatSpec> = match matches.values_of("format") {
+        Some(values) => values.map(formats::parse_format_spec).collect(),
+        None => vec![formats::parse_format_spec("console")],
+    };
+    if let Some(html_path) = matches.value_of("html") {
+        format_specs.push(formats::FormatSpec { name: "html".to_owned(), destination: Some(std::path::PathBuf::from(html_path)) });
+    }
+    format_specs
+}
+
+// --report-file if given, otherwise the newest index.json under path_to_reports
+fn latest_report_file(matches: &ArgMatches, config: &TestConfiguration) -> std::path::PathBuf {
+    match matches.value_of("report-file") {
+        Some(report_file) => std::path::PathBuf::from(report_file),
+        None => match report::find_report_file(config.path_to_reports.as_str()) {
+            Some(report_file) => report_file,
+            None => {
+                eprintln!("{}{}", "no index.json found under ".red(), config.path_to_reports);
+                exit(EXIT_REPORT_NOT_FOUND);
+            }
+        },
+    }
+}
+
+// --lang, english by default
+fn selected_lang(matches: &ArgMatches) -> lang::Lang {
+    matches.value_of("lang").and_then(lang::parse_lang).unwrap_or_default()
+}
+
+// where the engine writes -log=runtests.log
+fn engine_log_path(config: &TestConfiguration) -> std::path::PathBuf {
+    project_directory(config).join("Saved").join("Logs").join("runtests.log")
+}
+
+// where a run writes, with [remote] those are on the remote machine and can't be checked from here
+pub(crate) fn disk_space_directories(config: &TestConfiguration) -> Vec<std::path::PathBuf> {
+    if config.remote.is_some() {
+        return Vec::new();
+    }
+    let log_directory = engine_log_path(config).parent().map(std::path::Path::to_path_buf).unwrap_or_default();
+    vec![std::path::PathBuf::from(config.path_to_reports.as_str()), log_directory]
+}
+
+// the cleaned callstack of the crash in this log, symbolized first if a [symbolizer] is configured
+fn crash_callstack(config: &TestConfiguration, log_name: &str) -> Vec<crash::StackFrame> {
+    let mut frames = crash::read_log_callstack(&engine_log_path(config).with_file_name(log_name));
+    if let Some(symbolizer) = &config.symbolizer {
+        crash::symbolize(&mut frames, symbolizer);
+    }
+    crash::clean(frames)
+}
+
+// record_history is false when parsing an existing report so old reports don't get recorded twice
+fn process_report(report_file: &std::path::Path, config: &TestConfiguration, matches: &ArgMatches, run_metadata: &RunMetadata, record_history: bool) {
+    let test_pass = load_test_pass(report_file, config, matches.is_present("strict-parse"));
+    manifest::update(|manifest| {
+        manifest.report_file = Some(report_file.display().to_string());
+        manifest.tests = Some(manifest::TestCounts { succeeded: test_pass.succeeded, failed: test_pass.failed, not_run: test_pass.not_run });
+    });
+    for warning in engine::report_warnings(&test_pass, config) {
+        eprintln!("{}", warning.yellow());
+    }
+    // an empty report isn't a pass, the selection was misspelled, filtered away or its module didn't load;
+    // after a crash the crash is the more useful thing to report
+    if test_pass.tests.is_empty() && !run_metadata.crashed {
+        print_no_tests(config, run_metadata);
+        exit(EXIT_NO_TESTS);
+    }
+    if let Some(cache_inputs) = &run_metadata.cache_inputs {
+        let mut result_cache = cache::load_cache(&cache_file(config));
+        cache::record(&mut result_cache, &test_pass, cache_inputs.as_str());
+        if let Err(error) = cache::save_cache(&cache_file(config), &result_cache) {
+            eprintln!("{}{}", "failed to save the result cache: ".yellow(), error);
+        }
+    }
+    let report_directory = report_file.parent().unwrap_or_else(|| std::path::Path::new("."));
+    let comparison_export = comparisons::read_comparison_export(&test_pass, report_directory, config).unwrap_or_else(|error| {
+        eprintln!("{}{}", "the screenshot comparisons were exported but can't be read: ".yellow(), error);
+        None
+    });
+
+    let context = formats::ReportContext {
+        test_pass: &test_pass,
+        config,
+        run_metadata,
+        report_directory,
+        theme: theme::Theme::from_config(config.theme.as_ref(), selected_lang(matches)),
+        zone: datetime::parse_display_zone(config.timezone.as_deref()).expect("timezone is checked when the config is loaded"),
+        lang: selected_lang(matches),
+        csv_profiles: if matches.is_present("csv-profiles") { csvprofile::summarize_profiles(&test_pass, report_directory) } else { Vec::new() },
+        comparison_export,
+        log_excerpts: if matches.is_present("log-excerpts") {
+            logexcerpts::find_excerpts(&test_pass, &logexcerpts::log_files(&engine_log_path(config)), config.log_excerpt_lines, &config.ignore_regexes)
+        } else {
+            Vec::new()
+        },
+        options: formats::RenderOptions {
+            plain: matches.is_present("plain"),
+            durations: matches.is_present("durations"),
+            timestamps: matches.is_present("timestamps"),
+            timeline: matches.is_present("timeline"),
+            warnings_by_file: matches.is_present("warnings-by-file"),
+            show_ignored: matches.is_present("show-ignored"),
+            // with a details file the console only gets the status lines, the entries go to the file
+            summary_only: matches.value_of("details-file").is_some(),
+            width: console_width(matches),
+        },
+    };
+    for format_spec in format_specs(matches) {
+        match (formats::write_output(&format_spec, &context), &format_spec.destination) {
+            (Ok(()), Some(destination)) => {
+                eprintln!("wrote {} report to {}", format_spec.name, destination.display());
+                manifest::update(|manifest| manifest.exports.push(manifest::Export { kind: format_spec.name.clone(), path: destination.display().to_string() }));
+            }
+            (Ok(()), None) => {}
+            (Err(error), _) => eprintln!("{}{}", format!("failed to write {} report: ", format_spec.name).red(), error),
+        }
+    }
+
+    if let Some(gitlab_config) = config.gitlab.as_ref().filter(|gitlab_config| gitlab_config.merge_request_comment) {
+        let mut summary = Vec::new();
+        let posted = match formats::formatter("markdown").expect("markdown formatter").write(&context, &mut summary) {
+            Ok(()) => gitlab::post_merge_request_comment(gitlab_config, String::from_utf8_lossy(&summary).as_ref(), run_metadata.session_name.as_deref()),
+            Err(error) => Err(error.to_string()),
+        };
+        match posted {
+            Ok(action) => eprintln!("{} the merge request comment", action),
+            Err(error) => eprintln!("{}{}", "failed to comment on the merge request: ".yellow(), error),
+        }
+    }
+
+    if let Some(jenkins_config) = &config.jenkins {
+        let description = jenkins::build_description(&test_pass, run_metadata, context.lang);
+        match jenkins::set_build_description(jenkins_config, description.as_str()) {
+            Ok(()) => eprintln!("set the jenkins build description"),
+            Err(error) => eprintln!("{}{}", "failed to set the jenkins build description: ".yellow(), error),
+        }
+        if let Some(checks_config) = &jenkins_config.checks {
+            match jenkins::publish_check_run(jenkins_config, checks_config, &test_pass, run_metadata, description.as_str()) {
+                Ok(()) => eprintln!("published the {} check run", checks_config.name),
+                Err(error) => eprintln!("{}{}", "failed to publish the check run: ".yellow(), error),
+            }
+        }
+    }
+
+    if let Some(discord_config) = config.discord.as_ref().filter(|discord_config| discord::should_notify(discord_config, &test_pass, run_metadata)) {
+        let message = discord::embed_message(discord_config, &test_pass, run_metadata, &config.ignore_regexes, context.lang);
+        if let Err(error) = discord::post_message(discord_config, &message) {
+            eprintln!("{}{}", "failed to post to discord: ".yellow(), error);
+        }
+    }
+
+    if let Some(telemetry_config) = &config.telemetry {
+        if let Err(error) = telemetry::export_trace(telemetry_config, &telemetry::trace(telemetry_config, &test_pass, run_metadata)) {
+            eprintln!("{}{}", "failed to export the trace: ".yellow(), error);
+        }
+    }
+
+    if let Some(details_file) = matches.value_of("details-file") {
+        match details::write_details_file(std::path::Path::new(details_file), &test_pass, &config.ignore_regexes, &context.zone) {
+            Ok(()) => {
+                eprintln!("wrote details to {}", details_file);
+                manifest::update(|manifest| manifest.exports.push(manifest::Export { kind: "details".to_owned(), path: details_file.to_owned() }));
+            }
+            Err(error) => eprintln!("{}{}", "failed to write details file: ".red(), error),
+        }
+    }
+
+    if let Some(split_logs) = matches.value_of("split-logs") {
+        match details::write_split_logs(std::path::Path::new(split_logs), &test_pass, &config.ignore_regexes) {
+            Ok(written) => {
+                eprintln!("wrote {} failing test logs to {}", written.len(), split_logs);
+                manifest::update(|manifest| manifest.exports.extend(written.iter().map(|path| manifest::Export { kind: "split-log".to_owned(), path: path.display().to_string() })));
+            }
+            Err(error) => eprintln!("{}{}", "failed to write the split logs: ".red(), error),
+        }
+    }
+
+    if let Some(editor) = matches.value_of("open-in") {
+        editor::open_locations(editor, &failing_locations(&test_pass, &config.ignore_regexes));
+    }
+
+    let mut exit_code = 0;
+    let theme = theme::Theme::from_config(config.theme.as_ref(), context.lang);
+    if let Some(expected_min_tests) = config.expected_min_tests {
+        let ran = test_pass.tests.iter().filter(|test| matches!(test.state, TestResult::Success | TestResult::Fail)).count();
+        if ran < expected_min_tests {
+            eprintln!("{}", theme.fail.paint(format!("only {} tests ran, expected at least {} (expected_min_tests), tests have gone missing", ran, expected_min_tests).as_str()));
+            exit_code = EXIT_TOO_FEW_TESTS;
+        }
+    }
+    let history = config.history_file.as_ref().map_or_else(Vec::new, |history_file| history::load_history(std::path::Path::new(history_file.as_str())));
+    // an older report parsed again would be compared with runs that came after it
+    if record_history {
+        if let Some(changes) = history::test_set_changes(&history, &test_pass, run_metadata.session_name.as_deref()) {
+            history::print_test_set_changes(&changes, &theme);
+        }
+    }
+    // before this run is appended, new failures are the ones the previous run didn't have
+    if let Some(plugins_config) = &config.plugins {
+        let mut run_json = Vec::new();
+        let run = match formats::formatter("json").expect("json formatter").write(&context, &mut run_json) {
+            Ok(()) => serde_json::from_slice(&run_json).unwrap_or_default(),
+            Err(_) => serde_json::Value::Null,
+        };
+        let new_failures = history::new_failures(&history, &test_pass, run_metadata.session_name.as_deref());
+        let mut failures = plugins::notify(&plugins_config.run_finished, "run_finished", &plugins::message("run_finished", &new_failures, run.clone()));
+        if !new_failures.is_empty() {
+            failures.extend(plugins::notify(&plugins_config.new_failure, "new_failure", &plugins::message("new_failure", &new_failures, run)));
+        }
+        for failure in failures {
+            eprintln!("{}{}", "plugin failed: ".yellow(), failure);
+        }
+    }
+    if let Some(regression_config) = config.duration_regressions.as_ref().filter(|_| config.history_file.is_some()) {
+        let regressions = history::duration_regressions(&history, &test_pass, regression_config);
+        history::print_duration_regressions(&regressions, &theme);
+        if regression_config.fail_run && !regressions.is_empty() {
+            exit_code = EXIT_DURATION_REGRESSION;
+        }
+    }
+    let metrics = match &config.perf {
+        Some(perf_config) => {
+            let metrics = perf::extract_metrics(&test_pass, report_directory, perf_config);
+            let regressions = perf::perf_regressions(&metrics, &history, perf_config);
+            perf::print_perf_regressions(&regressions, &theme);
+            if perf_config.fail_run && !regressions.is_empty() {
+                exit_code = EXIT_PERF_REGRESSION;
+            }
+            metrics
+        }
+        None => Vec::new(),
+    };
+    if let Some(history_file) = config.history_file.as_ref().filter(|_| record_history) {
+        let run = history::history_run(&test_pass, run_metadata, metrics);
+        if let Err(error) = history::append_run(std::path::Path::new(history_file.as_str()), &run) {
+            eprintln!("{}{}", "failed to record history: ".red(), error);
+        }
+    }
+
+    // after the history is recorded so this run counts towards keep_runs
+    if let Some(retention_config) = config.retention.as_ref().filter(|_| record_history) {
+        prune(config, retention_config, run_metadata.started_at, false);
+    }
+
+    if let Some(archive_path) = matches.value_of("archive") {
+        let manifest = archive::ArchiveManifest {
+            report_file: report_file.display().to_string(),
+            changelist: run_metadata.changelist.clone(),
+            commit: run_metadata.git.as_ref().map(|git_info| git_info.commit.clone()),
+            engine_version: run_metadata.engine_version.as_ref().map(|engine_version| engine_version.to_string()),
+            session_name: run_metadata.session_name.clone(),
+            meta: run_metadata.meta.clone(),
+            succeeded: test_pass.succeeded,
+            failed: test_pass.failed,
+            not_run: test_pass.not_run,
+            files: Vec::new(),
+        };
+        let engine_log = engine_log_path(config);
+        match archive::write_archive(std::path::Path::new(archive_path), report_directory, Some(engine_log.as_path()), context.comparison_export.as_ref(), manifest) {
+            Ok(()) => {
+                eprintln!("archived report to {}", archive_path);
+                manifest::update(|manifest| manifest.exports.push(manifest::Export { kind: "archive".to_owned(), path: archive_path.to_owned() }));
+            }
+            Err(error) => {
+                eprintln!("{}{}", "failed to archive report: ".red(), error);
+                exit(EXIT_ARCHIVE_FAILED);
+            }
+        }
+    }
+
+    // the engine exited with tests still running, what's missing from the report never got a result
+    if analysis::in_process_count(&test_pass) > 0 {
+        exit_code = EXIT_INCOMPLETE_RUN;
+    }
+    if run_metadata.crashed {
+        exit_code = EXIT_ENGINE_CRASHED;
+    }
+    // last and above every other outcome, a broken critical test is what the run has to say
+    let critical_failures = critical::critical_failures(&test_pass, &config.critical_tests);
+    if !critical_failures.is_empty() {
+        critical::print_banner(&critical_failures, &theme);
+        exit_code = EXIT_CRITICAL_TESTS_FAILED;
+    }
+    lock::release();
+    manifest::finish(exit_code);
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+// --width 0 turns wrapping off, --plain output doesn't depend on the terminal unless --width asks for it
+fn console_width(matches: &ArgMatches) -> Option<usize> {
+    match matches.value_of("width") {
+        Some(width) => match width.parse() {
+            Ok(0) => None,
+            Ok(width) => Some(width),
+            Err(_) => {
+                eprintln!("{}{}", "invalid width: ".red(), width);
+                exit(EXIT_INVALID_ARGUMENTS);
+            }
+        },
+        None if matches.is_present("plain") => None,
+        None => width::terminal_width(),
+    }
+}
+
+// each run is a process of its own, started like runtests --config FILE [schedule] args, so one crashing doesn't
+// end the schedule; a run that comes around while the previous one is still going is skipped
+fn run_schedule(config_file_path: &str, config: &TestConfiguration) {
+    let schedule_config = config.schedule.as_ref().unwrap_or_else(|| {
+        eprintln!("{}", "schedule needs a [schedule] section".red());
+        std::process::exit(EXIT_INVALID_CONFIG);
+    });
+    let cron = schedule::CronSchedule::parse(schedule_config.cron.as_str()).unwrap_or_else(|error| {
+        eprintln!("{}{}", "invalid [schedule] cron: ".red(), error);
+        std::process::exit(EXIT_INVALID_CONFIG);
+    });
+    let runtests = std::env::current_exe().expect("failed to find runtests");
+    let mut running: Option<(std::process::Child, chrono::DateTime<chrono::Utc>)> = None;
+    loop {
+        let next = cron.next_after(chrono::Local::now().naive_local()).unwrap_or_else(|| {
+            eprintln!("{}{}", "[schedule] cron never comes around: ".red(), schedule_config.cron);
+            std::process::exit(EXIT_INVALID_CONFIG);
+        });
+        eprintln!("next run at {}", next.format("%Y-%m-%d %H:%M"));
+        while chrono::Local::now().naive_local() < next {
+            if let Some((child, started_at)) = running.as_mut() {
+                if let Ok(Some(status)) = child.try_wait() {
+                    finish_scheduled_run(config, schedule_config, status, *started_at);
+                    running = None;
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_secs(5));
+        }
+        if let Some((_, started_at)) = &running {
+            eprintln!("{}", format!("skipping the run due at {}, the one started at {} is still going", next.format("%H:%M"), started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")).yellow());
+            continue;
+        }
+        if let Some(on_start) = &schedule_config.on_start {
+            run_hook(on_start.as_str());
+        }
+        match std::process::Command::new(&runtests).args(["--config", config_file_path]).args(&schedule_config.args).spawn() {
+            Ok(child) => running = Some((child, chrono::Utc::now())),
+            Err(error) => eprintln!("{}{}", "failed to start the scheduled run: ".red(), error),
+        }
+    }
+}
+
+// the run's manifest tells failed tests apart from a clean run, its exit code alone doesn't
+fn finish_scheduled_run(config: &TestConfiguration, schedule_config: &schedule::ScheduleConfiguration, status: std::process::ExitStatus, started_at: chrono::DateTime<chrono::Utc>) {
+    let exit_code = status.code().unwrap_or(-1);
+    let manifest_file = local_reports_directory(config).join("run-manifest.json");
+    let result = std::fs::read_to_string(&manifest_file).ok()
+        .filter(|_| report::written_since(&manifest_file, started_at))
+        .and_then(|manifest| serde_json::from_str::<serde_json::Value>(manifest.as_str()).ok())
+        .and_then(|manifest| manifest["exit_classification"].as_str().map(str::to_owned))
+        .unwrap_or_else(|| manifest::exit_classification(exit_code, false).to_owned());
+    eprintln!("scheduled run finished: {} ({})", result, exit_code);
+    if let Some(on_finish) = &schedule_config.on_finish {
+        run_hook(on_finish.replace("{exit_code}", exit_code.to_string().as_str()).replace("{result}", result.as_str()).as_str());
+    }
+}
+
+fn run_hook(command_line: &str) {
+    match runner::shell_command(command_line).stdout(std::io::stderr()).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("{}", format!("{} exited with {}", command_line, status).yellow()),
+        Err(error) => eprintln!("{}{}: {}", "failed to run ".yellow(), command_line, error),
+    }
+}
+
+// every step is a run of its own, started like runtests --config FILE TEST, so it builds, locks and reports as usual
+fn bisect(config_file_path: &str, config: &TestConfiguration, bisect_matches: &ArgMatches) {
+    let test = bisect_matches.value_of("test").expect("failed to get test");
+    let good = bisect_matches.value_of("good").expect("failed to get good");
+    let bad = bisect_matches.value_of("bad").expect("failed to get bad");
+    let sync_template = bisect_matches.value_of("sync").expect("failed to get sync");
+    if !sync_template.contains("{change}") {
+        eprintln!("{}", "--sync needs {change} where the changelist or commit goes".red());
+        std::process::exit(EXIT_INVALID_ARGUMENTS);
+    }
+    let changes = match &config.p4 {
+        Some(p4_config) => vcs::changelists_between(p4_config, good, bad),
+        None => vcs::commits_between(project_directory(config), good, bad),
+    };
+    let changes = changes.unwrap_or_else(|error| {
+        eprintln!("{}{}", "failed to list the changes to bisect: ".red(), error);
+        std::process::exit(EXIT_INVALID_ARGUMENTS);
+    });
+    if changes.is_empty() {
+        eprintln!("{}", format!("no changes between {} and {}", good, bad).red());
+        std::process::exit(EXIT_INVALID_ARGUMENTS);
+    }
+    let runtests = std::env::current_exe().expect("failed to find runtests");
+    let mut bisection = bisect::Bisection::new(changes);
+    while let Some(index) = bisection.next_change() {
+        let change = bisection.changes[index].clone();
+        eprintln!("{}", format!("{} changes left, trying {}", bisection.remaining(), change).bold());
+        let outcome = match bisect::sync(sync_template, change.as_str()) {
+            Err(error) => {
+                eprintln!("{}{}", "failed to sync: ".red(), error);
+                bisect::Outcome::Inconclusive
+            }
+            Ok(()) => {
+                let started_at = chrono::Utc::now();
+                let mut run = std::process::Command::new(&runtests);
+                run.args(["--config", config_file_path, test]).stdout(std::io::stderr());
+                if bisect_matches.is_present("skip-build") {
+                    run.arg("--skip-build");
+                }
+                // the regression checks don't decide whether the test passed, the build and the run not starting do
+                let ran = run.status().ok().and_then(|status| status.code()).is_some_and(|code| {
+                    matches!(code, 0 | EXIT_DURATION_REGRESSION | EXIT_ENGINE_CRASHED | EXIT_PERF_REGRESSION | EXIT_TOO_FEW_TESTS | EXIT_INCOMPLETE_RUN)
+                });
+                match report::find_report_file(local_reports_directory(config).display().to_string().as_str()) {
+                    Some(report_file) if ran && report::written_since(&report_file, started_at) => match report::read_test_pass(&report_file) {
+                        Ok(test_pass) => bisect::test_outcome(&test_pass, test),
+                        Err(_) => bisect::Outcome::Inconclusive,
+                    },
+                    _ => bisect::Outcome::Inconclusive,
+                }
+            }
+        };
+        match outcome {
+            bisect::Outcome::Pass => eprintln!("{}", format!("{} passes at {}", test, change).green()),
+            bisect::Outcome::Fail => eprintln!("{}", format!("{} fails at {}", test, change).red()),
+            bisect::Outcome::Inconclusive => eprintln!("{}", format!("couldn't tell at {}, skipping it", change).yellow()),
+        }
+        bisection.record(index, outcome);
+    }
+
+    println!("{}", format!("{} first fails at {}", test, bisection.culprit()).red().bold());
+    let suspects = bisection.suspects();
+    if !suspects.is_empty() {
+        println!("{}", format!("it may have broken at {} instead, those couldn't be tested", suspects.join(", ")).yellow());
+    }
+    if let Err(error) = bisect::sync(sync_template, bad) {
+        eprintln!("{}{}", "failed to sync back to --bad: ".yellow(), error);
+    }
+}
+
+fn prune(config: &TestConfiguration, retention_config: &retention::RetentionConfiguration, run_started_at: Option<chrono::DateTime<chrono::Utc>>, dry_run: bool) {
+    let verb = if dry_run { "would delete" } else { "deleted" };
+    let now = chrono::Utc::now();
+    for directory in retention::prune_reports(local_reports_directory(config).to_str().expect("invalid report path"), retention_config, now, run_started_at, dry_run) {
+        eprintln!("{} {}", verb, directory.display());
+    }
+    if let Some(history_file) = &config.history_file {
+        match retention::prune_history(std::path::Path::new(history_file.as_str()), retention_config, now, dry_run) {
+            Ok(0) => {}
+            Ok(pruned) => eprintln!("{} {} runs from {}", verb, pruned, history_file),
+            Err(error) => eprintln!("{}{}", "failed to prune the history: ".red(), error),
+        }
+    }
+}
+
+fn load_test_pass(report_file: &std::path::Path, config: &TestConfiguration, strict: bool) -> TestPass {
+    let mut test_pass = report::read_report(report_file, strict).unwrap_or_else(|error| {
+        eprintln!("{}{}", "failed to read the report: ".red(), error);
+        exit(EXIT_INVALID_REPORT);
+    });
+    // an engine version writing a state or entry type this tool doesn't know yet shouldn't lose the whole run
+    if !test_pass.schema_warnings.is_empty() {
+        eprintln!("{}", "schema warnings".yellow());
+        for schema_warning in test_pass.schema_warnings.iter() {
+            eprintln!("  {}", schema_warning);
+        }
+    }
+    test_pass.tests.sort_by(|a, b| a.full_test_path.cmp(&b.full_test_path));
+    testinfo::read_test_info(&mut test_pass, report_file.parent().unwrap_or_else(|| std::path::Path::new(".")), pipeline::worker_count(config.postprocess_workers));
+    let filtered_count = filters::apply_test_filters(&mut test_pass, &config.include_tests, &config.exclude_tests);
+    if filtered_count > 0 {
+        eprintln!("{}", format!("{} tests left out by include_tests / exclude_tests", filtered_count).dimmed());
+    }
+    if !config.path_map.is_empty() {
+        for test in test_pass.tests.iter_mut() {
+            for entry in test.entries.iter_mut() {
+                entry.filename = paths::remap_path(entry.filename.as_str(), &config.path_map).into();
+            }
+            if let Some(source_file) = test.info.as_mut().and_then(|info| info.source_file.as_mut()) {
+                *source_file = paths::remap_path(source_file.as_str(), &config.path_map);
+            }
+        }
+    }
+    if let Some(test_map_file) = &config.test_map_file {
+        testmap::apply_test_map(&mut test_pass, &testmap::load_test_map(std::path::Path::new(test_map_file.as_str())));
+    }
+    match &config.expected_error_markers {
+        Some(markers) => expected::mark_expected_messages(&mut test_pass, markers),
+        None => expected::mark_expected_messages(&mut test_pass, expected::DEFAULT_EXPECTED_MARKERS),
+    }
+    screenshots::apply_tolerances(&mut test_pass, &config.screenshot_tolerances);
+    let normalizer = analysis::Normalizer::new(config.normalization.as_ref()).expect("normalization patterns are checked when the config is loaded");
+    analysis::assign_failure_signatures(&mut test_pass, &config.ignore_regexes, &normalizer);
+    test_pass
+}
+
+fn print_no_tests(config: &TestConfiguration, run_metadata: &RunMetadata) {
+    eprintln!("{}", "no tests in the report".red());
+    if !config.include_tests.is_empty() || !config.exclude_tests.is_empty() {
+        eprintln!("{}", "include_tests / exclude_tests may have left out every test".yellow());
+    }
+    let mut known_tests = completions::test_names(config);
+    known_tests.retain(|test| !run_metadata.requested_tests.contains(test));
+    for requested in run_metadata.requested_tests.iter() {
+        let near_misses = find::near_misses(requested.as_str(), &known_tests, 5);
+        if near_misses.is_empty() {
+            eprintln!("    {} matched nothing", requested);
+        } else {
+            eprintln!("    {} matched nothing, did you mean {}?", requested, near_misses.join(", "));
+        }
+    }
+}
+
+// unique locations of the unexpected errors in failed tests
+fn failing_locations(test_pass: &TestPass, ignore_regexes: &[String]) -> Vec<(String, i32)> {
+    let mut failing_locations: Vec<(String, i32)> = Vec::new();
+    for test in test_pass.tests.iter().filter(|test| test.state == TestResult::Fail) {
+        for entry in test.entries.iter() {
+            if !matches!(entry.event.entry_type, EntryType::Error) || entry.expected || entry.filename.is_empty() {
+                continue;
+            }
+            if should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                continue;
+            }
+            let location = (entry.filename.to_string(), entry.line_number);
+            if !failing_locations.contains(&location) {
+                failing_locations.push(location);
+            }
+        }
+    }
+    failing_locations
+}
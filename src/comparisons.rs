@@ -0,0 +1,110 @@
+use crate::{ArtifactType, Test, TestConfiguration, TestPass};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// with comparisonExported the engine also wrote its screenshot comparisons (approved, incoming and delta images
+// with their json) to comparisonExportDirectory, they're linked from failing tests and packed into archives
+#[derive(Debug)]
+pub struct ComparisonExport {
+    pub directory: PathBuf,
+    pub files: Vec<PathBuf>, // relative to directory, sorted
+}
+
+impl ComparisonExport {
+    // the exported files of a test, found by one of its screenshot names (or the test's own name) in their path
+    pub fn files_for(&self, test: &Test) -> Vec<PathBuf> {
+        let mut names: Vec<String> = test.artifacts.iter()
+            .filter(|artifact| artifact.artifact_type == ArtifactType::Comparison)
+            .map(|artifact| artifact.name.to_lowercase())
+            .collect();
+        if !test.test_display_name.is_empty() {
+            names.push(test.test_display_name.to_lowercase());
+        }
+        self.files.iter()
+            .filter(|file| {
+                let stem = file.file_stem().map(|stem| stem.to_string_lossy().to_lowercase());
+                file.iter().map(|component| component.to_string_lossy().to_lowercase()).chain(stem)
+                    .any(|component| names.contains(&component))
+            })
+            .map(|file| self.directory.join(file))
+            .collect()
+    }
+}
+
+fn collect_files(directory: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for dir_entry in fs::read_dir(directory.join(relative))? {
+        let dir_entry = dir_entry?;
+        let path = relative.join(dir_entry.file_name());
+        if dir_entry.file_type()?.is_dir() {
+            collect_files(directory, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// the export directory on this machine; with [remote] only an export under path_to_reports was fetched
+#[cfg_attr(not(feature = "runner"), allow(unused_variables))]
+fn local_directory(test_pass: &TestPass, report_directory: &Path, config: &TestConfiguration) -> Result<PathBuf, String> {
+    let directory = test_pass.comparison_export_directory.as_str();
+    #[cfg(feature = "runner")]
+    if let Some(remote) = &config.remote {
+        let normalized = directory.replace('\\', "/");
+        let remote_reports = config.path_to_reports.replace('\\', "/");
+        return match normalized.get(..remote_reports.len()).filter(|prefix| prefix.eq_ignore_ascii_case(remote_reports.as_str())) {
+            Some(_) => Ok(crate::local_reports_directory(config).join(normalized[remote_reports.len()..].trim_start_matches('/'))),
+            None => Err(format!("{} is outside path_to_reports and was left on {}", directory, remote.host)),
+        };
+    }
+    Ok(report_directory.join(directory))
+}
+
+// None when the run didn't export comparisons
+pub fn read_comparison_export(test_pass: &TestPass, report_directory: &Path, config: &TestConfiguration) -> Result<Option<ComparisonExport>, String> {
+    if !test_pass.comparison_exported || test_pass.comparison_export_directory.is_empty() {
+        return Ok(None);
+    }
+    let directory = local_directory(test_pass, report_directory, config)?;
+    if !directory.is_dir() {
+        return Err(format!("{} does not exist", directory.display()));
+    }
+    let mut files = Vec::new();
+    collect_files(&directory, Path::new(""), &mut files).map_err(|error| format!("failed to read {}: {}", directory.display(), error))?;
+    files.sort();
+    Ok(Some(ComparisonExport { directory, files }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_files_are_found_by_screenshot_name() {
+        let directory = std::env::temp_dir().join(format!("runtests-comparisons-{}", std::process::id()));
+        for file in ["CastleGate/Win64_D3D12/Approved.png", "CastleGate/Win64_D3D12/Delta.png", "Moat/Win64_D3D12/Approved.png"] {
+            let path = directory.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "png").unwrap();
+        }
+        let json = format!(r#"{{"devices": null, "reportCreatedOn": "", "succeeded": 0, "succeededWithWarnings": 0, "failed": 1, "notRun": 0,
+            "inProcess": null, "totalDuration": 0, "comparisonExported": true, "comparisonExportDirectory": {}, "tests": [
+                {{"testDisplayName": "Gate", "fullTestPath": "Project.Screenshots.Gate", "state": "Fail", "entries": [], "warnings": 0, "errors": 1,
+                    "artifacts": [{{"id": "1", "name": "CastleGate", "type": "Comparison"}}]}}]}}"#, serde_json::to_string(&directory).unwrap());
+        let test_pass: TestPass = serde_json::from_str(json.as_str()).unwrap();
+        let config: TestConfiguration = toml::from_str(r#"
+            path_to_unrealengine = ""
+            path_to_project = ""
+            path_to_reports = ""
+            run_tests = ""
+            test_exit = ""
+            ignore_regexes = []"#).unwrap();
+
+        let export = read_comparison_export(&test_pass, Path::new("."), &config).unwrap().unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(export.files.len(), 3);
+        let linked = export.files_for(&test_pass.tests[0]);
+        assert_eq!(linked, [directory.join("CastleGate/Win64_D3D12/Approved.png"), directory.join("CastleGate/Win64_D3D12/Delta.png")]);
+        assert!(read_comparison_export(&test_pass, Path::new("."), &config).is_err());
+    }
+}
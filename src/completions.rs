@@ -0,0 +1,231 @@
+use crate::{formats, history, instances, lang, report, runner, TestConfiguration};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+pub const SHELL_NAMES: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+const SUBCOMMANDS: &[&str] = &["parse", "find", "list", "map-tests", "prune", "rerun", "bisect", "failing-since", "schedule", "self-update", "validate", "help-all", "completions"];
+const EDITOR_NAMES: &[&str] = &["vscode", "rider", "clion", "sublime", "notepad++"];
+
+enum Value {
+    None,
+    File,
+    Choices(&'static [&'static str]),
+    Any,
+}
+
+struct CompletionOption {
+    long: &'static str,
+    short: Option<char>,
+    value: Value,
+}
+
+const fn option(long: &'static str, value: Value) -> CompletionOption {
+    CompletionOption { long, short: None, value }
+}
+
+// the top level and global options of main's App, kept in the same order
+const OPTIONS: &[CompletionOption] = &[
+    CompletionOption { long: "config", short: Some('c'), value: Value::File },
+    option("chdir", Value::File),
+    option("branch", Value::Any),
+    option("tests", Value::Any),
+    option("filter", Value::Choices(runner::FILTER_NAMES)),
+    option("shuffle", Value::None),
+    option("order", Value::Choices(history::ORDER_NAMES)),
+    option("isolate", Value::None),
+    CompletionOption { long: "jobs", short: Some('j'), value: Value::Any },
+    option("skip-build", Value::None),
+    option("cache", Value::None),
+    option("skip-warmup", Value::None),
+    option("force", Value::None),
+    option("open-in", Value::Choices(EDITOR_NAMES)),
+    option("plain", Value::None),
+    option("width", Value::Any),
+    option("lang", Value::Choices(lang::LANG_NAMES)),
+    option("durations", Value::None),
+    option("report-file", Value::File),
+    option("archive", Value::File),
+    option("html", Value::File),
+    option("format", Value::Choices(formats::FORMAT_NAMES)),
+    option("csv-profiles", Value::None),
+    option("details-file", Value::File),
+    option("split-logs", Value::File),
+    option("log-excerpts", Value::None),
+    option("show-ignored", Value::None),
+    option("timestamps", Value::None),
+    option("timeline", Value::None),
+    option("warnings-by-file", Value::None),
+    option("strict-parse", Value::None),
+    option("session-name", Value::Any),
+    option("meta", Value::Any),
+];
+
+// the names offered for the tests argument: the selections in the config (run_tests is usually a prefix
+// that stands for a whole suite) and every test of the most recent run, from the history file when
+// there is one since that's much cheaper to read than a report on every keypress
+pub fn test_names(config: &TestConfiguration) -> Vec<String> {
+    let mut names: BTreeSet<String> = instances::individual_tests(config.run_tests.as_str()).into_iter().collect();
+    if let Some(warmup_tests) = &config.warmup_tests {
+        names.extend(instances::individual_tests(warmup_tests.as_str()));
+    }
+
+    let last_run = config.history_file.as_ref().and_then(|history_file| history::load_history(Path::new(history_file.as_str())).pop());
+    match last_run {
+        Some(run) => names.extend(run.tests.into_iter().map(|test| test.path)),
+        None => {
+            let test_pass = report::find_report_file(config.path_to_reports.as_str()).and_then(|report_file| report::read_test_pass(&report_file).ok());
+            if let Some(test_pass) = test_pass {
+                names.extend(test_pass.tests.into_iter().map(|test| test.full_test_path));
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+// the command the scripts run to complete test names, it reads the same config as the command being completed would
+const LIST_TESTS: &str = "runtests completions --list-tests 2>/dev/null";
+
+fn option_words() -> Vec<String> {
+    OPTIONS.iter()
+        .flat_map(|option| std::iter::once(format!("--{}", option.long)).chain(option.short.map(|short| format!("-{}", short))))
+        .collect()
+}
+
+fn bash() -> String {
+    let mut script = String::new();
+    writeln!(script, "_runtests() {{").unwrap();
+    writeln!(script, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"").unwrap();
+    writeln!(script, "    case \"$prev\" in").unwrap();
+    for option in OPTIONS.iter() {
+        let names = std::iter::once(format!("--{}", option.long)).chain(option.short.map(|short| format!("-{}", short))).collect::<Vec<String>>().join("|");
+        match option.value {
+            Value::File => writeln!(script, "        {}) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;", names).unwrap(),
+            Value::Choices(choices) => writeln!(script, "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;", names, choices.join(" ")).unwrap(),
+            Value::Any => writeln!(script, "        {}) return ;;", names).unwrap(),
+            Value::None => {}
+        }
+    }
+    writeln!(script, "        completions) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;", SHELL_NAMES.join(" ")).unwrap();
+    writeln!(script, "    esac").unwrap();
+    writeln!(script, "    if [[ \"$cur\" == -* ]]; then").unwrap();
+    writeln!(script, "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", option_words().join(" ")).unwrap();
+    writeln!(script, "    elif [[ $COMP_CWORD -eq 1 ]]; then").unwrap();
+    writeln!(script, "        COMPREPLY=($(compgen -W \"{} $({})\" -- \"$cur\"))", SUBCOMMANDS.join(" "), LIST_TESTS).unwrap();
+    writeln!(script, "    else").unwrap();
+    writeln!(script, "        COMPREPLY=($(compgen -W \"$({})\" -- \"$cur\"))", LIST_TESTS).unwrap();
+    writeln!(script, "    fi").unwrap();
+    writeln!(script, "}}").unwrap();
+    writeln!(script, "complete -F _runtests runtests").unwrap();
+    script
+}
+
+fn zsh() -> String {
+    let mut script = String::new();
+    writeln!(script, "#compdef runtests").unwrap();
+    writeln!(script, "_runtests() {{").unwrap();
+    writeln!(script, "    local -a tests").unwrap();
+    writeln!(script, "    tests=(${{(f)\"$({})\"}})", LIST_TESTS).unwrap();
+    writeln!(script, "    _arguments \\").unwrap();
+    for option in OPTIONS.iter() {
+        let action = match option.value {
+            Value::None => String::new(),
+            Value::File => format!(":{}:_files", option.long),
+            Value::Choices(choices) => format!(":{}:({})", option.long, choices.join(" ")),
+            Value::Any => format!(":{}: ", option.long),
+        };
+        let repeat = if option.long == "format" || option.long == "meta" { "*" } else { "" };
+        match option.short {
+            Some(short) => writeln!(script, "        '{}'{{-{},--{}}}'{}' \\", repeat, short, option.long, action).unwrap(),
+            None => writeln!(script, "        '{}--{}{}' \\", repeat, option.long, action).unwrap(),
+        }
+    }
+    writeln!(script, "        '1:test or command:({} $tests)' \\", SUBCOMMANDS.join(" ")).unwrap();
+    writeln!(script, "        '*:test:($tests)'").unwrap();
+    writeln!(script, "}}").unwrap();
+    writeln!(script, "compdef _runtests runtests").unwrap();
+    script
+}
+
+fn fish() -> String {
+    let mut script = String::new();
+    writeln!(script, "complete -c runtests -f").unwrap();
+    writeln!(script, "complete -c runtests -n __fish_use_subcommand -a '{}'", SUBCOMMANDS.join(" ")).unwrap();
+    writeln!(script, "complete -c runtests -n '__fish_seen_subcommand_from completions' -a '{}'", SHELL_NAMES.join(" ")).unwrap();
+    writeln!(script, "complete -c runtests -n 'not __fish_seen_subcommand_from {}' -a '({})'", SUBCOMMANDS.join(" "), LIST_TESTS).unwrap();
+    for option in OPTIONS.iter() {
+        let short = option.short.map_or(String::new(), |short| format!(" -s {}", short));
+        match option.value {
+            Value::None => writeln!(script, "complete -c runtests -l {}{}", option.long, short).unwrap(),
+            Value::File => writeln!(script, "complete -c runtests -l {}{} -r -F", option.long, short).unwrap(),
+            Value::Choices(choices) => writeln!(script, "complete -c runtests -l {}{} -x -a '{}'", option.long, short, choices.join(" ")).unwrap(),
+            Value::Any => writeln!(script, "complete -c runtests -l {}{} -x", option.long, short).unwrap(),
+        }
+    }
+    script
+}
+
+fn powershell() -> String {
+    let quoted = |words: &[&str]| words.iter().map(|word| format!("'{}'", word)).collect::<Vec<String>>().join(", ");
+    let mut script = String::new();
+    writeln!(script, "Register-ArgumentCompleter -Native -CommandName runtests -ScriptBlock {{").unwrap();
+    writeln!(script, "    param($wordToComplete, $commandAst, $cursorPosition)").unwrap();
+    writeln!(script, "    $elements = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})").unwrap();
+    writeln!(script, "    $previous = if ($wordToComplete) {{ $elements[-2] }} else {{ $elements[-1] }}").unwrap();
+    writeln!(script, "    $candidates = switch ($previous) {{").unwrap();
+    for option in OPTIONS.iter() {
+        if let Value::Choices(choices) = option.value {
+            writeln!(script, "        '--{}' {{ @({}) }}", option.long, quoted(choices)).unwrap();
+        }
+    }
+    writeln!(script, "        'completions' {{ @({}) }}", quoted(SHELL_NAMES)).unwrap();
+    writeln!(script, "        default {{").unwrap();
+    writeln!(script, "            if ($wordToComplete -like '-*') {{ @({}) }}", option_words().iter().map(|word| format!("'{}'", word)).collect::<Vec<String>>().join(", ")).unwrap();
+    writeln!(script, "            else {{ @({}) + @(runtests completions --list-tests 2>$null) }}", quoted(SUBCOMMANDS)).unwrap();
+    writeln!(script, "        }}").unwrap();
+    writeln!(script, "    }}").unwrap();
+    writeln!(script, "    $candidates | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{").unwrap();
+    writeln!(script, "        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)").unwrap();
+    writeln!(script, "    }}").unwrap();
+    writeln!(script, "}}").unwrap();
+    script
+}
+
+pub fn script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        "powershell" | "pwsh" => Some(powershell()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_option_is_known_to_the_app() {
+        for option in OPTIONS.iter() {
+            let mut args = vec!["runtests".to_owned(), format!("--{}", option.long)];
+            match option.value {
+                Value::None => {}
+                Value::Choices(choices) => args.push(choices[0].to_owned()),
+                Value::File | Value::Any => args.push("x=y".to_owned()),
+            }
+            assert!(crate::cli::app().try_get_matches_from(args).is_ok(), "--{}", option.long);
+        }
+    }
+
+    #[test]
+    fn scripts_for_every_shell() {
+        assert!(SHELL_NAMES.iter().all(|shell| script(shell).is_some()));
+        assert!(script("tcsh").is_none());
+        let bash = script("bash").unwrap();
+        assert!(bash.contains("--filter) COMPREPLY=($(compgen -W \"smoke engine product perf stress negative\" -- \"$cur\")); return ;;"));
+        assert!(bash.contains("--config|-c) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;"));
+        assert!(script("fish").unwrap().contains("complete -c runtests -l jobs -s j -x"));
+    }
+}
@@ -0,0 +1,373 @@
+use crate::{analysis, discord, display, gitlab, history, jenkins, logexcerpts, perf, runner, selfupdate, telemetry, TestConfiguration};
+use serde::de::value::Error;
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+
+// the example config doubles as the documentation of every key it shows
+const EXAMPLE_CONFIG: &str = include_str!("../testconfig.toml");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigKey {
+    pub key: String, // dotted, [] marks the element of a list
+    pub kind: String,
+    pub optional: bool,
+}
+
+// a deserializer that answers every request with a placeholder and writes down what was asked for, so
+// running TestConfiguration's derived Deserialize over it lists every key and its type without a schema
+// that could drift from the structs; `omit` leaves one key out to find out whether it's required
+struct Probe<'a> {
+    key: String,
+    optional: bool,
+    keys: &'a RefCell<Vec<ConfigKey>>,
+    omit: Option<&'a str>,
+}
+
+impl<'a> Probe<'a> {
+    fn record(&self, kind: &str) {
+        self.keys.borrow_mut().push(ConfigKey { key: self.key.clone(), kind: kind.to_owned(), optional: self.optional });
+    }
+
+    fn child(&self, key: String) -> Probe<'a> {
+        Probe { key, optional: false, keys: self.keys, omit: self.omit }
+    }
+}
+
+macro_rules! probe_leaf {
+    ($($method:ident => $kind:expr, $visit:ident($value:expr);)*) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.record($kind);
+            visitor.$visit($value)
+        })*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for Probe<'a> {
+    type Error = Error;
+
+    probe_leaf! {
+        deserialize_bool => "boolean", visit_bool(false);
+        deserialize_i8 => "integer", visit_i8(0);
+        deserialize_i16 => "integer", visit_i16(0);
+        deserialize_i32 => "integer", visit_i32(0);
+        deserialize_i64 => "integer", visit_i64(0);
+        deserialize_u8 => "integer", visit_u8(0);
+        deserialize_u16 => "integer", visit_u16(0);
+        deserialize_u32 => "integer", visit_u32(0);
+        deserialize_u64 => "integer", visit_u64(0);
+        deserialize_f32 => "number", visit_f32(0.0);
+        deserialize_f64 => "number", visit_f64(0.0);
+        deserialize_char => "string", visit_char(' ');
+        deserialize_str => "string", visit_str("");
+        deserialize_string => "string", visit_str("");
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.record("any");
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(Probe { optional: true, ..self })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.record("list");
+        let element = self.child(format!("{}[]", self.key));
+        visitor.visit_seq(ProbeSeq { element: Some(element) })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.record("table");
+        visitor.visit_map(ProbeStruct { probe: self, fields: &[], index: 0 })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        if !self.key.is_empty() {
+            self.record("table");
+        }
+        visitor.visit_map(ProbeStruct { probe: self, fields, index: 0 })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        self.record(format!("one of {}", variants.join(", ")).as_str());
+        visitor.visit_enum(ProbeEnum { variant: variants.first().copied().unwrap_or_default() })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct newtype_struct tuple tuple_struct identifier ignored_any
+    }
+}
+
+// a list with a single element, enough to see what its elements look like
+struct ProbeSeq<'a> {
+    element: Option<Probe<'a>>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for ProbeSeq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.element.take() {
+            Some(element) => seed.deserialize(element).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct ProbeStruct<'a> {
+    probe: Probe<'a>,
+    fields: &'static [&'static str],
+    index: usize,
+}
+
+impl<'a> ProbeStruct<'a> {
+    fn field_key(&self, field: &str) -> String {
+        if self.probe.key.is_empty() { field.to_owned() } else { format!("{}.{}", self.probe.key, field) }
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for ProbeStruct<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        let fields = self.fields;
+        while let Some(field) = fields.get(self.index) {
+            if self.probe.omit != Some(self.field_key(field).as_str()) {
+                return seed.deserialize((*field).into_deserializer()).map(Some);
+            }
+            self.index += 1;
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let key = self.field_key(self.fields[self.index]);
+        self.index += 1;
+        seed.deserialize(self.probe.child(key))
+    }
+}
+
+struct ProbeEnum {
+    variant: &'static str,
+}
+
+impl<'de> EnumAccess<'de> for ProbeEnum {
+    type Error = Error;
+    type Variant = ProbeEnum;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, ProbeEnum), Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ProbeEnum {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, _seed: T) -> Result<T::Value, Error> {
+        Err(de::Error::custom("only unit variants can be probed"))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Error> {
+        Err(de::Error::custom("only unit variants can be probed"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, Error> {
+        Err(de::Error::custom("only unit variants can be probed"))
+    }
+}
+
+fn probe<'de, T: Deserialize<'de>>(omit: Option<&str>) -> Result<Vec<ConfigKey>, Error> {
+    let keys = RefCell::new(Vec::new());
+    T::deserialize(Probe { key: String::new(), optional: false, keys: &keys, omit })?;
+    Ok(keys.into_inner())
+}
+
+// every key of T, a key is optional when it's an Option or T still deserializes without it (a serde default)
+pub fn config_keys<'de, T: Deserialize<'de>>() -> Vec<ConfigKey> {
+    let mut keys = probe::<T>(None).expect("failed to probe the config");
+    for key in keys.iter_mut().filter(|key| !key.optional) {
+        key.optional = probe::<T>(Some(key.key.as_str())).is_ok();
+    }
+    keys
+}
+
+// defaults that come from a function rather than the type's own Default
+fn default_value(key: &str) -> Option<String> {
+    Some(match key {
+        "max_parallel_engines" => crate::default_max_parallel_engines().to_string(),
+        "engine_start_stagger" => crate::default_engine_start_stagger().to_string(),
+        "culprit_min_tests" => crate::default_culprit_min_tests().to_string(),
+        "normalization.guids" | "normalization.addresses" | "normalization.instance_suffixes" | "normalization.numbers" => analysis::default_enabled().to_string(),
+        "log_excerpt_lines" => logexcerpts::default_log_excerpt_lines().to_string(),
+        "runner.kind" => format!("{:?}", runner::RunnerKind::default()).to_lowercase(),
+        "display.res_x" => display::default_res_x().to_string(),
+        "display.res_y" => display::default_res_y().to_string(),
+        "display.windowed" => display::default_windowed().to_string(),
+        "display.force_res" => display::default_force_res().to_string(),
+        "duration_regressions.min_duration" => history::default_min_duration().to_string(),
+        "duration_regressions.min_samples" => history::default_min_samples().to_string(),
+        "duration_regressions.window" => history::default_window().to_string(),
+        "perf.metric_patterns" => format!("{:?}", perf::default_metric_patterns()),
+        "perf.min_samples" => perf::default_min_samples().to_string(),
+        "perf.window" => perf::default_window().to_string(),
+        "gitlab.token_env" => format!("{:?}", gitlab::default_token_env()),
+        "jenkins.token_env" => format!("{:?}", jenkins::default_token_env()),
+        "jenkins.checks.api_url" => format!("{:?}", jenkins::default_checks_api_url()),
+        "jenkins.checks.token_env" => format!("{:?}", jenkins::default_checks_token_env()),
+        "jenkins.checks.name" => format!("{:?}", jenkins::default_check_name()),
+        "discord.webhook_env" => format!("{:?}", discord::default_webhook_env()),
+        "telemetry.service_name" => format!("{:?}", telemetry::default_service_name()),
+        "updates.releases_url" => format!("{:?}", selfupdate::default_releases_url()),
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct KeyDocs {
+    description: Vec<String>,
+    example: Option<String>,
+}
+
+// the comment block above a key or [section] in the example config describes it, a trailing comment too;
+// a blank line ends a section since every section in the example is commented out
+fn example_docs(example_config: &str) -> Vec<(String, KeyDocs)> {
+    // quoted names like [branch."release/*"] too, so their keys aren't taken for top-level ones
+    let section_line = regex::Regex::new(r#"^#?\s*\[\[?([\w."/*-]+)\]\]?\s*$"#).unwrap();
+    let key_line = regex::Regex::new(r"^#?\s*([a-z_0-9]+)\s*=\s*(.*)$").unwrap();
+
+    let mut docs: Vec<(String, KeyDocs)> = Vec::new();
+    let mut section: Option<String> = None;
+    let mut comments: Vec<String> = Vec::new();
+    for line in example_config.lines().map(str::trim) {
+        if line.is_empty() {
+            section = None;
+            comments.clear();
+        } else if let Some(captures) = section_line.captures(line) {
+            let name = captures[1].to_owned();
+            docs.push((name.clone(), KeyDocs { description: std::mem::take(&mut comments), example: None }));
+            section = Some(name);
+        } else if let Some(captures) = key_line.captures(line) {
+            let key = match &section {
+                Some(section) => format!("{}.{}", section, &captures[1]),
+                None => captures[1].to_owned(),
+            };
+            let mut description = std::mem::take(&mut comments);
+            let example = match captures[2].split_once(" # ") {
+                Some((value, comment)) => {
+                    description.push(comment.trim().to_owned());
+                    format!("{}={}", &captures[1], value.trim())
+                }
+                None => format!("{}={}", &captures[1], captures[2].trim()),
+            };
+            docs.push((key, KeyDocs { description, example: Some(example) }));
+        } else if let Some(comment) = line.strip_prefix('#') {
+            comments.push(comment.trim().to_owned());
+        }
+    }
+    docs
+}
+
+// list elements are shown the way toml writes them, [[path_map]] fields as path_map.from
+fn display_key(key: &str) -> String {
+    key.replace("[]", "")
+}
+
+fn display_kind(key: &ConfigKey, keys: &[ConfigKey]) -> String {
+    if key.kind != "list" {
+        return key.kind.clone();
+    }
+    let element_key = format!("{}[]", key.key);
+    match keys.iter().find(|element| element.key == element_key) {
+        Some(element) if element.kind == "table" => "array of tables".to_owned(),
+        Some(element) => format!("list of {}", element.kind),
+        None => "list".to_owned(),
+    }
+}
+
+pub fn write_config_help(out: &mut String) {
+    let keys = config_keys::<TestConfiguration>();
+    let docs = example_docs(EXAMPLE_CONFIG);
+    writeln!(out, "Keys of the config file (-c, testconfig.toml by default):").unwrap();
+    for key in keys.iter().filter(|key| !key.key.ends_with("[]")) {
+        let name = display_key(key.key.as_str());
+        let kind = display_kind(key, &keys);
+        let requirement = match (key.optional, default_value(name.as_str())) {
+            (false, _) => "required".to_owned(),
+            (true, Some(default)) => format!("default {}", default),
+            (true, None) if kind == "boolean" => "default false".to_owned(),
+            (true, None) if kind.starts_with("list") || kind == "array of tables" => "default []".to_owned(),
+            (true, None) => "optional".to_owned(),
+        };
+        let section_depth = name.matches('.').count();
+        let indent = "    ".repeat(section_depth);
+        writeln!(out).unwrap();
+        if kind == "table" || kind == "array of tables" {
+            let brackets = if kind == "table" { ("[", "]") } else { ("[[", "]]") };
+            writeln!(out, "{}{}{}{} ({}, {})", indent, brackets.0, name, brackets.1, kind, requirement).unwrap();
+        } else {
+            writeln!(out, "{}{} ({}, {})", indent, name, kind, requirement).unwrap();
+        }
+        if let Some((_, key_docs)) = docs.iter().find(|(docs_key, _)| *docs_key == name) {
+            for line in key_docs.description.iter() {
+                writeln!(out, "{}    {}", indent, line).unwrap();
+            }
+            if let Some(example) = &key_docs.example {
+                writeln!(out, "{}    example: {}", indent, example).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(keys: &'a [ConfigKey], key: &str) -> &'a ConfigKey {
+        keys.iter().find(|config_key| config_key.key == key).unwrap_or_else(|| panic!("{} wasn't probed", key))
+    }
+
+    #[test]
+    fn keys_types_and_requirements_come_from_the_structs() {
+        let keys = config_keys::<TestConfiguration>();
+        assert_eq!(find(&keys, "path_to_project"), &ConfigKey { key: "path_to_project".to_owned(), kind: "string".to_owned(), optional: false });
+        assert!(find(&keys, "warmup_tests").optional);
+        assert!(find(&keys, "max_parallel_engines").optional);
+        assert_eq!(find(&keys, "build").kind, "table");
+        assert!(find(&keys, "build").optional);
+        assert!(!find(&keys, "build.target").optional);
+        assert_eq!(find(&keys, "runner.kind").kind, "one of editor, editor-cmd, packaged, gauntlet");
+        assert_eq!(display_kind(find(&keys, "perf.budgets"), &keys), "array of tables");
+        assert!(!find(&keys, "perf.budgets[].metric").optional);
+        assert!(find(&keys, "perf.budgets[].max").optional);
+        assert_eq!(display_kind(find(&keys, "ignore_regexes"), &keys), "list of string");
+        assert_eq!(find(&keys, "remote.port").kind, "integer");
+    }
+
+    #[test]
+    fn docs_from_the_example_config() {
+        let docs = example_docs("\
+# optional: compile before running tests
+# [build]
+# target=\"CastleAdventureEditor\"
+# extra_args=[] # passed on to the build tool
+
+# how many failed tests must share a first error
+# culprit_min_tests=3
+");
+        assert_eq!(docs[0], ("build".to_owned(), KeyDocs { description: vec!["optional: compile before running tests".to_owned()], example: None }));
+        assert_eq!(docs[1].0, "build.target");
+        assert_eq!(docs[2].1, KeyDocs { description: vec!["passed on to the build tool".to_owned()], example: Some("extra_args=[]".to_owned()) });
+        assert_eq!(docs[3], ("culprit_min_tests".to_owned(), KeyDocs {
+            description: vec!["how many failed tests must share a first error".to_owned()],
+            example: Some("culprit_min_tests=3".to_owned()),
+        }));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::report;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// frames the crash reporter itself adds on top of the actual fault
+const HANDLER_FRAME_PREFIXES: &[&str] = &[
+    "FDebug::",
+    "FOutputDevice",
+    "FWindowsErrorOutputDevice",
+    "FWindowsPlatformStackWalk",
+    "FGenericPlatformStackWalk",
+    "FGenericPlatformMisc::RaiseException",
+    "FUnixPlatformStackWalk",
+    "ReportAssert",
+    "ReportCrash",
+    "RaiseException",
+    "CheckVerifyFailedImpl",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StackFrame {
+    pub address: String,
+    pub module: String,
+    pub function: Option<String>, // None while the engine couldn't resolve it (UnknownFunction)
+    pub location: Option<String>,
+    pub repeated: usize, // consecutive identical frames (recursion) are collapsed into one
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let function = self.function.as_deref().unwrap_or(if self.address.is_empty() { "UnknownFunction" } else { self.address.as_str() });
+        if self.module.is_empty() {
+            write!(f, "{}", function)?;
+        } else {
+            write!(f, "{}!{}", self.module, function)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " [{}]", location)?;
+        }
+        if self.repeated > 1 {
+            write!(f, " (x{})", self.repeated)?;
+        }
+        Ok(())
+    }
+}
+
+// runs an external tool for frames the engine left unresolved (no pdbs next to the binaries), {module},
+// {address} (as logged, not module relative) and {symbol_path} in args are replaced per frame; the first line
+// of its output is taken as the function and an optional second line as the file:line, like llvm-symbolizer prints
+#[derive(Debug, Deserialize)]
+pub struct SymbolizerConfiguration {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub symbol_path: String,
+}
+
+fn parse_frame(line: &str, frame_regex: &regex::Regex) -> Option<StackFrame> {
+    let captures = frame_regex.captures(line)?;
+    let function = captures[3].trim();
+    let location = captures.get(4).map_or("", |location| location.as_str().trim());
+    Some(StackFrame {
+        address: captures[1].to_owned(),
+        module: captures[2].to_owned(),
+        function: if function.is_empty() || function == "UnknownFunction" { None } else { Some(function.to_owned()) },
+        location: if location.is_empty() { None } else { Some(location.to_owned()) },
+        repeated: 1,
+    })
+}
+
+// the [Callstack] lines of the last crash in the engine log, empty if the log has none
+pub fn read_log_callstack(log_path: &Path) -> Vec<StackFrame> {
+    let file = match File::open(log_path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    let frame_regex = regex::Regex::new(r"\[Callstack\]\s+(0x[0-9a-fA-F]+)\s+([^!\s]+)!(.*?)\s*(?:\[([^\]]*)\])?\s*$").unwrap();
+
+    let mut frames = Vec::new();
+    let mut in_callstack = false;
+    for line in BufReader::new(file).split(b'\n').map_while(Result::ok) {
+        let line = String::from_utf8_lossy(&line);
+        match parse_frame(&line, &frame_regex) {
+            Some(frame) => {
+                // a callstack that starts after other lines belongs to a later crash (or ensure), keep only the last
+                if !in_callstack {
+                    frames.clear();
+                    in_callstack = true;
+                }
+                frames.push(frame);
+            }
+            None => in_callstack = false,
+        }
+    }
+    frames
+}
+
+fn symbolize_frame(frame: &StackFrame, config: &SymbolizerConfiguration) -> Option<(String, Option<String>)> {
+    let args: Vec<String> = config.args.iter()
+        .map(|arg| arg.replace("{module}", frame.module.as_str()).replace("{address}", frame.address.as_str()).replace("{symbol_path}", config.symbol_path.as_str()))
+        .collect();
+    let output = Command::new(&config.command).args(&args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+    let function = lines.next().filter(|function| *function != "??")?.to_owned();
+    let location = lines.next().filter(|location| !location.starts_with("??")).map(str::to_owned);
+    Some((function, location))
+}
+
+pub fn symbolize(frames: &mut [StackFrame], config: &SymbolizerConfiguration) {
+    for frame in frames.iter_mut().filter(|frame| frame.function.is_none()) {
+        if let Some((function, location)) = symbolize_frame(frame, config) {
+            frame.function = Some(function);
+            if location.is_some() {
+                frame.location = location;
+            }
+        }
+    }
+}
+
+fn is_handler_frame(frame: &StackFrame) -> bool {
+    frame.function.as_deref().is_some_and(|function| HANDLER_FRAME_PREFIXES.iter().any(|prefix| function.starts_with(prefix)))
+}
+
+// drops the crash reporter's own frames from the top and collapses recursion
+pub fn clean(frames: Vec<StackFrame>) -> Vec<StackFrame> {
+    let first_fault = frames.iter().position(|frame| !is_handler_frame(frame)).unwrap_or(0);
+    let mut cleaned: Vec<StackFrame> = Vec::new();
+    for frame in frames.into_iter().skip(first_fault) {
+        match cleaned.last_mut() {
+            Some(previous) if previous.module == frame.module && previous.function.is_some() && previous.function == frame.function => previous.repeated += 1,
+            _ => cleaned.push(frame),
+        }
+    }
+    cleaned
+}
+
+// what the engine's crash reporter wrote to Saved/Crashes/<id>/CrashContext.runtime-xml
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrashContext {
+    pub folder: String,
+    pub crash_type: Option<String>, // Crash, Assert, Ensure, GPUCrash, Hang
+    pub error_message: Option<String>,
+    pub engine_version: Option<String>,
+    pub platform: Option<String>,
+    pub build_configuration: Option<String>,
+    pub seconds_since_start: Option<String>,
+    pub cpu: Option<String>,
+    pub gpu: Option<String>,
+    pub os: Option<String>,
+    pub total_physical_memory: Option<String>,
+}
+
+// the newest crash folder written since the run started, if the engine left one
+pub fn find_crash_context(project_directory: &Path, since: chrono::DateTime<chrono::Utc>) -> Option<PathBuf> {
+    let crashes = fs::read_dir(project_directory.join("Saved").join("Crashes")).ok()?;
+    crashes.filter_map(Result::ok)
+        .map(|crash_folder| crash_folder.path().join("CrashContext.runtime-xml"))
+        .filter(|context_file| report::written_since(context_file, since))
+        .max_by_key(|context_file| fs::metadata(context_file).and_then(|metadata| metadata.modified()).ok())
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// the file is flat enough that a full xml parser isn't worth the dependency, every value is a leaf <Tag>value</Tag>
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(format!("<{}>", tag).as_str())? + tag.len() + 2;
+    let end = start + xml[start..].find(format!("</{}>", tag).as_str())?;
+    let value = unescape_xml(xml[start..end].trim());
+    if value.is_empty() { None } else { Some(value) }
+}
+
+// crash context callstack lines look like the log's without the address: Module!Function() [File:Line]
+fn parse_context_frame(line: &str) -> StackFrame {
+    let (location, symbol) = match (line.rfind(" ["), line.ends_with(']')) {
+        (Some(bracket), true) => (line[bracket + 2..line.len() - 1].trim(), line[..bracket].trim()),
+        _ => ("", line.trim()),
+    };
+    let (module, function) = symbol.split_once('!').unwrap_or(("", symbol));
+    StackFrame {
+        address: String::new(),
+        module: module.to_owned(),
+        function: if function.is_empty() || function == "UnknownFunction" { None } else { Some(function.to_owned()) },
+        location: if location.is_empty() { None } else { Some(location.to_owned()) },
+        repeated: 1,
+    }
+}
+
+// crash contexts are usually utf-8, some engine versions write utf-16 with a byte order mark
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// the context and its callstack, which the crash reporter has already symbolized when the pdbs were available
+pub fn read_crash_context(context_file: &Path) -> Result<(CrashContext, Vec<StackFrame>), String> {
+    let bytes = fs::read(context_file).map_err(|error| format!("failed to read {}: {}", context_file.display(), error))?;
+    let xml = decode_text(&bytes);
+    if !xml.contains("<RuntimeProperties>") {
+        return Err(format!("{} is not a crash context", context_file.display()));
+    }
+
+    let crash_context = CrashContext {
+        folder: context_file.parent().unwrap_or(context_file).display().to_string(),
+        crash_type: xml_value(&xml, "CrashType"),
+        error_message: xml_value(&xml, "ErrorMessage"),
+        engine_version: xml_value(&xml, "EngineVersion"),
+        platform: xml_value(&xml, "PlatformName"),
+        build_configuration: xml_value(&xml, "BuildConfiguration"),
+        seconds_since_start: xml_value(&xml, "SecondsSinceStart"),
+        cpu: xml_value(&xml, "Misc.CPUBrand"),
+        gpu: xml_value(&xml, "Misc.PrimaryGPUBrand"),
+        os: xml_value(&xml, "Misc.OSVersionMajor"),
+        total_physical_memory: xml_value(&xml, "MemoryStats.TotalPhysical"),
+    };
+    let callstack = xml_value(&xml, "CallStack").map_or_else(Vec::new, |callstack| {
+        callstack.lines().map(str::trim).filter(|line| !line.is_empty()).map(parse_context_frame).collect()
+    });
+    Ok((crash_context, callstack))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+[2023.10.01-12.00.00:000][  0]LogWindows: Error: === Ensure ===
+[2023.10.01-12.00.00:000][  0]LogWindows: Error: [Callstack] 0x00007ff6a1b2c3d4 UnrealEditor-Core.dll!FDebug::EnsureFailed() [D:\\Engine\\Core\\AssertionMacros.cpp:100]
+[2023.10.01-12.00.01:000][  0]LogTemp: Display: still running
+[2023.10.01-12.00.02:000][  0]LogWindows: Error: [Callstack] 0x00007ff6a1b2c3d4 UnrealEditor-Core.dll!FWindowsPlatformStackWalk::StackWalkAndDump() []
+[2023.10.01-12.00.02:000][  0]LogWindows: Error: [Callstack] 0x00007ff6a1b2c3e0 UnrealEditor-Castle.dll!ADoor::Open() [D:\\Castle\\Door.cpp:42]
+[2023.10.01-12.00.02:000][  0]LogWindows: Error: [Callstack] 0x00007ff6a1b2c3e0 UnrealEditor-Castle.dll!ADoor::Open() [D:\\Castle\\Door.cpp:42]
+[2023.10.01-12.00.02:000][  0]LogWindows: Error: [Callstack] 0x00007ff6a1b2c400 UnrealEditor-Engine.dll!UnknownFunction []
+";
+
+    #[test]
+    fn last_callstack_in_the_log() {
+        let log_path = std::env::temp_dir().join(format!("runtests-crash-{}.log", std::process::id()));
+        std::fs::write(&log_path, LOG).unwrap();
+        let frames = read_log_callstack(&log_path);
+        std::fs::remove_file(&log_path).unwrap();
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].function.as_deref(), Some("FWindowsPlatformStackWalk::StackWalkAndDump()"));
+        assert_eq!(frames[0].location, None);
+        assert_eq!(frames[3].function, None);
+        assert!(read_log_callstack(Path::new("missing.log")).is_empty());
+    }
+
+    #[test]
+    fn handler_frames_dropped_and_recursion_collapsed() {
+        let frame_regex = regex::Regex::new(r"\[Callstack\]\s+(0x[0-9a-fA-F]+)\s+([^!\s]+)!(.*?)\s*(?:\[([^\]]*)\])?\s*$").unwrap();
+        let frames: Vec<StackFrame> = LOG.lines().skip(3).filter_map(|line| parse_frame(line, &frame_regex)).collect();
+        let cleaned: Vec<String> = clean(frames).iter().map(|frame| frame.to_string()).collect();
+        assert_eq!(cleaned, [
+            "UnrealEditor-Castle.dll!ADoor::Open() [D:\\Castle\\Door.cpp:42] (x2)",
+            "UnrealEditor-Engine.dll!0x00007ff6a1b2c400",
+        ]);
+    }
+
+    #[test]
+    fn utf16_crash_context() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n<FGenericCrashContext><RuntimeProperties>\n\
+            <CrashType>Assert</CrashType>\n\
+            <ErrorMessage>Assertion failed: Door &amp;&amp; Door-&gt;IsValid()</ErrorMessage>\n\
+            <PlatformName></PlatformName>\n\
+            <CallStack>UnrealEditor-Castle.dll!ADoor::Open() [D:\\Castle\\Door.cpp:42]\n\
+            UnrealEditor-Engine.dll!UnknownFunction []\n\
+            kernel32</CallStack>\n\
+            </RuntimeProperties></FGenericCrashContext>";
+        let bytes: Vec<u8> = vec![0xff, 0xfe].into_iter().chain(xml.encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        let context_file = std::env::temp_dir().join(format!("runtests-crash-context-{}.runtime-xml", std::process::id()));
+        fs::write(&context_file, bytes).unwrap();
+        let (crash_context, callstack) = read_crash_context(&context_file).unwrap();
+        fs::write(&context_file, "<Log/>").unwrap();
+        let not_a_context = read_crash_context(&context_file);
+        fs::remove_file(&context_file).unwrap();
+
+        assert_eq!(crash_context.crash_type.as_deref(), Some("Assert"));
+        assert_eq!(crash_context.error_message.as_deref(), Some("Assertion failed: Door && Door->IsValid()"));
+        assert_eq!(crash_context.platform, None);
+        let callstack: Vec<String> = callstack.iter().map(|frame| frame.to_string()).collect();
+        assert_eq!(callstack, ["UnrealEditor-Castle.dll!ADoor::Open() [D:\\Castle\\Door.cpp:42]", "UnrealEditor-Engine.dll!UnknownFunction", "kernel32"]);
+        assert!(not_a_context.is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::theme::Theme;
+use crate::{filters, TestPass, TestResult};
+
+// critical_tests must pass in every run, whatever the other thresholds say; a critical test that didn't run
+// or is missing from the report counts as failed
+#[derive(Debug, PartialEq)]
+pub struct CriticalFailure {
+    pub pattern: String, // the critical_tests entry
+    pub test: Option<String>, // None when nothing in the report matched the pattern
+    pub state: Option<TestResult>,
+}
+
+pub fn critical_failures(test_pass: &TestPass, critical_tests: &[String]) -> Vec<CriticalFailure> {
+    let mut failures = Vec::new();
+    for pattern in critical_tests {
+        let matching: Vec<_> = test_pass.tests.iter().filter(|test| filters::pattern_matches(pattern, test.full_test_path.as_str())).collect();
+        if matching.is_empty() {
+            failures.push(CriticalFailure { pattern: pattern.clone(), test: None, state: None });
+        }
+        failures.extend(matching.into_iter()
+            .filter(|test| test.state != TestResult::Success)
+            .map(|test| CriticalFailure { pattern: pattern.clone(), test: Some(test.full_test_path.clone()), state: Some(test.state) }));
+    }
+    failures
+}
+
+// on stderr below everything else, so it's the last thing in a CI log
+pub fn print_banner(failures: &[CriticalFailure], theme: &Theme) {
+    if failures.is_empty() {
+        return;
+    }
+    let rule = "=".repeat(60);
+    eprintln!("{}", theme.fail.paint(rule.as_str()));
+    eprintln!("{}", theme.fail.paint(format!("CRITICAL FAILURE: {} critical tests didn't pass", failures.len()).as_str()));
+    for failure in failures {
+        let line = match (&failure.test, failure.state) {
+            (Some(test), Some(state)) => format!("{:?} {}", state, test),
+            _ => format!("missing {} (not in the report)", failure.pattern),
+        };
+        eprintln!("{}{}", theme.spacer(), theme.fail.paint(line.as_str()));
+    }
+    eprintln!("{}", theme.fail.paint(rule.as_str()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_not_run_and_missing_critical_tests() {
+        let test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Boot.Loads", "state": "Success", "entries": [], "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Save.Writes", "state": "Fail", "entries": [], "warnings": 0, "errors": 1, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Save.Reads", "state": "NotRun", "entries": [], "warnings": 0, "errors": 0, "artifacts": []}"#);
+        let critical_tests = ["Project.Boot".to_owned(), "Project.Save".to_owned(), "Project.Login.Succeeds".to_owned()];
+        let failures = critical_failures(&test_pass, &critical_tests);
+        let described: Vec<(&str, Option<&str>)> = failures.iter().map(|failure| (failure.pattern.as_str(), failure.test.as_deref())).collect();
+        assert_eq!(described, [
+            ("Project.Save", Some("Project.Save.Writes")),
+            ("Project.Save", Some("Project.Save.Reads")),
+            ("Project.Login.Succeeds", None),
+        ]);
+        assert!(critical_failures(&test_pass, &critical_tests[..1]).is_empty());
+    }
+}
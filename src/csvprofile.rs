@@ -0,0 +1,146 @@
+use crate::TestPass;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+const FRAME_TIME_COLUMN: &str = "FrameTime";
+const TOP_STATS: usize = 5;
+
+// a `csvprofile` capture attached to a test, reduced to what's worth reading without opening the csv
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvProfileSummary {
+    pub path: String, // of the test
+    pub file: String, // relative to the report
+    pub frames: usize,
+    pub average_frame_time: f64,
+    pub p50_frame_time: f64,
+    pub p95_frame_time: f64,
+    pub p99_frame_time: f64,
+    pub top_stats: Vec<StatContribution>, // highest mean first
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatContribution {
+    pub name: String,
+    pub mean: f64,
+}
+
+// nearest rank, `sorted` can't be empty
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// the per-scope Exclusive/ columns say where the frame went; captures without them fall back to the
+// thread times, other columns (memory, counts) aren't in milliseconds and would drown those out
+fn contributor_columns(columns: &[&str]) -> Vec<usize> {
+    let exclusive: Vec<usize> = (0..columns.len()).filter(|index| columns[*index].starts_with("Exclusive/")).collect();
+    if !exclusive.is_empty() {
+        return exclusive;
+    }
+    (0..columns.len()).filter(|index| columns[*index] != FRAME_TIME_COLUMN && columns[*index].ends_with("Time")).collect()
+}
+
+// None for csvs that aren't csvprofile captures (no FrameTime column) or hold no frames
+fn summarize_csv(contents: &str, test_path: &str, file: &str) -> Option<CsvProfileSummary> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let columns: Vec<&str> = lines.next()?.split(',').map(str::trim).collect();
+    let frame_time_column = columns.iter().position(|column| *column == FRAME_TIME_COLUMN)?;
+    let contributors = contributor_columns(&columns);
+
+    let mut frame_times = Vec::new();
+    let mut totals = vec![(0.0, 0usize); contributors.len()];
+    for line in lines {
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        // the [HasHeaderRowAtEnd] metadata row, its values land in arbitrary columns
+        if cells[0].starts_with('[') {
+            continue;
+        }
+        // the header repeated at the end has no frame time either
+        let frame_time = match cells.get(frame_time_column).and_then(|cell| cell.parse::<f64>().ok()) {
+            Some(frame_time) => frame_time,
+            None => continue,
+        };
+        frame_times.push(frame_time);
+        for (total, column) in totals.iter_mut().zip(contributors.iter()) {
+            if let Some(value) = cells.get(*column).and_then(|cell| cell.parse::<f64>().ok()) {
+                total.0 += value;
+                total.1 += 1;
+            }
+        }
+    }
+    if frame_times.is_empty() {
+        return None;
+    }
+
+    let mut top_stats: Vec<StatContribution> = contributors.iter().zip(totals)
+        .filter(|(_, (_, count))| *count > 0)
+        .map(|(column, (sum, count))| StatContribution { name: columns[*column].to_owned(), mean: sum / count as f64 })
+        .collect();
+    top_stats.sort_by(|first, second| second.mean.total_cmp(&first.mean));
+    top_stats.truncate(TOP_STATS);
+
+    let average_frame_time = frame_times.iter().sum::<f64>() / frame_times.len() as f64;
+    frame_times.sort_by(f64::total_cmp);
+    Some(CsvProfileSummary {
+        path: test_path.to_owned(),
+        file: file.to_owned(),
+        frames: frame_times.len(),
+        average_frame_time,
+        p50_frame_time: percentile(&frame_times, 50.0),
+        p95_frame_time: percentile(&frame_times, 95.0),
+        p99_frame_time: percentile(&frame_times, 99.0),
+        top_stats,
+    })
+}
+
+// every csvprofile capture among the tests' artifacts, in test order
+pub fn summarize_profiles(test_pass: &TestPass, report_directory: &Path) -> Vec<CsvProfileSummary> {
+    let mut summaries = Vec::new();
+    for test in test_pass.tests.iter() {
+        let mut files: Vec<&String> = test.artifacts.iter()
+            .flat_map(|artifact| artifact.files.values())
+            .filter(|file| file.to_ascii_lowercase().ends_with(".csv"))
+            .collect();
+        files.sort();
+        files.dedup();
+        for file in files {
+            if let Ok(contents) = fs::read_to_string(report_directory.join(file)) {
+                summaries.extend(summarize_csv(&contents, test.full_test_path.as_str(), file));
+            }
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_frame_times_and_contributors() {
+        let mut csv = String::from("EVENTS,FrameTime,GameThreadTime,RenderThreadTime,Exclusive/GameThread/Physics,Exclusive/GameThread/Animation,MemoryFreeMB\n");
+        for frame in 1..=100 {
+            csv.push_str(format!(",{},10,8,{},2,4096\n", frame, frame as f64 / 10.0).as_str());
+        }
+        csv.push_str("EVENTS,FrameTime,GameThreadTime,RenderThreadTime,Exclusive/GameThread/Physics,Exclusive/GameThread/Animation,MemoryFreeMB\n");
+        csv.push_str("[HasHeaderRowAtEnd],1,[platform],Windows\n");
+
+        let summary = summarize_csv(&csv, "Project.Perf.Castle", "Profiling/Castle.csv").unwrap();
+        assert_eq!(summary.frames, 100);
+        assert_eq!(summary.average_frame_time, 50.5);
+        assert_eq!(summary.p50_frame_time, 50.0);
+        assert_eq!(summary.p95_frame_time, 95.0);
+        assert_eq!(summary.p99_frame_time, 99.0);
+        let top_stats: Vec<&str> = summary.top_stats.iter().map(|stat| stat.name.as_str()).collect();
+        assert_eq!(top_stats, vec!["Exclusive/GameThread/Physics", "Exclusive/GameThread/Animation"]);
+        assert!((summary.top_stats[0].mean - 5.05).abs() < 1e-9);
+
+        let without_scopes = summarize_csv("FrameTime,GameThreadTime,RenderThreadTime,MemoryFreeMB\n16,10,12,4096\n", "Project.Perf.Castle", "Castle.csv").unwrap();
+        let top_stats: Vec<&str> = without_scopes.top_stats.iter().map(|stat| stat.name.as_str()).collect();
+        assert_eq!(top_stats, vec!["RenderThreadTime", "GameThreadTime"]);
+
+        assert!(summarize_csv("Name,Value\nFPS,60\n", "Project.Perf.Castle", "metrics.csv").is_none());
+        assert!(summarize_csv("FrameTime,GameThreadTime\n", "Project.Perf.Castle", "empty.csv").is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::lang::{self, Lang, Message};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+
+#[derive(Debug, Clone)]
+pub enum DisplayZone {
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+// "local" (the default), "utc" or a fixed offset such as "+09:00"
+pub fn parse_display_zone(name: Option<&str>) -> Result<DisplayZone, String> {
+    let name = match name {
+        Some(name) => name.trim(),
+        None => return Ok(DisplayZone::Local),
+    };
+    if name.eq_ignore_ascii_case("local") || name.is_empty() {
+        return Ok(DisplayZone::Local);
+    }
+    if name.eq_ignore_ascii_case("utc") {
+        return Ok(DisplayZone::Utc);
+    }
+
+    let invalid = || format!("invalid timezone {}, expected local, utc or an offset like +09:00", name);
+    let (sign, digits) = match (name.strip_prefix('+'), name.strip_prefix('-')) {
+        (Some(digits), _) => (1, digits),
+        (_, Some(digits)) => (-1, digits),
+        _ => return Err(invalid()),
+    };
+    let mut parts = digits.splitn(2, ':');
+    let hours: u32 = parts.next().and_then(|hours| hours.parse().ok()).filter(|hours| *hours < 24).ok_or_else(invalid)?;
+    let minutes: u32 = parts.next().map_or(Some(0), |minutes| minutes.parse().ok()).filter(|minutes| *minutes < 60).ok_or_else(invalid)?;
+    let seconds = (hours * 3600 + minutes * 60) as i32;
+    FixedOffset::east_opt(sign * seconds).map(DisplayZone::Fixed).ok_or_else(|| format!("timezone offset {} is out of range", name))
+}
+
+// unreal writes report_created_on with FDateTime::ToString (local time of the machine that ran the tests,
+// 2023.10.01-12.34.56) and entry timestamps either the same way or as iso 8601 utc
+pub fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    for format in ["%Y.%m.%d-%H.%M.%S%.f", "%Y.%m.%d-%H.%M.%S", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(text, format) {
+            return Local.from_local_datetime(&naive).earliest().map(|local| local.with_timezone(&Utc));
+        }
+    }
+    None
+}
+
+pub fn format_datetime(datetime: DateTime<Utc>, zone: &DisplayZone) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+    match zone {
+        DisplayZone::Local => datetime.with_timezone(&Local).format(FORMAT).to_string(),
+        DisplayZone::Utc => datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        DisplayZone::Fixed(offset) => datetime.with_timezone(offset).format(FORMAT).to_string(),
+    }
+}
+
+// timestamps that can't be parsed are shown as unreal wrote them
+pub fn format_timestamp(text: &str, zone: &DisplayZone) -> String {
+    match parse_timestamp(text) {
+        Some(datetime) => format_datetime(datetime, zone),
+        None => text.to_owned(),
+    }
+}
+
+pub fn relative_age(then: DateTime<Utc>, now: DateTime<Utc>, lang: Lang) -> String {
+    let seconds = now.signed_duration_since(then).num_seconds();
+    match seconds {
+        s if s < 0 => lang::text(lang, Message::InTheFuture).to_owned(),
+        s if s < 60 => lang::text(lang, Message::JustNow).to_owned(),
+        s if s < 60 * 60 => lang::format(lang, Message::MinutesAgo, &[&(s / 60)]),
+        s if s < 60 * 60 * 24 => lang::format(lang, Message::HoursAgo, &[&(s / 3600), &(s % 3600 / 60)]),
+        s => lang::format(lang, Message::DaysAgo, &[&(s / (60 * 60 * 24))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_zones() {
+        assert!(matches!(parse_display_zone(None), Ok(DisplayZone::Local)));
+        assert!(matches!(parse_display_zone(Some("UTC")), Ok(DisplayZone::Utc)));
+        match parse_display_zone(Some("-03:30")).unwrap() {
+            DisplayZone::Fixed(offset) => assert_eq!(offset.local_minus_utc(), -(3 * 3600 + 30 * 60)),
+            zone => panic!("unexpected {:?}", zone),
+        }
+        for invalid in ["CET", "東京", "+9x", "+99:00", "+09:75", "+", "+-3"] {
+            assert!(parse_display_zone(Some(invalid)).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn iso_timestamps_in_another_zone() {
+        let zone = parse_display_zone(Some("+09:00")).unwrap();
+        assert_eq!(format_timestamp("2023-10-01T12:34:56Z", &zone), "2023-10-01 21:34:56 +09:00");
+        assert_eq!(format_timestamp("not a time", &zone), "not a time");
+        assert!(parse_timestamp("2023.10.01-12.34.56").is_some());
+    }
+
+    #[test]
+    fn relative_ages() {
+        let now = Utc.with_ymd_and_hms(2023, 10, 2, 12, 0, 0).unwrap();
+        let age = |then, lang| relative_age(Utc.with_ymd_and_hms(2023, 10, 2, 10, then, 0).unwrap(), now, lang);
+        assert_eq!(age(45, Lang::En), "1h 15m ago");
+        assert_eq!(age(45, Lang::Ja), "1時間15分前");
+        assert_eq!(relative_age(now, now, Lang::En), "just now");
+        assert_eq!(relative_age(Utc.with_ymd_and_hms(2023, 9, 29, 11, 0, 0).unwrap(), now, Lang::En), "3d ago");
+        assert_eq!(relative_age(now + chrono::Duration::minutes(5), now, Lang::En), "in the future");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::datetime::{self, DisplayZone};
+use crate::{matching_ignore_regex, EntryType, Test, TestPass, TestResult};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn status_name(state: TestResult) -> &'static str {
+    match state {
+        TestResult::Success => "Success",
+        TestResult::Fail => "Fail",
+        TestResult::NotRun => "NotRun",
+        TestResult::InProcess => "InProcess",
+        TestResult::NotEnoughParticipants => "NotEnoughParticipants",
+    }
+}
+
+// None keeps the timestamps exactly as the report has them
+fn write_test(details: &mut String, test: &Test, ignore_regexes: &[String], zone: Option<&DisplayZone>) {
+    writeln!(details, "{:>12} {} ({:.3}s)", status_name(test.state), test.full_test_path, test.duration).unwrap();
+
+    for entry in test.entries.iter() {
+        let label = match entry.event.entry_type {
+            _ if entry.expected => "Expected",
+            EntryType::Info => "Info",
+            EntryType::Warning => "Warning",
+            EntryType::Error => "Error",
+        };
+        let timestamp = match zone {
+            Some(zone) => datetime::format_timestamp(entry.timestamp.as_str(), zone),
+            None => entry.timestamp.clone(),
+        };
+        write!(details, "{:>12} [{}] {}", label, timestamp, entry.event.message).unwrap();
+        if let Some(ignore_regex) = matching_ignore_regex(entry.event.message.as_str(), ignore_regexes) {
+            write!(details, " (ignored by {})", ignore_regex).unwrap();
+        }
+        writeln!(details).unwrap();
+        if !entry.filename.is_empty() {
+            writeln!(details, "{:>12} {}:{}", "", entry.filename, entry.line_number).unwrap();
+        }
+    }
+}
+
+// the complete entry list for every test, nothing is filtered out; ignored entries are annotated
+// with the pattern that hid them from the console
+pub fn write_details_file(path: &Path, test_pass: &TestPass, ignore_regexes: &[String], zone: &DisplayZone) -> std::io::Result<()> {
+    let mut details = String::new();
+    for test in test_pass.tests.iter() {
+        write_test(&mut details, test, ignore_regexes, Some(zone));
+    }
+    writeln!(details, "{} passed, {} failed, {} other", test_pass.succeeded, test_pass.failed, test_pass.not_run + test_pass.succeeded_with_warnings).unwrap();
+    fs::write(path, details)
+}
+
+// test paths have spaces and the odd character windows won't have in a file name
+fn log_file_name(full_test_path: &str) -> String {
+    let name: String = full_test_path.chars().map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' }).collect();
+    format!("{}.txt", name)
+}
+
+// one file per failing test with the same entry list as the details file but the report's own timestamps,
+// small enough to attach to a bug ticket
+pub fn write_split_logs(directory: &Path, test_pass: &TestPass, ignore_regexes: &[String]) -> std::io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+    let mut written = Vec::new();
+    for test in test_pass.tests.iter().filter(|test| test.state == TestResult::Fail) {
+        let mut log = String::new();
+        write_test(&mut log, test, ignore_regexes, None);
+        let path = directory.join(log_file_name(test.full_test_path.as_str()));
+        fs::write(&path, log)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn details_keep_ignored_entries() {
+        let mut test_pass = crate::test_pass_from_tests(r#"{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Fail", "duration": 1.5, "warnings": 1, "errors": 1,
+            "artifacts": [], "entries": [
+                {"event": {"type": "Warning", "message": "LogViewport: resized", "context": "", "artifact": ""},
+                    "filename": "", "lineNumber": 0, "timestamp": "2023-10-01T12:34:56Z"},
+                {"event": {"type": "Error", "message": "Door stuck", "context": "", "artifact": ""},
+                    "filename": "Door.cpp", "lineNumber": 12, "timestamp": "2023-10-01T12:34:57Z"}]}"#);
+        test_pass.failed = 1;
+        let path = std::env::temp_dir().join(format!("runtests-details-{}.txt", std::process::id()));
+
+        write_details_file(&path, &test_pass, &["LogViewport:".to_owned()], &DisplayZone::Utc).unwrap();
+        let details = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = details.lines().collect();
+        assert_eq!(lines, [
+            "        Fail Project.Door (1.500s)",
+            "     Warning [2023-10-01 12:34:56 UTC] LogViewport: resized (ignored by LogViewport:)",
+            "       Error [2023-10-01 12:34:57 UTC] Door stuck",
+            "             Door.cpp:12",
+            "0 passed, 1 failed, 0 other",
+        ]);
+
+        let directory = std::env::temp_dir().join(format!("runtests-split-logs-{}", std::process::id()));
+        let written = write_split_logs(&directory, &test_pass, &[]).unwrap();
+        let log = fs::read_to_string(&written[0]).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(written, [directory.join("Project.Door.txt")]);
+        assert!(log.contains("       Error [2023-10-01T12:34:57Z] Door stuck"));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::lang::{self, Lang, Message};
+use crate::{http, should_ignore_message, EntryType, RunMetadata, TestPass, TestResult};
+use serde::Deserialize;
+
+// with [discord] every run posts an embed to a channel webhook
+#[derive(Debug, Deserialize)]
+pub struct DiscordConfiguration {
+    #[serde(default = "default_webhook_env")]
+    pub webhook_env: String,        // environment variable holding the webhook url, which is all it takes to post
+    pub report_url: Option<String>, // where CI publishes the html report, {session} is replaced by --session-name
+    #[serde(default)]
+    pub only_on_failure: bool,
+}
+
+pub(crate) fn default_webhook_env() -> String {
+    "DISCORD_WEBHOOK_URL".to_owned()
+}
+
+const MAX_FAILURE_FIELDS: usize = 10;
+
+// discord rejects the whole message when a field is over its limit
+const MAX_FIELD_NAME: usize = 256;
+const MAX_FIELD_VALUE: usize = 1024;
+
+const COLOR_PASSED: u32 = 0x2ecc71;
+const COLOR_FAILED: u32 = 0xe74c3c;
+const COLOR_CRASHED: u32 = 0x992d22;
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+pub fn should_notify(config: &DiscordConfiguration, test_pass: &TestPass, run_metadata: &RunMetadata) -> bool {
+    !config.only_on_failure || test_pass.failed > 0 || run_metadata.crashed
+}
+
+pub fn embed_message(config: &DiscordConfiguration, test_pass: &TestPass, run_metadata: &RunMetadata, ignore_regexes: &[String], lang: Lang) -> serde_json::Value {
+    let color = if run_metadata.crashed {
+        COLOR_CRASHED
+    } else if test_pass.failed > 0 {
+        COLOR_FAILED
+    } else {
+        COLOR_PASSED
+    };
+    let mut description = lang::format(lang, Message::Summary, &[&test_pass.succeeded, &test_pass.failed, &(test_pass.not_run + test_pass.succeeded_with_warnings)]);
+    if run_metadata.crashed {
+        description = format!("**{}**\n{}", lang::text(lang, Message::CrashedDuringExecution), description);
+    }
+
+    let failed: Vec<&crate::Test> = test_pass.tests.iter().filter(|test| test.state == TestResult::Fail).collect();
+    let mut fields: Vec<serde_json::Value> = failed.iter().take(MAX_FAILURE_FIELDS)
+        .map(|test| {
+            let first_error = test.entries.iter()
+                .find(|entry| matches!(entry.event.entry_type, EntryType::Error) && !entry.expected && !should_ignore_message(entry.event.message.as_str(), ignore_regexes))
+                .map_or_else(|| "-".to_owned(), |entry| entry.event.message.to_string());
+            serde_json::json!({ "name": truncate(test.full_test_path.as_str(), MAX_FIELD_NAME), "value": truncate(first_error.as_str(), MAX_FIELD_VALUE) })
+        })
+        .collect();
+    if failed.len() > MAX_FAILURE_FIELDS {
+        fields.push(serde_json::json!({ "name": "…", "value": lang::format(lang, Message::AndMoreFailedTests, &[&(failed.len() - MAX_FAILURE_FIELDS)]) }));
+    }
+
+    let mut title = lang::text(lang, Message::TestReport).to_owned();
+    if let Some(session_name) = &run_metadata.session_name {
+        title = format!("{}: {}", title, session_name);
+    }
+    let mut embed = serde_json::json!({ "title": truncate(title.as_str(), MAX_FIELD_NAME), "description": description, "color": color, "fields": fields });
+    if let Some(report_url) = &config.report_url {
+        embed["url"] = serde_json::Value::String(report_url.replace("{session}", run_metadata.session_name.as_deref().unwrap_or_default()));
+    }
+    let footer: Vec<String> = run_metadata.changelist.iter().map(|changelist| lang::format(lang, Message::AtChangelist, &[changelist]))
+        .chain(run_metadata.git.iter().map(|git_info| lang::format(lang, Message::AtCommit, &[git_info])))
+        .collect();
+    if !footer.is_empty() {
+        embed["footer"] = serde_json::json!({ "text": footer.join(", ") });
+    }
+    serde_json::json!({ "embeds": [embed] })
+}
+
+pub fn post_message(config: &DiscordConfiguration, message: &serde_json::Value) -> Result<(), String> {
+    let webhook_url = std::env::var(config.webhook_env.as_str()).map_err(|_| format!("{} is unset", config.webhook_env))?;
+    http::send_json("POST", webhook_url.as_str(), &[], message)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_become_fields() {
+        let mut test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "entries": [], "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Fail", "warnings": 0, "errors": 1, "artifacts": [],
+                    "entries": [{"event": {"type": "Error", "message": "Door stuck", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]}"#);
+        test_pass.succeeded = 1;
+        test_pass.failed = 1;
+        let run_metadata = RunMetadata { session_name: Some("nightly".to_owned()), ..RunMetadata::default() };
+        let config: DiscordConfiguration = toml::from_str(r#"report_url = "https://ci.example.com/{session}/report.html""#).unwrap();
+
+        let message = embed_message(&config, &test_pass, &run_metadata, &[], Lang::En);
+        let embed = &message["embeds"][0];
+        assert_eq!(embed["title"], "Test report: nightly");
+        assert_eq!(embed["color"], COLOR_FAILED);
+        assert_eq!(embed["url"], "https://ci.example.com/nightly/report.html");
+        assert_eq!(embed["fields"], serde_json::json!([{ "name": "Project.Door.Closes", "value": "Door stuck" }]));
+        assert!(should_notify(&DiscordConfiguration { only_on_failure: true, ..config }, &test_pass, &run_metadata));
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+// the directory may not exist before the first run, its nearest existing ancestor is on the same volume
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let absolute = std::env::current_dir().map_or_else(|_| path.to_path_buf(), |current_dir| current_dir.join(path));
+    absolute.ancestors().find(|ancestor| ancestor.exists()).unwrap_or(&absolute).to_path_buf()
+}
+
+#[cfg(windows)]
+fn free_bytes_command(path: &Path) -> Command {
+    let script = format!("[System.IO.DriveInfo]::new([System.IO.Path]::GetPathRoot('{}')).AvailableFreeSpace", path.display().to_string().replace('\'', "''"));
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", script.as_str()]);
+    command
+}
+
+#[cfg(not(windows))]
+fn free_bytes_command(path: &Path) -> Command {
+    let mut command = Command::new("df");
+    command.arg("-Pk").arg(path);
+    command
+}
+
+// powershell prints the byte count, df -P a header and then one line whose fourth column is the available 1k blocks
+fn parse_free_bytes(output: &str) -> Option<u64> {
+    let output = output.trim();
+    if let Ok(bytes) = output.parse() {
+        return Some(bytes);
+    }
+    let kilobytes: u64 = output.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+// None when the free space can't be told, the run goes ahead rather than failing on a missing tool
+pub fn free_bytes(path: &Path) -> Option<u64> {
+    let output = free_bytes_command(&existing_ancestor(path)).output().ok().filter(|output| output.status.success())?;
+    parse_free_bytes(String::from_utf8_lossy(&output.stdout).as_ref())
+}
+
+// Err names the directory and how much is free when it's below min_free_disk_gb
+pub fn check_free_space(path: &Path, min_free_disk_gb: f64) -> Result<Option<f64>, String> {
+    let free_gb = match free_bytes(path) {
+        Some(bytes) => bytes as f64 / BYTES_PER_GB,
+        None => return Ok(None),
+    };
+    if free_gb < min_free_disk_gb {
+        return Err(format!("{} has {:.1} GB free, min_free_disk_gb is {}", path.display(), free_gb, min_free_disk_gb));
+    }
+    Ok(Some(free_gb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn df_and_powershell_output_are_read() {
+        let df = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n/dev/sda1        102400000  61440000  40960000      60% /\n";
+        assert_eq!(parse_free_bytes(df), Some(40960000 * 1024));
+        assert_eq!(parse_free_bytes("53687091200\r\n"), Some(53687091200));
+        assert_eq!(parse_free_bytes("df: /missing: No such file or directory"), None);
+    }
+}
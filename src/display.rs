@@ -0,0 +1,149 @@
+use crate::runner::TestSelection;
+use crate::{instances, ArtifactType, TestPass};
+use serde::Deserialize;
+use std::process::Command;
+
+// [display] renders with a real rhi at a fixed resolution, screenshot comparisons only match at the size they
+// were captured at; without it the editor runners pass -NullRHI
+#[derive(Debug, Deserialize)]
+pub struct DisplayConfiguration {
+    #[serde(default)]
+    pub null_rhi: bool, // for configs that only render some suites, see overrides
+    #[serde(default = "default_res_x")]
+    pub res_x: u32,
+    #[serde(default = "default_res_y")]
+    pub res_y: u32,
+    #[serde(default = "default_windowed")]
+    pub windowed: bool, // fullscreen otherwise
+    #[serde(default = "default_force_res")]
+    pub force_res: bool, // keeps the resolution even when it's larger than the desktop
+    #[serde(default)]
+    pub overrides: Vec<DisplayOverride>,
+}
+
+// applied when every test an engine process is launched with is under tests, ie with --isolate or a run_tests
+// that names a single suite; the first matching override wins
+#[derive(Debug, Deserialize)]
+pub struct DisplayOverride {
+    pub tests: String, // a test path prefix, ie Project.Rendering.
+    pub null_rhi: Option<bool>,
+    pub res_x: Option<u32>,
+    pub res_y: Option<u32>,
+    pub windowed: Option<bool>,
+    pub force_res: Option<bool>,
+}
+
+pub(crate) fn default_res_x() -> u32 {
+    1280
+}
+
+pub(crate) fn default_res_y() -> u32 {
+    720
+}
+
+pub(crate) fn default_windowed() -> bool {
+    true
+}
+
+pub(crate) fn default_force_res() -> bool {
+    true
+}
+
+fn matching_override<'a>(config: &'a DisplayConfiguration, selection: &TestSelection) -> Option<&'a DisplayOverride> {
+    let run_tests = match selection {
+        TestSelection::Tests(run_tests) => run_tests,
+        TestSelection::Filter(_) => return None,
+    };
+    let tests = instances::individual_tests(run_tests);
+    config.overrides.iter().find(|display_override| !tests.is_empty() && tests.iter().all(|test| test.starts_with(display_override.tests.as_str())))
+}
+
+// what the engine is launched with to render (or not), null_rhi_by_default is the runner's behaviour without [display]
+pub fn display_args(display: Option<&DisplayConfiguration>, selection: &TestSelection, extra_args: &[String], null_rhi_by_default: bool) -> Vec<String> {
+    let display = match display {
+        Some(display) => display,
+        None if null_rhi_by_default => return vec!["-NullRHI".to_owned()],
+        None => return Vec::new(),
+    };
+    let display_override = matching_override(display, selection);
+    if display_override.and_then(|display_override| display_override.null_rhi).unwrap_or(display.null_rhi) {
+        return vec!["-NullRHI".to_owned()];
+    }
+    // -nullrhi in extra_args wins, a resolution means nothing without a renderer
+    if extra_args.iter().any(|arg| arg.eq_ignore_ascii_case("-nullrhi")) {
+        return Vec::new();
+    }
+    let res_x = display_override.and_then(|display_override| display_override.res_x).unwrap_or(display.res_x);
+    let res_y = display_override.and_then(|display_override| display_override.res_y).unwrap_or(display.res_y);
+    let mut args = vec![format!("-ResX={}", res_x), format!("-ResY={}", res_y)];
+    if display_override.and_then(|display_override| display_override.windowed).unwrap_or(display.windowed) {
+        args.push("-Windowed".to_owned());
+    } else {
+        args.push("-FullScreen".to_owned());
+    }
+    if display_override.and_then(|display_override| display_override.force_res).unwrap_or(display.force_res) {
+        args.push("-ForceRes".to_owned());
+    }
+    args
+}
+
+// -nullrhi anywhere in the command, remote runners pass the engine's command line as one argument
+pub fn runs_null_rhi(command: &Command) -> bool {
+    command.get_args().any(|arg| arg.to_string_lossy().split_whitespace().any(|word| word.eq_ignore_ascii_case("-nullrhi")))
+}
+
+// tests that compared screenshots in a report, to know them before the next run
+pub fn comparison_tests(test_pass: &TestPass) -> Vec<String> {
+    test_pass.tests.iter()
+        .filter(|test| test.artifacts.iter().any(|artifact| artifact.artifact_type == ArtifactType::Comparison))
+        .map(|test| test.full_test_path.clone())
+        .collect()
+}
+
+// the screenshot tests a selection runs, a selected prefix runs every screenshot test under it
+pub fn selected_screenshot_tests(selection: &TestSelection, screenshot_tests: &[String]) -> Vec<String> {
+    let tests = match selection {
+        TestSelection::Tests(run_tests) => instances::individual_tests(run_tests),
+        TestSelection::Filter(_) => return Vec::new(),
+    };
+    screenshot_tests.iter()
+        .filter(|screenshot_test| tests.iter().any(|test| screenshot_test.starts_with(test.as_str()) || test.starts_with(screenshot_test.as_str())))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suites_override_the_display() {
+        let display: DisplayConfiguration = toml::from_str(r#"
+            null_rhi = true
+            [[overrides]]
+            tests = "Project.Rendering."
+            null_rhi = false
+            res_x = 1920
+            res_y = 1080"#).unwrap();
+        let rendering = TestSelection::Tests("Project.Rendering.Water+Project.Rendering.Sky".to_owned());
+        assert_eq!(display_args(Some(&display), &rendering, &[], true), ["-ResX=1920", "-ResY=1080", "-Windowed", "-ForceRes"]);
+        assert_eq!(display_args(Some(&display), &TestSelection::Tests("Project.Rendering.Water+Project.Door".to_owned()), &[], true), ["-NullRHI"]);
+        assert_eq!(display_args(Some(&display), &TestSelection::Filter("Smoke"), &[], true), ["-NullRHI"]);
+        assert!(display_args(Some(&display), &rendering, &["-nullrhi".to_owned()], true).is_empty());
+        assert_eq!(display_args(None, &rendering, &[], true), ["-NullRHI"]);
+        assert!(display_args(None, &rendering, &[], false).is_empty());
+    }
+
+    #[test]
+    fn screenshot_tests_under_a_selection() {
+        let screenshot_tests = ["Project.Rendering.Water".to_owned(), "Project.Rendering.Sky".to_owned()];
+        assert_eq!(selected_screenshot_tests(&TestSelection::Tests("Project.Rendering.".to_owned()), &screenshot_tests), screenshot_tests);
+        assert_eq!(selected_screenshot_tests(&TestSelection::Tests("Project.Door Project.Rendering.Sky".to_owned()), &screenshot_tests), ["Project.Rendering.Sky"]);
+        assert!(selected_screenshot_tests(&TestSelection::Tests("Project.Door".to_owned()), &screenshot_tests).is_empty());
+
+        let mut command = Command::new("ssh");
+        command.args(["testlab-01", "UnrealEditor Castle.uproject -game -nullrhi"]);
+        assert!(runs_null_rhi(&command));
+        assert!(!runs_null_rhi(Command::new("UnrealEditor").args(["Castle.uproject", "-ResX=1280"])));
+    }
+}
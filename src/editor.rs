@@ -0,0 +1,74 @@
+use colored::*;
+use std::process::Command;
+
+// opening hundreds of editor tabs after a bad run isn't helpful
+const MAX_OPEN_LOCATIONS: usize = 10;
+
+fn editor_command(editor: &str, filename: &str, line_number: i32) -> Option<Command> {
+    let mut command;
+    match editor {
+        "vscode" | "code" => {
+            command = Command::new("code");
+            command.args(["--goto", format!("{}:{}", filename, line_number).as_str()]);
+        }
+        "rider" | "clion" => {
+            command = Command::new(editor);
+            command.args(["--line", line_number.to_string().as_str(), filename]);
+        }
+        "sublime" | "subl" => {
+            command = Command::new("subl");
+            command.arg(format!("{}:{}", filename, line_number));
+        }
+        "notepad++" => {
+            command = Command::new("notepad++");
+            command.args([format!("-n{}", line_number).as_str(), filename]);
+        }
+        _ => return None,
+    }
+    Some(command)
+}
+
+pub fn open_locations(editor: &str, locations: &[(String, i32)]) {
+    if locations.len() > MAX_OPEN_LOCATIONS {
+        eprintln!("{}", format!("{} failing locations, only opening the first {}", locations.len(), MAX_OPEN_LOCATIONS).yellow());
+    }
+
+    for (filename, line_number) in locations.iter().take(MAX_OPEN_LOCATIONS) {
+        let mut command = match editor_command(editor, filename, *line_number) {
+            Some(command) => command,
+            None => {
+                eprintln!("{}{} (expected vscode, rider, clion, sublime or notepad++)", "unknown editor: ".red(), editor);
+                return;
+            }
+        };
+        eprintln!("opening {}:{}", filename, line_number);
+        if let Err(error) = command.spawn() {
+            eprintln!("{}{}", "failed to start editor: ".red(), error);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(editor: &str) -> Option<Vec<String>> {
+        let command = editor_command(editor, "Source/Door.cpp", 42)?;
+        Some(std::iter::once(command.get_program()).chain(command.get_args()).map(|arg| arg.to_string_lossy().into_owned()).collect())
+    }
+
+    #[test]
+    fn each_editor_gets_the_location_its_way() {
+        assert_eq!(command_line("vscode").unwrap(), ["code", "--goto", "Source/Door.cpp:42"]);
+        assert_eq!(command_line("rider").unwrap(), ["rider", "--line", "42", "Source/Door.cpp"]);
+        assert_eq!(command_line("clion").unwrap(), ["clion", "--line", "42", "Source/Door.cpp"]);
+        assert_eq!(command_line("sublime").unwrap(), ["subl", "Source/Door.cpp:42"]);
+        assert_eq!(command_line("notepad++").unwrap(), ["notepad++", "-n42", "Source/Door.cpp"]);
+    }
+
+    #[test]
+    fn unknown_editor() {
+        assert!(command_line("vim").is_none());
+    }
+}
@@ -0,0 +1,274 @@
+#[cfg(feature = "runner")]
+use crate::runner::RunnerKind;
+use crate::{TestConfiguration, TestPass};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Engine/Build/Build.version, written by every installed or source engine
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BuildVersion {
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub patch_version: u32,
+    #[allow(dead_code)] // not shown by validate
+    #[serde(default)]
+    pub changelist: u32,
+    #[serde(default)]
+    pub branch_name: String,
+}
+
+impl fmt::Display for BuildVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major_version, self.minor_version, self.patch_version)?;
+        if !self.branch_name.is_empty() {
+            write!(f, " ({})", self.branch_name)?;
+        }
+        Ok(())
+    }
+}
+
+// path_to_unrealengine points into Engine/Binaries/<platform>/, Build.version lives under Engine/Build/
+pub fn build_version_path(path_to_unrealengine: &str) -> Option<PathBuf> {
+    Path::new(path_to_unrealengine).ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name.eq_ignore_ascii_case("Engine")))
+        .map(|engine_directory| engine_directory.join("Build").join("Build.version"))
+}
+
+pub fn read_build_version(path_to_unrealengine: &str) -> Result<BuildVersion, String> {
+    let path = build_version_path(path_to_unrealengine).ok_or_else(|| format!("no Engine directory above {}", path_to_unrealengine))?;
+    let bytes = fs::read(&path).map_err(|error| format!("failed to read {}: {}", path.display(), error))?;
+    serde_json::from_str(crate::utf_from_bytes(&bytes).as_str()).map_err(|error| format!("invalid {}: {}", path.display(), error))
+}
+
+// mismatches between the detected engine and what the config or report assume
+pub fn compatibility_warnings(version: &BuildVersion, config: &TestConfiguration) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let executable = Path::new(config.path_to_unrealengine.as_str()).file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().to_lowercase());
+    match version.major_version {
+        4 if executable.starts_with("unrealeditor") => warnings.push(format!("engine {} is UE4 but path_to_unrealengine is a UE5 executable, UE4's editor is UE4Editor", version)),
+        5 if executable.starts_with("ue4editor") => warnings.push(format!("engine {} is UE5 but path_to_unrealengine is a UE4 executable, UE5's editor is UnrealEditor", version)),
+        4 | 5 => {}
+        _ => warnings.push(format!("engine {} is neither UE4 nor UE5, report parsing and command lines are only known to work with those", version)),
+    }
+    #[cfg(feature = "runner")]
+    {
+        let runner_kind = config.runner.as_ref().map_or(RunnerKind::Editor, |runner_config| runner_config.kind);
+        if version.major_version == 4 && runner_kind == RunnerKind::EditorCmd && config.runner.as_ref().is_none_or(|runner_config| runner_config.executable.is_none()) {
+            warnings.push("the editor-cmd runner derives its executable from path_to_unrealengine, set [runner] executable if UE4Editor-Cmd isn't next to it".to_owned());
+        }
+    }
+    warnings
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProjectFile {
+    #[serde(default)]
+    engine_association: String,
+}
+
+// LauncherInstalled.dat, the epic launcher's list of installed engines (and everything else it installed)
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LauncherInstalled {
+    installation_list: Vec<LauncherInstallation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LauncherInstallation {
+    install_location: String,
+    app_name: String,
+}
+
+fn launcher_installed_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        let program_data = std::env::var("PROGRAMDATA").unwrap_or_else(|_| "C:/ProgramData".to_owned());
+        Some(Path::new(program_data.as_str()).join("Epic").join("UnrealEngineLauncher").join("LauncherInstalled.dat"))
+    } else if cfg!(target_os = "macos") {
+        let home = std::env::var("HOME").ok()?;
+        Some(Path::new(home.as_str()).join("Library/Application Support/Epic/UnrealEngineLauncher/LauncherInstalled.dat"))
+    } else {
+        None
+    }
+}
+
+// the launcher names engine installs UE_5.3
+fn launcher_engine(launcher_installed: &str, association: &str) -> Option<PathBuf> {
+    let installed: LauncherInstalled = serde_json::from_str(launcher_installed).ok()?;
+    let app_name = format!("UE_{}", association);
+    installed.installation_list.into_iter()
+        .find(|installation| installation.app_name.eq_ignore_ascii_case(app_name.as_str()))
+        .map(|installation| PathBuf::from(installation.install_location))
+}
+
+// the value of one name in `reg query` output: `    InstalledDirectory    REG_SZ    C:\Program Files\Epic Games\UE_5.3`
+fn registry_value(reg_output: &str, name: &str) -> Option<String> {
+    reg_output.lines().find_map(|line| {
+        let mut parts = line.trim().splitn(3, "    ");
+        if !parts.next()?.eq_ignore_ascii_case(name) || !parts.next()?.trim().starts_with("REG_") {
+            return None;
+        }
+        Some(parts.next()?.trim().to_owned())
+    })
+}
+
+fn query_registry(key: &str, name: &str) -> Option<String> {
+    let output = std::process::Command::new("reg").args(["query", key, "/v", name]).output().ok().filter(|output| output.status.success())?;
+    registry_value(String::from_utf8_lossy(&output.stdout).as_ref(), name)
+}
+
+// source builds register themselves under a guid, in the registry on windows and in Install.ini elsewhere
+fn install_ini_value(install_ini: &str, association: &str) -> Option<PathBuf> {
+    let mut in_installations = false;
+    for line in install_ini.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_installations = line.eq_ignore_ascii_case("[Installations]");
+        } else if let Some((name, value)) = line.split_once('=').filter(|_| in_installations) {
+            if name.trim().eq_ignore_ascii_case(association) {
+                return Some(PathBuf::from(value.trim()));
+            }
+        }
+    }
+    None
+}
+
+fn engine_root(association: &str, project_directory: &Path) -> Result<PathBuf, String> {
+    // no association means the project sits inside a source engine's tree
+    if association.is_empty() {
+        return project_directory.ancestors()
+            .find(|ancestor| ancestor.join("Engine").join("Build").join("Build.version").is_file())
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("the project has no EngineAssociation and no engine above {}", project_directory.display()));
+    }
+    let is_source_build = association.starts_with('{');
+    if cfg!(windows) {
+        let found = if is_source_build {
+            query_registry("HKCU\\SOFTWARE\\Epic Games\\Unreal Engine\\Builds", association)
+        } else {
+            query_registry(format!("HKLM\\SOFTWARE\\EpicGames\\Unreal Engine\\{}", association).as_str(), "InstalledDirectory")
+        };
+        if let Some(found) = found {
+            return Ok(PathBuf::from(found));
+        }
+    } else if is_source_build {
+        let home = std::env::var("HOME").map_err(|_| "HOME is unset".to_owned())?;
+        let install_ini = Path::new(home.as_str()).join(".config/Epic/UnrealEngine/Install.ini");
+        if let Some(found) = fs::read_to_string(&install_ini).ok().and_then(|contents| install_ini_value(contents.as_str(), association)) {
+            return Ok(found);
+        }
+    }
+    if !is_source_build {
+        let launcher_installed = launcher_installed_path().and_then(|path| fs::read_to_string(path).ok());
+        if let Some(found) = launcher_installed.and_then(|contents| launcher_engine(contents.as_str(), association)) {
+            return Ok(found);
+        }
+    }
+    Err(format!("EngineAssociation {} isn't a registered engine on this machine", association))
+}
+
+// the editor under an engine root, UE4's if that's the one that's there
+fn editor_binary(engine_root: &Path) -> PathBuf {
+    let binaries = engine_root.join("Engine").join("Binaries");
+    let candidates: Vec<PathBuf> = ["UnrealEditor", "UE4Editor"].iter()
+        .map(|name| if cfg!(windows) {
+            binaries.join("Win64").join(format!("{}.exe", name))
+        } else if cfg!(target_os = "macos") {
+            binaries.join("Mac").join(format!("{}.app", name)).join("Contents").join("MacOS").join(name)
+        } else {
+            binaries.join("Linux").join(name)
+        })
+        .collect();
+    candidates.iter().find(|candidate| candidate.is_file()).unwrap_or(&candidates[0]).clone()
+}
+
+// when path_to_unrealengine is omitted, the editor of the engine the .uproject is associated with;
+// Ok has the association and the editor binary
+pub fn discover_editor(path_to_project: &str) -> Result<(String, PathBuf), String> {
+    let bytes = fs::read(path_to_project).map_err(|error| format!("failed to read {}: {}", path_to_project, error))?;
+    let project: ProjectFile = serde_json::from_str(crate::utf_from_bytes(&bytes).as_str()).map_err(|error| format!("invalid {}: {}", path_to_project, error))?;
+    let project_directory = Path::new(path_to_project).parent().unwrap_or_else(|| Path::new("."));
+    let engine_root = engine_root(project.engine_association.as_str(), project_directory)?;
+    Ok((project.engine_association, editor_binary(&engine_root)))
+}
+
+// engines before per-test durations were written leave every duration at 0
+pub fn report_warnings(test_pass: &TestPass, config: &TestConfiguration) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if config.duration_regressions.is_some() && !test_pass.tests.is_empty() && test_pass.tests.iter().all(|test| test.duration == 0.0) {
+        warnings.push("the report has no per-test durations, duration_regressions can't compare anything".to_owned());
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_version_next_to_the_binaries() {
+        let path = build_version_path("C:/UE_5.3/engine/Binaries/Win64/UnrealEditor.exe").unwrap();
+        assert_eq!(path, Path::new("C:/UE_5.3/engine").join("Build").join("Build.version"));
+        assert_eq!(build_version_path("UnrealEditor.exe"), None);
+    }
+
+    #[test]
+    fn build_version_display() {
+        let version: BuildVersion = serde_json::from_str(r#"{"MajorVersion": 5, "MinorVersion": 3, "PatchVersion": 2,
+            "Changelist": 29314046, "BranchName": "++UE5+Release-5.3"}"#).unwrap();
+        assert_eq!(version.to_string(), "5.3.2 (++UE5+Release-5.3)");
+        let version: BuildVersion = serde_json::from_str(r#"{"MajorVersion": 5, "MinorVersion": 4, "PatchVersion": 0}"#).unwrap();
+        assert_eq!(version.to_string(), "5.4.0");
+    }
+
+    #[test]
+    fn engines_from_the_launcher_registry_and_install_ini() {
+        let launcher_installed = r#"{"InstallationList": [
+            {"InstallLocation": "C:\\Program Files\\Epic Games\\Fab", "AppName": "Fab"},
+            {"InstallLocation": "C:\\Program Files\\Epic Games\\UE_5.3", "AppName": "UE_5.3"}]}"#;
+        assert_eq!(launcher_engine(launcher_installed, "5.3"), Some(PathBuf::from("C:\\Program Files\\Epic Games\\UE_5.3")));
+        assert_eq!(launcher_engine(launcher_installed, "5.4"), None);
+
+        let reg_output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\EpicGames\\Unreal Engine\\5.3\r\n    InstalledDirectory    REG_SZ    C:\\Program Files\\Epic Games\\UE_5.3\r\n";
+        assert_eq!(registry_value(reg_output, "InstalledDirectory").as_deref(), Some("C:\\Program Files\\Epic Games\\UE_5.3"));
+
+        let install_ini = "[Installations]\n{8C5A1F2E-4B7D-4E3A-9F61-2D0C7B9E1A44}=/home/build/UnrealEngine\n";
+        assert_eq!(install_ini_value(install_ini, "{8c5a1f2e-4b7d-4e3a-9f61-2d0c7b9e1a44}"), Some(PathBuf::from("/home/build/UnrealEngine")));
+    }
+
+    fn config(path_to_unrealengine: &str, extra: &str) -> TestConfiguration {
+        toml::from_str(format!(r#"
+            path_to_unrealengine = "{}"
+            path_to_project = "Castle.uproject"
+            path_to_reports = "Reports"
+            run_tests = "Project"
+            test_exit = "Automation Test Queue Empty"
+            ignore_regexes = []
+            {}"#, path_to_unrealengine, extra).as_str()).unwrap()
+    }
+
+    fn version(major_version: u32) -> BuildVersion {
+        BuildVersion { major_version, minor_version: 27, patch_version: 2, changelist: 0, branch_name: String::new() }
+    }
+
+    #[test]
+    fn executable_from_the_other_engine_generation() {
+        let ue5_editor = config("Engine/Binaries/Win64/UnrealEditor.exe", "");
+        assert_eq!(compatibility_warnings(&version(4), &ue5_editor).len(), 1);
+        assert!(compatibility_warnings(&version(5), &ue5_editor).is_empty());
+        assert_eq!(compatibility_warnings(&version(3), &ue5_editor).len(), 1);
+    }
+
+    #[cfg(feature = "runner")]
+    #[test]
+    fn editor_cmd_without_its_executable_on_ue4() {
+        let ue4_editor_cmd = config("Engine/Binaries/Win64/UE4Editor.exe", "[runner]\nkind = \"editor-cmd\"");
+        assert_eq!(compatibility_warnings(&version(4), &ue4_editor_cmd).len(), 1);
+        let with_executable = config("Engine/Binaries/Win64/UE4Editor.exe", "[runner]\nkind = \"editor-cmd\"\nexecutable = \"UE4Editor-Cmd.exe\"");
+        assert!(compatibility_warnings(&version(4), &with_executable).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{EntryType, TestPass};
+
+// the info unreal adds when a test's expected message was seen, ie
+// Expected Error or Warning matching 'Failed to load asset' occurred 1 times with Contains match type
+pub const DEFAULT_EXPECTED_MARKERS: &[&str] = &[r"^Expected .*matching '(.+)'.* occurred \d+ times?"];
+
+// checked when the config is parsed so a typo fails the config instead of a run
+pub fn check_markers<S: AsRef<str>>(markers: &[S]) -> Result<(), String> {
+    for marker in markers {
+        regex::Regex::new(marker.as_ref()).map_err(|error| format!("expected error marker {} is invalid: {}", marker.as_ref(), error))?;
+    }
+    Ok(())
+}
+
+// flags the expected-message markers and the errors/warnings they account for, so they can be
+// shown as informational matches instead of real errors. when a marker regex has a capture group,
+// that text is what the expected message contained and matching entries in the same test are flagged too
+pub fn mark_expected_messages<S: AsRef<str>>(test_pass: &mut TestPass, markers: &[S]) {
+    let markers: Vec<regex::Regex> = markers.iter()
+        .filter_map(|marker| regex::Regex::new(marker.as_ref()).ok())
+        .collect();
+
+    for test in test_pass.tests.iter_mut() {
+        let mut expected_texts = Vec::new();
+        for entry in test.entries.iter_mut() {
+            for marker in markers.iter() {
+                if let Some(captures) = marker.captures(entry.event.message.as_str()) {
+                    entry.expected = true;
+                    if let Some(expected_text) = captures.get(1) {
+                        expected_texts.push(expected_text.as_str().to_lowercase());
+                    }
+                    break;
+                }
+            }
+        }
+
+        if expected_texts.is_empty() {
+            continue;
+        }
+        for entry in test.entries.iter_mut() {
+            if let EntryType::Info = entry.event.entry_type {
+                continue;
+            }
+            let message = entry.event.message.to_lowercase();
+            if expected_texts.iter().any(|expected_text| message.contains(expected_text.as_str())) {
+                entry.expected = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entry_type: &str, message: &str) -> String {
+        format!(r#"{{"event": {{"type": "{}", "message": "{}", "context": "", "artifact": ""}},
+            "filename": "", "lineNumber": 0, "timestamp": ""}}"#, entry_type, message)
+    }
+
+    #[test]
+    fn marker_flags_the_entries_it_accounts_for() {
+        let entries = [
+            entry("Error", "Failed to load asset /Game/Missing"),
+            entry("Error", "Ensure condition failed"),
+            entry("Info", "Expected Error or Warning matching 'failed to load asset' occurred 1 times with Contains match type"),
+        ];
+        let mut test_pass = crate::test_pass_from_tests(format!(r#"{{"testDisplayName": "", "fullTestPath": "Project.Load", "state": "Fail", "entries": [{}],
+            "warnings": 0, "errors": 2, "artifacts": []}}"#, entries.join(",")).as_str());
+
+        mark_expected_messages(&mut test_pass, DEFAULT_EXPECTED_MARKERS);
+        let expected: Vec<bool> = test_pass.tests[0].entries.iter().map(|entry| entry.expected).collect();
+        assert_eq!(expected, [true, false, true]);
+    }
+
+    #[test]
+    fn invalid_markers_are_reported() {
+        assert!(check_markers(DEFAULT_EXPECTED_MARKERS).is_ok());
+        let error = check_markers(&["^Expected .*matching '(.+".to_owned()]).unwrap_err();
+        assert!(error.starts_with("expected error marker ^Expected .*matching '(.+ is invalid"));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{TestPass, TestResult};
+
+// a pattern without wildcards matches the test path itself and everything under it (Project.Functional
+// matches Project.Functional.Door.Open), * matches any run of characters
+pub fn pattern_matches(pattern: &str, test_path: &str) -> bool {
+    if !pattern.contains('*') {
+        return test_path == pattern || (test_path.starts_with(pattern) && test_path[pattern.len()..].starts_with('.'));
+    }
+    let expression = format!("^{}$", pattern.split('*').map(regex::escape).collect::<Vec<String>>().join(".*"));
+    regex::Regex::new(expression.as_str()).is_ok_and(|re| re.is_match(test_path))
+}
+
+pub fn is_selected(test_path: &str, include_tests: &[String], exclude_tests: &[String]) -> bool {
+    let included = include_tests.is_empty() || include_tests.iter().any(|pattern| pattern_matches(pattern, test_path));
+    included && !exclude_tests.iter().any(|pattern| pattern_matches(pattern, test_path))
+}
+
+// drops tests outside include_tests / inside exclude_tests from the report and its counts, returns how many were dropped
+pub fn apply_test_filters(test_pass: &mut TestPass, include_tests: &[String], exclude_tests: &[String]) -> usize {
+    if include_tests.is_empty() && exclude_tests.is_empty() {
+        return 0;
+    }
+    let mut removed = 0;
+    for test in test_pass.tests.iter().filter(|test| !is_selected(test.full_test_path.as_str(), include_tests, exclude_tests)) {
+        match test.state {
+            TestResult::Success if test.warnings > 0 => test_pass.succeeded_with_warnings -= 1,
+            TestResult::Success => test_pass.succeeded -= 1,
+            TestResult::Fail => test_pass.failed -= 1,
+            TestResult::NotRun | TestResult::NotEnoughParticipants => test_pass.not_run -= 1,
+            TestResult::InProcess => {
+                if let Some(in_process) = test_pass.in_process.as_mut() {
+                    *in_process -= 1;
+                }
+            }
+        }
+        removed += 1;
+    }
+    test_pass.tests.retain(|test| is_selected(test.full_test_path.as_str(), include_tests, exclude_tests));
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_patterns_stop_at_a_dot() {
+        assert!(pattern_matches("Project.Functional", "Project.Functional"));
+        assert!(pattern_matches("Project.Functional", "Project.Functional.Door.Open"));
+        assert!(!pattern_matches("Project.Functional", "Project.FunctionalDoor"));
+        assert!(pattern_matches("Project.*.Open", "Project.Functional.Door.Open"));
+        assert!(!pattern_matches("Project.*.Open", "Project.Functional.Door.Opened"));
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let include_tests = vec!["Project.Functional".to_owned()];
+        let exclude_tests = vec!["*.Slow*".to_owned()];
+        assert!(is_selected("Project.Functional.Door", &include_tests, &exclude_tests));
+        assert!(!is_selected("Project.Functional.SlowDoor", &include_tests, &exclude_tests));
+        assert!(!is_selected("Project.Rendering.Water", &include_tests, &exclude_tests));
+        assert!(is_selected("Project.Rendering.Water", &[], &exclude_tests));
+    }
+
+    #[test]
+    fn filtered_tests_leave_the_counts() {
+        let tests: Vec<String> = [("Project.Door", "Success", 0), ("Project.Water", "Success", 1), ("Project.Wall", "Fail", 0), ("Project.Roof", "NotRun", 0)].iter()
+            .map(|(path, state, warnings)| format!(r#"{{"testDisplayName": "", "fullTestPath": "{}", "state": "{}", "entries": [],
+                "warnings": {}, "errors": 0, "artifacts": []}}"#, path, state, warnings))
+            .collect();
+        let mut test_pass = crate::test_pass_from_tests(tests.join(",").as_str());
+        test_pass.succeeded = 1;
+        test_pass.succeeded_with_warnings = 1;
+        test_pass.failed = 1;
+        test_pass.not_run = 1;
+
+        assert_eq!(apply_test_filters(&mut test_pass, &[], &["Project.W*".to_owned(), "Project.Roof".to_owned()]), 3);
+        assert_eq!(test_pass.tests.len(), 1);
+        assert_eq!((test_pass.succeeded, test_pass.succeeded_with_warnings, test_pass.failed, test_pass.not_run), (1, 0, 0, 0));
+    }
+}
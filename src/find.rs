@@ -0,0 +1,113 @@
+use crate::theme::Theme;
+use crate::{should_ignore_message, EntryType, TestPass, TestResult};
+use colored::*;
+
+// characters of the query appearing in order, consecutive matches score higher so
+// "abilitysys" ranks Project.AbilitySystem.Cooldowns above Project.Ability.Basic.Yield.Sys
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let offset = candidate[position..].iter().position(|c| *c == query_char)?;
+        let index = position + offset;
+        score += if previous_match == Some(index.wrapping_sub(1)) { 5 } else { 1 };
+        previous_match = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
+
+fn edit_distance(first: &[char], second: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+    for (i, first_char) in first.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, second_char) in second.iter().enumerate() {
+            let substitution = previous[j] + if first_char == second_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[second.len()]
+}
+
+// known test names close to a requested one that matched nothing, a requested name is usually a prefix
+// (Project.Door) so it's compared against each candidate cut to its length, closest first
+pub fn near_misses<'a>(requested: &str, candidates: &'a [String], limit: usize) -> Vec<&'a str> {
+    let requested: Vec<char> = requested.to_lowercase().chars().collect();
+    let max_distance = (requested.len() / 4).max(2);
+    let mut misses: Vec<(usize, &str)> = candidates.iter()
+        .filter_map(|candidate| {
+            let prefix: Vec<char> = candidate.to_lowercase().chars().take(requested.len()).collect();
+            let distance = edit_distance(&requested, &prefix);
+            (distance <= max_distance).then_some((distance, candidate.as_str()))
+        })
+        .collect();
+    misses.sort();
+    misses.dedup_by(|(_, a), (_, b)| a == b);
+    misses.into_iter().take(limit).map(|(_, candidate)| candidate).collect()
+}
+
+pub fn find(test_pass: &TestPass, query: &str, failed_only: bool, ignore_regexes: &[String], theme: &Theme) {
+    let query_lower = query.to_lowercase();
+    let empty_spacer = theme.spacer();
+
+    let mut found: Vec<(i32, &crate::Test)> = Vec::new();
+    for test in test_pass.tests.iter() {
+        if failed_only && test.state != TestResult::Fail {
+            continue;
+        }
+        let path_score = fuzzy_score(query, test.full_test_path.as_str());
+        let message_match = test.entries.iter().any(|entry| entry.event.message.to_lowercase().contains(query_lower.as_str()));
+        match (path_score, message_match) {
+            (Some(score), _) => found.push((score, test)),
+            (None, true) => found.push((0, test)),
+            _ => {}
+        }
+    }
+    found.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    for (_, test) in found.iter() {
+        println!("{}{}", theme.test_status(test.state), theme.test_path(test.state, test.full_test_path.as_str()));
+        for entry in test.entries.iter() {
+            if should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                continue;
+            }
+            let mentions_query = entry.event.message.to_lowercase().contains(query_lower.as_str());
+            if matches!(entry.event.entry_type, EntryType::Info) && !mentions_query {
+                continue;
+            }
+            let message = if mentions_query { entry.event.message.bold() } else { entry.event.message.normal() };
+            println!("{}{}{}", empty_spacer, theme.entry_label(entry), message);
+        }
+    }
+    println!("{} matching tests", found.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_matches_rank_higher() {
+        let grouped = fuzzy_score("abilitysys", "Project.AbilitySystem.Cooldowns").unwrap();
+        let scattered = fuzzy_score("abilitysys", "Project.Ability.Basic.Yield.Sys").unwrap();
+        assert!(grouped > scattered);
+    }
+
+    #[test]
+    fn near_misses_of_a_misspelled_prefix() {
+        let candidates: Vec<String> = ["Project.Door.Opens", "Project.Door.Closes", "Project.Floor.Holds", "Project.Window.Opens"].iter().map(|name| name.to_string()).collect();
+        assert_eq!(near_misses("Project.Dorr", &candidates, 5), vec!["Project.Door.Closes", "Project.Door.Opens"]);
+        assert_eq!(near_misses("Project.Door.Opnes", &candidates, 1), vec!["Project.Door.Opens"]);
+        assert!(near_misses("Engine.Rendering", &candidates, 5).is_empty());
+    }
+
+    #[test]
+    fn query_characters_must_appear_in_order() {
+        assert_eq!(fuzzy_score("ab", "Project.Ability"), Some(6));
+        assert_eq!(fuzzy_score("ba", "Project.Ability"), None);
+        assert_eq!(fuzzy_score("pro ab", "Project.Ability"), fuzzy_score("proab", "Project.Ability"));
+    }
+}
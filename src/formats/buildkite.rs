@@ -0,0 +1,106 @@
+use super::html::escape;
+use super::junit::unexpected_errors;
+use super::{OutputFormatter, ReportContext};
+use crate::lang::{self, Message};
+use crate::{analysis, should_ignore_message, EntryType, TestResult};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+pub struct BuildkiteFormatter;
+
+// annotations are capped at 1MiB, a run where everything broke doesn't need every failure spelled out
+const MAX_DETAILED_FAILURES: usize = 50;
+
+// a buildkite annotation: the summary, then every failed test collapsed to its first error
+impl OutputFormatter for BuildkiteFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let ignore_regexes = &context.config.ignore_regexes;
+        let lang = context.lang;
+
+        write!(out, "<p><strong>{}</strong>", lang::text(lang, Message::TestReport))?;
+        if let Some(session_name) = &context.run_metadata.session_name {
+            write!(out, " <code>{}</code>", escape(session_name.as_str()))?;
+        }
+        writeln!(out, ": {}, {}</p>",
+            lang::format(lang, Message::Summary, &[&test_pass.succeeded, &test_pass.failed, &(test_pass.not_run + test_pass.succeeded_with_warnings)]),
+            lang::format(lang, Message::Elapsed, &[&test_pass.total_duration]))?;
+        if context.run_metadata.crashed {
+            let crashed = match analysis::last_running_test(test_pass) {
+                Some(test) => lang::format(lang, Message::CrashedDuringExecutionIn, &[&format!("<code>{}</code>", escape(test.full_test_path.as_str()))]),
+                None => lang::text(lang, Message::CrashedDuringExecution).to_owned(),
+            };
+            writeln!(out, "<p><strong>{}</strong></p>", crashed)?;
+        }
+
+        let failed: Vec<&crate::Test> = test_pass.tests.iter().filter(|test| test.state == TestResult::Fail).collect();
+        for test in failed.iter().take(MAX_DETAILED_FAILURES) {
+            let first_error = unexpected_errors(test, ignore_regexes).next().map_or(String::new(), |entry| format!(" — {}", escape(entry.event.message.as_str())));
+            writeln!(out)?;
+            writeln!(out, "<details><summary><code>{}</code>{}</summary>", escape(test.full_test_path.as_str()), first_error)?;
+            writeln!(out)?;
+            writeln!(out, "```term")?;
+            for entry in test.entries.iter() {
+                if entry.expected || should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                    continue;
+                }
+                // buildkite renders ansi colors in term blocks
+                match entry.event.entry_type {
+                    EntryType::Info => continue,
+                    EntryType::Warning => writeln!(out, "\x1b[33mWarning: {}\x1b[0m", entry.event.message)?,
+                    EntryType::Error => writeln!(out, "\x1b[31mError: {}\x1b[0m", entry.event.message)?,
+                }
+                if !entry.filename.is_empty() {
+                    writeln!(out, "    {}:{}", entry.filename, entry.line_number)?;
+                }
+            }
+            writeln!(out, "```")?;
+            writeln!(out)?;
+            writeln!(out, "</details>")?;
+        }
+        if failed.len() > MAX_DETAILED_FAILURES {
+            writeln!(out)?;
+            writeln!(out, "<p>and {} more failed tests</p>", failed.len() - MAX_DETAILED_FAILURES)?;
+        }
+        Ok(())
+    }
+}
+
+// buildkite sets BUILDKITE=true in every job
+pub fn on_agent() -> bool {
+    std::env::var("BUILDKITE").map_or(false, |value| value == "true")
+}
+
+fn style(context: &ReportContext) -> &'static str {
+    if context.test_pass.failed > 0 || context.run_metadata.crashed {
+        "error"
+    } else if context.test_pass.not_run > 0 {
+        "warning"
+    } else {
+        "success"
+    }
+}
+
+// one annotation per session, a retried job replaces its annotation instead of adding another
+fn annotation_context(context: &ReportContext) -> String {
+    match &context.run_metadata.session_name {
+        Some(session_name) => format!("runtests-{}", session_name),
+        None => "runtests".to_owned(),
+    }
+}
+
+// hands the annotation to buildkite-agent, which is what --format buildkite without a file does inside a job
+pub fn annotate(context: &ReportContext) -> io::Result<()> {
+    let mut annotation = Vec::new();
+    BuildkiteFormatter.write(context, &mut annotation)?;
+    let mut child = Command::new("buildkite-agent")
+        .args(["annotate", "--style", style(context), "--context", annotation_context(context).as_str()])
+        .stdin(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().expect("buildkite-agent stdin").write_all(&annotation)?;
+    let exit_status = child.wait()?;
+    if !exit_status.success() {
+        return Err(io::Error::new(io::ErrorKind::Other, format!("buildkite-agent annotate exited with {}", exit_status)));
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use super::{OutputFormatter, ReportContext};
+use crate::lang::{self, Lang, Message};
+use crate::testinfo::TestInfo;
+use crate::logexcerpts::LogExcerpt;
+use crate::testnames::TestNames;
+use crate::{analysis, datetime, ignores, knownissues, noisyfiles, timeline, width};
+use crate::{matching_ignore_regex, should_ignore_message, Entry, EntryType, Test, TestResult};
+use colored::*;
+use std::io::{self, Write};
+
+pub struct ConsoleFormatter;
+
+// narrower than this after the indent isn't worth wrapping into
+const MIN_WRAP_WIDTH: usize = 20;
+
+fn write_expected_counts(out: &mut dyn Write, test: &Test, ignore_regexes: &[String], empty_spacer: &str, lang: Lang) -> io::Result<()> {
+    let mut expected_count = 0;
+    let mut unexpected_count = 0;
+    for entry in test.entries.iter().filter(|entry| !should_ignore_message(entry.event.message.as_str(), ignore_regexes)) {
+        match (entry.expected, &entry.event.entry_type) {
+            (true, _) => expected_count += 1,
+            (false, EntryType::Warning) | (false, EntryType::Error) => unexpected_count += 1,
+            _ => {}
+        }
+    }
+    if expected_count > 0 {
+        writeln!(out, "{}{}", empty_spacer, lang::format(lang, Message::ExpectedMessages, &[&expected_count, &unexpected_count]).dimmed())?;
+    }
+    Ok(())
+}
+
+// continuation lines are indented under the start of the message, past the spacer and label
+fn write_wrapped(out: &mut dyn Write, spacer: &str, label: ColoredString, text: &str, width: Option<usize>) -> io::Result<()> {
+    let indent = spacer.chars().count() + label.chars().count();
+    let width = match width.filter(|width| *width > indent + MIN_WRAP_WIDTH) {
+        Some(width) => width,
+        None => return writeln!(out, "{}{}{}", spacer, label, text),
+    };
+    for (index, line) in width::wrap(text, width - indent).iter().enumerate() {
+        match index {
+            0 => writeln!(out, "{}{}{}", spacer, label, line)?,
+            _ => writeln!(out, "{}{}", " ".repeat(indent), line)?,
+        }
+    }
+    Ok(())
+}
+
+// paths and file:line locations are shortened in the middle rather than wrapped
+fn fit(text: &str, width: Option<usize>, indent: usize) -> String {
+    match width.filter(|width| *width > indent + MIN_WRAP_WIDTH) {
+        Some(width) => width::truncate_middle(text, width - indent),
+        None => text.to_owned(),
+    }
+}
+
+// file:line of an entry, a terminal hyperlink (OSC 8) to source hosting when a source_url_template is set;
+// terminals without support show just the text
+fn location(context: &ReportContext, entry: &Entry, indent: usize) -> String {
+    let text = fit(format!("{}:{}", entry.filename, entry.line_number).as_str(), context.options.width, indent);
+    match context.source_url(entry).filter(|_| !context.options.plain && colored::control::SHOULD_COLORIZE.should_colorize()) {
+        Some(url) => format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text),
+        None => text,
+    }
+}
+
+// the engine log around an error, the line the error was found at is the one not dimmed
+fn write_log_excerpt(out: &mut dyn Write, excerpt: &LogExcerpt, spacer: &str, width: Option<usize>) -> io::Result<()> {
+    let indent = format!("{}{}", spacer, spacer);
+    writeln!(out, "{}{}", indent, format!("{}:{}", excerpt.log_file, excerpt.first_line_number + excerpt.matched).dimmed())?;
+    for (index, line) in excerpt.lines.iter().enumerate() {
+        let line = fit(line.as_str(), width, indent.chars().count() + 2);
+        if index == excerpt.matched {
+            writeln!(out, "{}> {}", indent, line)?;
+        } else {
+            writeln!(out, "{}{}", indent, format!("| {}", line).dimmed())?;
+        }
+    }
+    Ok(())
+}
+
+// where a failed test lives, so it can be opened without searching for its display name
+fn defined_in(info: &TestInfo, lang: Lang) -> Option<String> {
+    let source_file = info.source_file.as_ref()?;
+    let location = match info.source_file_line {
+        Some(line) => lang::format(lang, Message::DefinedIn, &[&format!("{}:{}", source_file, line)]),
+        None => lang::format(lang, Message::DefinedIn, &[source_file]),
+    };
+    Some(match &info.owner {
+        Some(owner) => format!("{}, {}", location, lang::format(lang, Message::OwnedBy, &[owner])),
+        None => location,
+    })
+}
+
+impl OutputFormatter for ConsoleFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let config = context.config;
+        let run_metadata = context.run_metadata;
+        let theme = &context.theme;
+        let zone = &context.zone;
+        let options = &context.options;
+        let lang = context.lang;
+
+        let stamp = |entry: &Entry| if options.timestamps { format!("[{}] ", datetime::format_timestamp(entry.timestamp.as_str(), zone)) } else { String::new() };
+        let empty_spacer = theme.spacer();
+        let summary_only = options.summary_only;
+        let show_ignored = options.show_ignored;
+        let width = options.width;
+        let mut test_names = TestNames::new(config.test_names.as_ref(), test_pass.tests.iter().map(|test| test.full_test_path.as_str()));
+        let crashed_in = if run_metadata.crashed { analysis::last_running_test(test_pass).map(|test| test.full_test_path.as_str()) } else { None };
+        // without a crash the engine still exited before these finished, ie a -TestExit that came too early
+        let in_process = if run_metadata.crashed { 0 } else { analysis::in_process_count(test_pass) };
+        let in_flight: Vec<&str> = if in_process > 0 { analysis::in_flight_tests(test_pass).iter().map(|test| test.full_test_path.as_str()).collect() } else { Vec::new() };
+
+        for test in test_pass.tests.iter() {
+            match test.state {
+                TestResult::Success => {
+                    writeln!(out, "{}{}", theme.test_status(test.state), theme.test_path(test.state, fit(test_names.display(test.full_test_path.as_str()).as_str(), width, theme.test_status(test.state).chars().count()).as_str()))?;
+                    for entry in test.entries.iter().filter(|_| !summary_only) {
+
+                        if let Some(ignore_regex) = matching_ignore_regex(entry.event.message.as_str(), &config.ignore_regexes) {
+                            if show_ignored {
+                                writeln!(out, "{}{}{}", empty_spacer, theme.ignored_label(), theme.ignored.paint(format!("{}{} (ignored by {})", stamp(entry), entry.event.message, ignore_regex).as_str()))?;
+                            }
+                            continue;
+                        }
+                        if entry.expected {
+                            write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                            continue;
+                        }
+
+                        match entry.event.entry_type {
+                            EntryType::Warning => {
+                                write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                                writeln!(out, "{}{}{}", empty_spacer, empty_spacer, location(context, entry, empty_spacer.chars().count() * 2))?;
+                            }
+                            EntryType::Error => {
+                                write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                                writeln!(out, "{}{}{}", empty_spacer, empty_spacer, location(context, entry, empty_spacer.chars().count() * 2))?;
+                            },
+                            _ => {}
+                        }
+                    }
+                    if !summary_only {
+                        write_expected_counts(out, test, &config.ignore_regexes, empty_spacer.as_str(), lang)?;
+                    }
+                },
+                TestResult::Fail => {
+                    writeln!(out, "{}{}", theme.test_status(test.state), theme.test_path(test.state, fit(test_names.display(test.full_test_path.as_str()).as_str(), width, theme.test_status(test.state).chars().count()).as_str()))?;
+                    if let Some(defined_in) = test.info.as_ref().and_then(|info| defined_in(info, lang)).filter(|_| !summary_only) {
+                        writeln!(out, "{}{}", empty_spacer, defined_in.dimmed())?;
+                    }
+                    for (entry_index, entry) in test.entries.iter().enumerate().filter(|_| !summary_only) {
+
+                        if let Some(ignore_regex) = matching_ignore_regex(entry.event.message.as_str(), &config.ignore_regexes) {
+                            if show_ignored {
+                                writeln!(out, "{}{}{}", empty_spacer, theme.ignored_label(), theme.ignored.paint(format!("{}{} (ignored by {})", stamp(entry), entry.event.message, ignore_regex).as_str()))?;
+                            }
+                            continue;
+                        }
+                        if entry.expected {
+                            write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                            continue;
+                        }
+
+                        match entry.event.entry_type {
+                            EntryType::Info => write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?,
+                            EntryType::Warning => {
+                                write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                                writeln!(out, "{}{}{}", empty_spacer, empty_spacer, location(context, entry, empty_spacer.chars().count() * 2))?;
+                            }
+                            EntryType::Error => {
+                                write_wrapped(out, empty_spacer.as_str(), theme.entry_label(entry), format!("{}{}", stamp(entry), entry.event.message).as_str(), width)?;
+                                writeln!(out, "{}{}{}", empty_spacer, empty_spacer, location(context, entry, empty_spacer.chars().count() * 2))?;
+                                if let Some(excerpt) = context.log_excerpt(test, entry_index) {
+                                    write_log_excerpt(out, excerpt, empty_spacer.as_str(), width)?;
+                                }
+                            }
+                        }
+                    }
+                    if !summary_only {
+                        write_expected_counts(out, test, &config.ignore_regexes, empty_spacer.as_str(), lang)?;
+                    }
+                }
+                _ => writeln!(out, "{}{}", theme.test_status(test.state), theme.test_path(test.state, fit(test_names.display(test.full_test_path.as_str()).as_str(), width, theme.test_status(test.state).chars().count()).as_str()))?,
+            }
+            if crashed_in == Some(test.full_test_path.as_str()) {
+                writeln!(out, "{}{}", empty_spacer, theme.fail.paint(lang::text(lang, Message::CrashedDuringThisTest)))?;
+            } else if in_flight.contains(&test.full_test_path.as_str()) {
+                writeln!(out, "{}{}", empty_spacer, theme.fail.paint(lang::text(lang, Message::ExitedDuringThisTest)))?;
+            }
+        }
+        // left out by --cache, they passed with the same inputs last time
+        for cached_test in run_metadata.cached_tests.iter() {
+            writeln!(out, "{}{}", empty_spacer, lang::format(lang, Message::CachedPass, &[&test_names.display(cached_test.as_str())]).dimmed())?;
+        }
+
+        let normalizer = analysis::Normalizer::new(config.normalization.as_ref()).expect("normalization patterns are checked when the config is loaded");
+        analysis::write_failure_clusters(out, test_pass, &config.ignore_regexes, &normalizer, config.culprit_min_tests, theme)?;
+        if show_ignored || config.warn_unused_ignores {
+            let ignore_counts = ignores::ignore_pattern_counts(test_pass, &config.ignore_regexes);
+            if show_ignored {
+                ignores::write_ignore_pattern_counts(out, &ignore_counts, theme)?;
+            }
+            if config.warn_unused_ignores {
+                ignores::write_unused_ignore_patterns(out, &ignore_counts, theme)?;
+            }
+        }
+        knownissues::write_known_issues(out, &config.known_issues, test_pass, chrono::Local::now().date_naive(), theme)?;
+
+        if run_metadata.crashed {
+            match crashed_in {
+                Some(test_path) => writeln!(out, "{}", theme.fail.paint(lang::format(lang, Message::CrashedDuringExecutionIn, &[&test_path]).as_str()))?,
+                None => writeln!(out, "{}", theme.fail.paint(lang::text(lang, Message::CrashedDuringExecution)))?,
+            }
+            if let Some(crash_context) = &run_metadata.crash_context {
+                if let Some(error_message) = &crash_context.error_message {
+                    let crash_type = crash_context.crash_type.as_deref().unwrap_or("Crash");
+                    writeln!(out, "{}{}", empty_spacer, theme.fail.paint(format!("{}: {}", crash_type, error_message).as_str()))?;
+                }
+                let machine: Vec<&str> = [&crash_context.cpu, &crash_context.gpu, &crash_context.os].iter().filter_map(|part| part.as_deref()).collect();
+                if !machine.is_empty() {
+                    writeln!(out, "{}{}", empty_spacer, lang::format(lang, Message::On, &[&machine.join(", ")]))?;
+                }
+                writeln!(out, "{}{}", empty_spacer, lang::format(lang, Message::CrashReport, &[&crash_context.folder]))?;
+            }
+            for frame in run_metadata.callstack.iter() {
+                writeln!(out, "{}{}", empty_spacer, frame)?;
+            }
+        } else if in_process > 0 {
+            writeln!(out, "{}", theme.fail.paint(lang::format(lang, Message::IncompleteRun, &[&in_process]).as_str()))?;
+            for test_path in in_flight.iter() {
+                writeln!(out, "{}{}", empty_spacer, test_names.display(test_path))?;
+            }
+        }
+
+        let succeeded_count = test_pass.succeeded;
+        let failed_count = test_pass.failed;
+        let other_count = test_pass.not_run + test_pass.succeeded_with_warnings;
+
+        let summary = lang::format(lang, Message::Summary, &[&succeeded_count, &failed_count, &other_count]);
+        if test_pass.failed > 0 || in_process > 0 {
+            writeln!(out, "{}", theme.fail.paint(summary.as_str()))?;
+        } else if test_pass.not_run > 0 || test_pass.succeeded_with_warnings > 0 {
+            writeln!(out, "{}", theme.warning.paint(summary.as_str()))?;
+        } else {
+            writeln!(out, "{}", theme.success.paint(summary.as_str()))?;
+        }
+        if !options.plain || options.durations {
+            writeln!(out, "{}", lang::format(lang, Message::Elapsed, &[&test_pass.total_duration]))?;
+            if let Some(warmup) = &run_metadata.warmup {
+                let mut details = Vec::new();
+                if let Some(shader_compile_seconds) = warmup.shader_compile_seconds {
+                    details.push(lang::format(lang, Message::ShaderCompileSeconds, &[&format!("{:.1}", shader_compile_seconds)]));
+                }
+                if warmup.ddc_misses > 0 {
+                    details.push(lang::format(lang, Message::DdcMisses, &[&warmup.ddc_misses]));
+                }
+                let details = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+                writeln!(out, "{}", lang::format(lang, Message::Warmup, &[&format!("{:.1}", warmup.seconds), &details]))?;
+            }
+        }
+        if !options.plain {
+            match datetime::parse_timestamp(test_pass.report_created_on.as_str()) {
+                Some(created_on) => {
+                    writeln!(out, "{}", lang::format(lang, Message::ReportCreated, &[&datetime::format_datetime(created_on, zone), &datetime::relative_age(created_on, chrono::Utc::now(), lang)]))?;
+                    if let Some(started_at) = run_metadata.started_at {
+                        if created_on < started_at {
+                            writeln!(out, "{}", theme.warning.paint(lang::text(lang, Message::ReportOlderThanRun)))?;
+                        }
+                    }
+                }
+                None => writeln!(out, "{}", lang::format(lang, Message::ReportCreatedRaw, &[&test_pass.report_created_on]))?,
+            }
+            if let Some(changelist) = &run_metadata.changelist {
+                writeln!(out, "{}", lang::format(lang, Message::AtChangelist, &[changelist]))?;
+            }
+            if let Some(git_info) = &run_metadata.git {
+                writeln!(out, "{}", lang::format(lang, Message::AtCommit, &[git_info]))?;
+            }
+            if let Some(engine_version) = &run_metadata.engine_version {
+                writeln!(out, "{}", lang::format(lang, Message::OnEngine, &[engine_version]))?;
+            }
+            if let Some(shuffle_seed) = run_metadata.shuffle_seed {
+                writeln!(out, "{}", lang::format(lang, Message::ShuffledWithSeed, &[&shuffle_seed, &shuffle_seed]))?;
+            }
+            if let Some(session_name) = &run_metadata.session_name {
+                writeln!(out, "{}", lang::format(lang, Message::Session, &[session_name]))?;
+            }
+            for (key, value) in run_metadata.meta.iter() {
+                writeln!(out, "{} = {}", key, value)?;
+            }
+        }
+
+        if options.timeline {
+            timeline::write_timeline(out, test_pass, &config.ignore_regexes, theme, zone)?;
+        }
+        if options.warnings_by_file {
+            noisyfiles::write_warnings_by_file(out, test_pass, &config.ignore_regexes, theme)?;
+        }
+        Ok(())
+    }
+}
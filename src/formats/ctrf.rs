@@ -0,0 +1,161 @@
+use super::junit::unexpected_errors;
+use super::{OutputFormatter, ReportContext};
+use crate::{Test, TestResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+pub struct CtrfFormatter;
+
+// common test report format (ctrf.io), the json the ci summary actions and dashboards built around it read
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CtrfReport {
+    report_format: &'static str,
+    spec_version: &'static str,
+    results: Results,
+}
+
+#[derive(Serialize)]
+struct Results {
+    tool: Tool,
+    summary: Summary,
+    tests: Vec<CtrfTest>,
+    environment: Environment,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+// start and stop are milliseconds since the epoch, 0 when the report was parsed without a run
+#[derive(Serialize)]
+struct Summary {
+    tests: usize,
+    passed: usize,
+    failed: usize,
+    pending: usize,
+    skipped: usize,
+    other: usize,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CtrfTest {
+    name: String,
+    status: &'static str,
+    duration: i64, // milliseconds
+    suite: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<i32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<&'static str, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Environment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_version: Option<String>, // the engine version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_name: Option<String>, // the session name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, String>, // changelist, dirty and --meta labels
+}
+
+fn status(state: TestResult) -> &'static str {
+    match state {
+        TestResult::Success => "passed",
+        TestResult::Fail => "failed",
+        TestResult::NotRun | TestResult::NotEnoughParticipants => "skipped",
+        TestResult::InProcess => "other",
+    }
+}
+
+fn ctrf_test(test: &Test, ignore_regexes: &[String]) -> CtrfTest {
+    let suite = test.full_test_path.rsplit_once('.').map_or("", |(suite, _)| suite);
+    let errors: Vec<&crate::Entry> = if test.state == TestResult::Fail { unexpected_errors(test, ignore_regexes).collect() } else { Vec::new() };
+    let trace: Vec<String> = errors.iter().map(|entry| format!("{}\n    {}:{}", entry.event.message, entry.filename, entry.line_number)).collect();
+    let info = test.info.as_ref();
+    let mut extra = BTreeMap::new();
+    if let Some(signature) = &test.signature {
+        extra.insert("signature", signature.clone());
+    }
+    if let Some(owner) = info.and_then(|info| info.owner.as_ref()) {
+        extra.insert("owner", owner.clone());
+    }
+    CtrfTest {
+        name: test.full_test_path.clone(),
+        status: status(test.state),
+        duration: (test.duration * 1000.0).round() as i64,
+        suite: suite.to_owned(),
+        message: match test.state {
+            TestResult::Fail => Some(errors.first().map_or_else(|| "test failed".to_owned(), |entry| entry.event.message.to_string())),
+            _ => None,
+        },
+        trace: if trace.is_empty() { None } else { Some(trace.join("\n")) },
+        file_path: info.and_then(|info| info.source_file.clone()),
+        line: info.and_then(|info| info.source_file_line),
+        extra,
+    }
+}
+
+impl OutputFormatter for CtrfFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let run_metadata = context.run_metadata;
+        let count = |status_name: &str| test_pass.tests.iter().filter(|test| status(test.state) == status_name).count();
+        let start = run_metadata.started_at.map_or(0, |started_at| started_at.timestamp_millis());
+        let stop = if start == 0 { 0 } else { start + (test_pass.total_duration * 1000.0).round() as i64 };
+
+        let vcs = context.vcs();
+        let mut extra = run_metadata.meta.clone();
+        if let Some(changelist) = vcs.changelist {
+            extra.insert("changelist".to_owned(), changelist.to_owned());
+        }
+        if let Some(dirty) = vcs.dirty {
+            extra.insert("dirty".to_owned(), dirty.to_string());
+        }
+        let report = CtrfReport {
+            report_format: "CTRF",
+            spec_version: "0.0.0",
+            results: Results {
+                tool: Tool { name: "runtests", version: env!("CARGO_PKG_VERSION") },
+                summary: Summary {
+                    tests: test_pass.tests.len(),
+                    passed: count("passed"),
+                    failed: count("failed"),
+                    pending: 0,
+                    skipped: count("skipped"),
+                    other: count("other"),
+                    start,
+                    stop,
+                },
+                tests: test_pass.tests.iter().map(|test| ctrf_test(test, &context.config.ignore_regexes)).collect(),
+                environment: Environment {
+                    app_version: run_metadata.engine_version.as_ref().map(|engine_version| engine_version.to_string()),
+                    branch_name: vcs.branch.map(str::to_owned),
+                    commit: vcs.commit.map(str::to_owned),
+                    build_name: run_metadata.session_name.clone(),
+                    extra,
+                },
+            },
+        };
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+        writeln!(out)
+    }
+}
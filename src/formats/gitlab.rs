@@ -0,0 +1,108 @@
+use super::junit::unexpected_errors;
+use super::{OutputFormatter, ReportContext};
+use crate::sha256::Sha256;
+use crate::{Test, TestResult};
+use serde::Serialize;
+use std::io::{self, Write};
+
+pub struct GitlabFormatter;
+
+// gitlab only reads junit xml as a unit test report (--format junit), the json it shows in merge requests is
+// the code quality report (artifacts:reports:codequality), which puts each failing error on its source line
+#[derive(Serialize)]
+struct CodeQualityIssue {
+    description: String,
+    check_name: String,
+    fingerprint: String,
+    severity: &'static str,
+    location: Location,
+}
+
+#[derive(Serialize)]
+struct Location {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: i32,
+}
+
+// gitlab matches locations against the repository, absolute paths from the build machine never match
+fn repository_path(path: &str, project_dir: Option<&str>) -> String {
+    let path = path.replace('\\', "/");
+    let project_dir = match project_dir {
+        Some(project_dir) => project_dir.replace('\\', "/"),
+        None => return path,
+    };
+    let prefix = format!("{}/", project_dir.trim_end_matches('/'));
+    match path.strip_prefix(prefix.as_str()) {
+        Some(relative) => relative.to_owned(),
+        None => path,
+    }
+}
+
+// stable across runs so gitlab can tell new issues from ones the target branch already had,
+// the line is left out since unrelated edits move it
+fn fingerprint(test: &Test, message: &str, path: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [test.full_test_path.as_str(), message, path] {
+        hasher.update(part.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finish()
+}
+
+fn issues(context: &ReportContext, project_dir: Option<&str>) -> Vec<CodeQualityIssue> {
+    let mut issues = Vec::new();
+    for test in context.test_pass.tests.iter().filter(|test| test.state == TestResult::Fail) {
+        let mut located = false;
+        for entry in unexpected_errors(test, &context.config.ignore_regexes).filter(|entry| !entry.filename.is_empty()) {
+            let path = repository_path(entry.filename.as_str(), project_dir);
+            issues.push(CodeQualityIssue {
+                description: format!("{}: {}", test.full_test_path, entry.event.message),
+                check_name: "runtests".to_owned(),
+                fingerprint: fingerprint(test, entry.event.message.as_str(), path.as_str()),
+                severity: "major",
+                location: Location { path, lines: Lines { begin: entry.line_number.max(1) } },
+            });
+            located = true;
+        }
+        // a failure without a located error still points at the test itself when its source is known
+        if !located {
+            if let Some(source_file) = test.info.as_ref().and_then(|info| info.source_file.as_ref()) {
+                let path = repository_path(source_file.as_str(), project_dir);
+                issues.push(CodeQualityIssue {
+                    description: format!("{} failed", test.full_test_path),
+                    check_name: "runtests".to_owned(),
+                    fingerprint: fingerprint(test, "", path.as_str()),
+                    severity: "major",
+                    location: Location { path, lines: Lines { begin: test.info.as_ref().and_then(|info| info.source_file_line).unwrap_or(1).max(1) } },
+                });
+            }
+        }
+    }
+    issues
+}
+
+impl OutputFormatter for GitlabFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let project_dir = std::env::var("CI_PROJECT_DIR").ok();
+        serde_json::to_writer_pretty(&mut *out, &issues(context, project_dir.as_deref()))?;
+        writeln!(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_made_relative_to_the_checkout() {
+        assert_eq!(repository_path("C:\\builds\\castle\\Source\\Door.cpp", Some("C:\\builds\\castle")), "Source/Door.cpp");
+        assert_eq!(repository_path("/builds/castle/Source/Door.cpp", Some("/builds/castle/")), "Source/Door.cpp");
+        assert_eq!(repository_path("/engine/Source/Runtime/Core.cpp", Some("/builds/castle")), "/engine/Source/Runtime/Core.cpp");
+        assert_eq!(repository_path("Source/Door.cpp", None), "Source/Door.cpp");
+    }
+}
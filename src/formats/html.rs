@@ -0,0 +1,251 @@
+use super::{OutputFormatter, ReportContext};
+use crate::csvprofile::CsvProfileSummary;
+use crate::logexcerpts::LogExcerpt;
+use crate::pipeline;
+use crate::lang::{self, Lang, Message};
+use crate::testinfo::TestInfo;
+use crate::{should_ignore_message, Artifact, ArtifactType, EntryType, TestPass, TestResult};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct HtmlFormatter;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+.success{color:#2a2}.fail{color:#c22}.other{color:#b80}\
+.info{margin:0 2em;color:#666}.entry{margin-left:2em;font-family:monospace;white-space:pre-wrap}\
+.profile{margin:1em 2em;border-collapse:collapse}.profile td,.profile th{padding:0 1em 0 0;text-align:left}\
+.excerpt{margin:0.5em 4em;color:#666;font-size:smaller;white-space:pre-wrap}.excerpt b{color:#000}\
+.comparison{display:flex;gap:1em;margin:1em 2em}.comparison figure{margin:0}.comparison img{max-width:32vw;border:1px solid #ccc}";
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - index * 6) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// images are inlined so the report is a single file that can be attached to a ticket or CI artifact
+fn image_source(report_directory: &Path, relative_path: &str) -> Option<String> {
+    let image_path = report_directory.join(relative_path);
+    let bytes = fs::read(&image_path).ok()?;
+    let mime = match image_path.extension().and_then(|extension| extension.to_str()) {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        _ => "image/png",
+    };
+    Some(format!("data:{};base64,{}", mime, base64_encode(&bytes)))
+}
+
+fn comparison_file<'a>(artifact: &'a Artifact, key: &str) -> Option<&'a String> {
+    artifact.files.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, file)| file)
+}
+
+// exported comparisons stay next to the report rather than being inlined, so they're linked
+fn file_url(path: &std::path::Path) -> String {
+    let absolute = std::env::current_dir().map_or_else(|_| path.to_path_buf(), |current_dir| current_dir.join(path));
+    format!("file:///{}", absolute.display().to_string().replace('\\', "/").trim_start_matches('/').replace(' ', "%20"))
+}
+
+fn comparison_artifacts(test_pass: &TestPass) -> impl Iterator<Item = &Artifact> {
+    test_pass.tests.iter()
+        .filter(|test| test.state == TestResult::Fail)
+        .flat_map(|test| test.artifacts.iter())
+        .filter(|artifact| artifact.artifact_type == ArtifactType::Comparison)
+}
+
+// reading and encoding thousands of screenshots one at a time dominated large reports, so they're all
+// encoded up front on a bounded pool, keyed by their path relative to the report
+fn encode_images(test_pass: &TestPass, report_directory: &Path, workers: usize) -> HashMap<String, String> {
+    let mut image_paths: Vec<&String> = comparison_artifacts(test_pass).flat_map(|artifact| artifact.files.values()).collect();
+    image_paths.sort();
+    image_paths.dedup();
+    let sources = pipeline::map_bounded(&image_paths, workers, |image_path| image_source(report_directory, image_path));
+    image_paths.into_iter().zip(sources)
+        .filter_map(|(image_path, source)| source.map(|source| (image_path.clone(), source)))
+        .collect()
+}
+
+fn write_comparison(html: &mut String, artifact: &Artifact, images: &HashMap<String, String>, lang: Lang) {
+    writeln!(html, "<div class=\"comparison\">").unwrap();
+    for (key, caption) in [("approved", Message::GroundTruth), ("unapproved", Message::Incoming), ("difference", Message::Delta)] {
+        write!(html, "<figure><figcaption>{}</figcaption>", lang::text(lang, caption)).unwrap();
+        match comparison_file(artifact, key).and_then(|file| images.get(file)) {
+            Some(source) => write!(html, "<img src=\"{}\" alt=\"{} {}\">", source, escape(artifact.name.as_str()), key).unwrap(),
+            None => write!(html, "<p>{}</p>", lang::text(lang, Message::Missing)).unwrap(),
+        }
+        writeln!(html, "</figure>").unwrap();
+    }
+    writeln!(html, "</div>").unwrap();
+}
+
+fn write_test_info(html: &mut String, info: &TestInfo, lang: Lang) {
+    let mut parts = Vec::new();
+    if let Some(source_file) = &info.source_file {
+        let location = match info.source_file_line {
+            Some(line) => format!("{}:{}", source_file, line),
+            None => source_file.clone(),
+        };
+        parts.push(format!("<a href=\"file:///{}\">{}</a>", escape(source_file.replace('\\', "/").trim_start_matches('/')), escape(location.as_str())));
+    }
+    if let Some(owner) = &info.owner {
+        parts.push(lang::format(lang, Message::OwnedBy, &[&escape(owner.as_str())]));
+    }
+    if !info.tags.is_empty() {
+        parts.push(escape(info.tags.join(" ").as_str()));
+    }
+    if let Some(open_command) = &info.open_command {
+        parts.push(format!("<code>{}</code>", escape(open_command.as_str())));
+    }
+    if !parts.is_empty() {
+        writeln!(html, "<p class=\"info\">{}</p>", parts.join(" &middot; ")).unwrap();
+    }
+}
+
+fn write_log_excerpt(html: &mut String, excerpt: &LogExcerpt) {
+    write!(html, "<pre class=\"excerpt\" title=\"{}:{}\">", escape(excerpt.log_file.as_str()), excerpt.first_line_number).unwrap();
+    for (index, line) in excerpt.lines.iter().enumerate() {
+        if index == excerpt.matched {
+            writeln!(html, "<b>{}</b>", escape(line)).unwrap();
+        } else {
+            writeln!(html, "{}", escape(line)).unwrap();
+        }
+    }
+    writeln!(html, "</pre>").unwrap();
+}
+
+fn write_csv_profile(html: &mut String, profile: &CsvProfileSummary) {
+    writeln!(html, "<h4>{}</h4>", escape(profile.file.as_str())).unwrap();
+    writeln!(html, "<table class=\"profile\"><tr><th>frames</th><th>average</th><th>p50</th><th>p95</th><th>p99</th></tr>").unwrap();
+    writeln!(html, "<tr><td>{}</td><td>{:.2} ms</td><td>{:.2} ms</td><td>{:.2} ms</td><td>{:.2} ms</td></tr></table>",
+        profile.frames, profile.average_frame_time, profile.p50_frame_time, profile.p95_frame_time, profile.p99_frame_time).unwrap();
+    if !profile.top_stats.is_empty() {
+        writeln!(html, "<table class=\"profile\"><tr><th>stat</th><th>mean</th></tr>").unwrap();
+        for stat in profile.top_stats.iter() {
+            writeln!(html, "<tr><td>{}</td><td>{:.2} ms</td></tr>", escape(stat.name.as_str()), stat.mean).unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+    }
+}
+
+impl OutputFormatter for HtmlFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn io::Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let report_directory = context.report_directory;
+        let ignore_regexes = &context.config.ignore_regexes;
+        let lang = context.lang;
+        let images = encode_images(test_pass, report_directory, pipeline::worker_count(context.config.postprocess_workers));
+
+        let mut html = String::new();
+        let title = lang::text(lang, Message::TestReport);
+        writeln!(html, "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>", title, STYLE).unwrap();
+        writeln!(html, "<h1>{}</h1>", title).unwrap();
+        writeln!(html, "<p>{} &mdash; {}, {}</p>",
+            lang::format(lang, Message::Summary, &[&test_pass.succeeded, &test_pass.failed, &(test_pass.not_run + test_pass.succeeded_with_warnings)]),
+            lang::format(lang, Message::Elapsed, &[&test_pass.total_duration]),
+            lang::format(lang, Message::CreatedOn, &[&escape(test_pass.report_created_on.as_str())])).unwrap();
+        if let Some(engine_version) = &context.run_metadata.engine_version {
+            writeln!(html, "<p>{}</p>", lang::format(lang, Message::OnEngine, &[&escape(engine_version.to_string().as_str())])).unwrap();
+        }
+
+        for test in test_pass.tests.iter() {
+            let (class, label) = match test.state {
+                TestResult::Success => ("success", "Success"),
+                TestResult::Fail => ("fail", "Fail"),
+                _ => ("other", "Warning"),
+            };
+            writeln!(html, "<h3 class=\"{}\">{} {}</h3>", class, label, escape(test.full_test_path.as_str())).unwrap();
+            if let Some(info) = &test.info {
+                write_test_info(&mut html, info, lang);
+            }
+
+            for (entry_index, entry) in test.entries.iter().enumerate() {
+                if should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                    continue;
+                }
+                let entry_label = match entry.event.entry_type {
+                    _ if entry.expected => "Expected",
+                    EntryType::Info => continue,
+                    EntryType::Warning => "Warning",
+                    EntryType::Error => "Error",
+                };
+                let location = format!("{}:{}", escape(entry.filename.as_str()), entry.line_number);
+                let location = match context.source_url(entry) {
+                    Some(url) => format!("<a href=\"{}\">{}</a>", escape(url.as_str()), location),
+                    None => location,
+                };
+                writeln!(html, "<div class=\"entry\">{}: {}\n    {}</div>", entry_label, escape(entry.event.message.as_str()), location).unwrap();
+                if let Some(excerpt) = context.log_excerpt(test, entry_index) {
+                    write_log_excerpt(&mut html, excerpt);
+                }
+            }
+
+            for profile in context.csv_profiles.iter().filter(|profile| profile.path == test.full_test_path) {
+                write_csv_profile(&mut html, profile);
+            }
+
+            if let TestResult::Fail = test.state {
+                for artifact in test.artifacts.iter().filter(|artifact| artifact.artifact_type == ArtifactType::Comparison) {
+                    writeln!(html, "<h4>{}</h4>", escape(artifact.name.as_str())).unwrap();
+                    write_comparison(&mut html, artifact, &images, lang);
+                }
+                let exported = context.exported_comparisons(test);
+                if !exported.is_empty() {
+                    writeln!(html, "<ul class=\"exported\">").unwrap();
+                    for file in exported.iter() {
+                        writeln!(html, "<li><a href=\"{}\">{}</a></li>", escape(file_url(file).as_str()), escape(file.display().to_string().as_str())).unwrap();
+                    }
+                    writeln!(html, "</ul>").unwrap();
+                }
+            }
+        }
+
+        writeln!(html, "</body></html>").unwrap();
+        out.write_all(html.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_markup() {
+        assert_eq!(escape("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn comparison_file_ignores_key_case() {
+        let artifact = Artifact {
+            id: "1".to_owned(),
+            name: "Castle".to_owned(),
+            artifact_type: ArtifactType::Comparison,
+            files: vec![("Approved".to_owned(), "Approved/castle.png".to_owned())].into_iter().collect(),
+        };
+        assert_eq!(comparison_file(&artifact, "approved").map(String::as_str), Some("Approved/castle.png"));
+        assert_eq!(comparison_file(&artifact, "difference"), None);
+    }
+}
@@ -0,0 +1,54 @@
+use super::{OutputFormatter, ReportContext, VcsInfo};
+use crate::{analysis, crash, csvprofile, warmup, TestPass};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+pub struct JsonFormatter;
+
+// the report as the engine wrote it (with the expected flags filled in) plus what this run knows about it
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    vcs: VcsInfo<'a>,
+    engine_version: Option<String>,
+    shuffle_seed: Option<u64>,
+    session_name: Option<&'a str>,
+    meta: &'a BTreeMap<String, String>,
+    crashed: bool,
+    last_running_test: Option<&'a str>,
+    incomplete: bool, // the engine exited with tests still in process
+    in_flight_tests: Vec<&'a str>,
+    callstack: &'a [crash::StackFrame],
+    crash_context: Option<&'a crash::CrashContext>,
+    warmup: Option<&'a warmup::Warmup>,
+    csv_profiles: &'a [csvprofile::CsvProfileSummary],
+    ignore_regexes: &'a [String],
+    schema_warnings: &'a [String],
+    report: &'a TestPass,
+}
+
+impl OutputFormatter for JsonFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let json_report = JsonReport {
+            vcs: context.vcs(),
+            engine_version: context.run_metadata.engine_version.as_ref().map(|engine_version| engine_version.to_string()),
+            shuffle_seed: context.run_metadata.shuffle_seed,
+            session_name: context.run_metadata.session_name.as_deref(),
+            meta: &context.run_metadata.meta,
+            crashed: context.run_metadata.crashed,
+            last_running_test: if context.run_metadata.crashed { analysis::last_running_test(context.test_pass).map(|test| test.full_test_path.as_str()) } else { None },
+            incomplete: analysis::in_process_count(context.test_pass) > 0,
+            in_flight_tests: analysis::in_flight_tests(context.test_pass).iter().map(|test| test.full_test_path.as_str()).collect(),
+            callstack: &context.run_metadata.callstack,
+            crash_context: context.run_metadata.crash_context.as_ref(),
+            warmup: context.run_metadata.warmup.as_ref(),
+            csv_profiles: &context.csv_profiles,
+            ignore_regexes: &context.config.ignore_regexes,
+            schema_warnings: &context.test_pass.schema_warnings,
+            report: context.test_pass,
+        };
+        serde_json::to_writer_pretty(&mut *out, &json_report)?;
+        writeln!(out)
+    }
+}
@@ -0,0 +1,99 @@
+use super::html::escape;
+use super::{OutputFormatter, ReportContext};
+use crate::{should_ignore_message, EntryType, Test, TestResult};
+use std::io::{self, Write};
+
+pub struct JunitFormatter;
+
+// junit has no test paths, everything before the last dot becomes the class name so CI groups tests the way the session frontend does
+fn class_and_name(test: &Test) -> (&str, &str) {
+    match test.full_test_path.rsplit_once('.') {
+        Some((class, name)) => (class, name),
+        None => ("", test.full_test_path.as_str()),
+    }
+}
+
+pub(super) fn unexpected_errors<'a>(test: &'a Test, ignore_regexes: &'a [String]) -> impl Iterator<Item = &'a crate::Entry> {
+    test.entries.iter().filter(move |entry| {
+        matches!(entry.event.entry_type, EntryType::Error)
+            && !entry.expected
+            && !should_ignore_message(entry.event.message.as_str(), ignore_regexes)
+    })
+}
+
+impl OutputFormatter for JunitFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let ignore_regexes = &context.config.ignore_regexes;
+        let skipped = test_pass.tests.iter().filter(|test| !matches!(test.state, TestResult::Success | TestResult::Fail)).count();
+
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<testsuites name=\"runtests\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">",
+            test_pass.tests.len(), test_pass.failed, skipped, test_pass.total_duration)?;
+        writeln!(out, "  <testsuite name=\"automation\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\" timestamp=\"{}\">",
+            test_pass.tests.len(), test_pass.failed, skipped, test_pass.total_duration, escape(test_pass.report_created_on.as_str()))?;
+        let run_metadata = context.run_metadata;
+        let mut properties: Vec<(String, String)> = context.vcs().properties().into_iter().map(|(name, value)| (name.to_owned(), value)).collect();
+        if let Some(engine_version) = &run_metadata.engine_version {
+            properties.push(("engine_version".to_owned(), engine_version.to_string()));
+        }
+        if let Some(session_name) = &run_metadata.session_name {
+            properties.push(("session_name".to_owned(), session_name.clone()));
+        }
+        for (key, value) in run_metadata.meta.iter() {
+            properties.push((format!("meta.{}", key), value.clone()));
+        }
+        if !properties.is_empty() {
+            writeln!(out, "    <properties>")?;
+            for (name, value) in properties.iter() {
+                writeln!(out, "      <property name=\"{}\" value=\"{}\"/>", escape(name.as_str()), escape(value.as_str()))?;
+            }
+            writeln!(out, "    </properties>")?;
+        }
+
+        for test in test_pass.tests.iter() {
+            let (class, name) = class_and_name(test);
+            write!(out, "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"", escape(class), escape(name), test.duration)?;
+            // file and line are what CI servers link a test case to its source with
+            if let Some(info) = &test.info {
+                if let Some(source_file) = &info.source_file {
+                    write!(out, " file=\"{}\"", escape(source_file.as_str()))?;
+                }
+                if let Some(line) = info.source_file_line {
+                    write!(out, " line=\"{}\"", line)?;
+                }
+            }
+            match test.state {
+                TestResult::Success => writeln!(out, "/>")?,
+                TestResult::Fail => {
+                    writeln!(out, ">")?;
+                    let errors: Vec<&crate::Entry> = unexpected_errors(test, ignore_regexes).collect();
+                    let message = errors.first().map_or("test failed", |entry| entry.event.message.as_str());
+                    writeln!(out, "      <failure message=\"{}\">", escape(message))?;
+                    for entry in errors.iter() {
+                        writeln!(out, "{}\n    {}:{}", escape(entry.event.message.as_str()), escape(entry.filename.as_str()), entry.line_number)?;
+                    }
+                    writeln!(out, "      </failure>")?;
+                    // the attachment convention jenkins' junit-attachments plugin and gitlab both pick up
+                    let exported = context.exported_comparisons(test);
+                    if !exported.is_empty() {
+                        writeln!(out, "      <system-out>")?;
+                        for file in exported.iter() {
+                            writeln!(out, "[[ATTACHMENT|{}]]", escape(file.display().to_string().as_str()))?;
+                        }
+                        writeln!(out, "      </system-out>")?;
+                    }
+                    writeln!(out, "    </testcase>")?;
+                }
+                _ => {
+                    writeln!(out, ">")?;
+                    writeln!(out, "      <skipped message=\"{:?}\"/>", test.state)?;
+                    writeln!(out, "    </testcase>")?;
+                }
+            }
+        }
+
+        writeln!(out, "  </testsuite>")?;
+        writeln!(out, "</testsuites>")
+    }
+}
@@ -0,0 +1,136 @@
+use super::{OutputFormatter, ReportContext};
+use crate::lang::{self, Message};
+use crate::{analysis, should_ignore_message, EntryType, TestResult};
+use std::io::{self, Write};
+
+pub struct MarkdownFormatter;
+
+// pipes would end the table cell early
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+// meant for pull request comments and CI job summaries, passing tests are only counted
+impl OutputFormatter for MarkdownFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()> {
+        let test_pass = context.test_pass;
+        let ignore_regexes = &context.config.ignore_regexes;
+        let lang = context.lang;
+        let bold = |count: i32| format!("**{}**", count);
+
+        writeln!(out, "## {}", lang::text(lang, Message::TestReport))?;
+        writeln!(out)?;
+        writeln!(out, "{}, {}",
+            lang::format(lang, Message::Summary, &[&bold(test_pass.succeeded), &bold(test_pass.failed), &bold(test_pass.not_run + test_pass.succeeded_with_warnings)]),
+            lang::format(lang, Message::Elapsed, &[&test_pass.total_duration]))?;
+        if context.run_metadata.crashed {
+            writeln!(out)?;
+            match analysis::last_running_test(test_pass) {
+                Some(test) => writeln!(out, "**The engine crashed during execution**, last running test `{}`", test.full_test_path)?,
+                None => writeln!(out, "**The engine crashed during execution**")?,
+            }
+            if let Some(error_message) = context.run_metadata.crash_context.as_ref().and_then(|crash_context| crash_context.error_message.as_ref()) {
+                writeln!(out)?;
+                writeln!(out, "> {}", error_message.replace('\n', " "))?;
+            }
+            if !context.run_metadata.callstack.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "```")?;
+                for frame in context.run_metadata.callstack.iter() {
+                    writeln!(out, "{}", frame)?;
+                }
+                writeln!(out, "```")?;
+            }
+        } else if analysis::in_process_count(test_pass) > 0 {
+            writeln!(out)?;
+            let in_flight: Vec<String> = analysis::in_flight_tests(test_pass).iter().map(|test| format!("`{}`", test.full_test_path)).collect();
+            let in_process = analysis::in_process_count(test_pass);
+            if in_flight.is_empty() {
+                writeln!(out, "**The run is incomplete**, the engine exited with {} tests still running", in_process)?;
+            } else {
+                writeln!(out, "**The run is incomplete**, the engine exited with {} tests still running: {}", in_process, in_flight.join(", "))?;
+            }
+        }
+        if let Some(changelist) = &context.run_metadata.changelist {
+            writeln!(out)?;
+            writeln!(out, "{}", lang::format(lang, Message::AtChangelist, &[changelist]))?;
+        }
+        if let Some(git_info) = &context.run_metadata.git {
+            writeln!(out)?;
+            writeln!(out, "{}", lang::format(lang, Message::AtCommit, &[&format!("`{}`", git_info)]))?;
+        }
+        if let Some(engine_version) = &context.run_metadata.engine_version {
+            writeln!(out)?;
+            writeln!(out, "{}", lang::format(lang, Message::OnEngine, &[engine_version]))?;
+        }
+        if let Some(shuffle_seed) = context.run_metadata.shuffle_seed {
+            writeln!(out)?;
+            writeln!(out, "shuffled with seed `{}`", shuffle_seed)?;
+        }
+        if let Some(session_name) = &context.run_metadata.session_name {
+            writeln!(out)?;
+            writeln!(out, "{}", lang::format(lang, Message::Session, &[&format!("`{}`", session_name)]))?;
+        }
+        if !context.run_metadata.meta.is_empty() {
+            writeln!(out)?;
+            let labels: Vec<String> = context.run_metadata.meta.iter().map(|(key, value)| format!("`{}={}`", key, value)).collect();
+            writeln!(out, "{}", lang::format(lang, Message::Labels, &[&labels.join(" ")]))?;
+        }
+
+        let unsuccessful: Vec<&crate::Test> = test_pass.tests.iter().filter(|test| test.state != TestResult::Success).collect();
+        if unsuccessful.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out)?;
+        writeln!(out, "| {} | {} | {} |", lang::text(lang, Message::State), lang::text(lang, Message::Test), lang::text(lang, Message::FirstError))?;
+        writeln!(out, "| --- | --- | --- |")?;
+        for test in unsuccessful.iter() {
+            let first_error = test.entries.iter()
+                .find(|entry| matches!(entry.event.entry_type, EntryType::Error) && !entry.expected && !should_ignore_message(entry.event.message.as_str(), ignore_regexes))
+                .map_or(String::new(), |entry| match context.source_url(entry) {
+                    Some(url) => format!("{} ([{}:{}]({}))", cell(entry.event.message.as_str()), cell(entry.filename.as_str()), entry.line_number, url),
+                    None => cell(entry.event.message.as_str()),
+                });
+            writeln!(out, "| {:?} | `{}` | {} |", test.state, test.full_test_path, first_error)?;
+        }
+
+        for test in unsuccessful.iter().filter(|test| test.state == TestResult::Fail) {
+            writeln!(out)?;
+            writeln!(out, "<details><summary>{}</summary>", test.full_test_path)?;
+            writeln!(out)?;
+            writeln!(out, "```")?;
+            for entry in test.entries.iter() {
+                if entry.expected || should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                    continue;
+                }
+                match entry.event.entry_type {
+                    EntryType::Info => continue,
+                    EntryType::Warning => writeln!(out, "{}", lang::format(lang, Message::WarningEntry, &[&entry.event.message]))?,
+                    EntryType::Error => writeln!(out, "{}", lang::format(lang, Message::ErrorEntry, &[&entry.event.message]))?,
+                }
+                writeln!(out, "    {}:{}", entry.filename, entry.line_number)?;
+            }
+            writeln!(out, "```")?;
+            // code blocks can't hold links, the locations that have one are listed below it
+            let mut linked: Vec<(String, String)> = Vec::new();
+            for entry in test.entries.iter().filter(|entry| !entry.expected && !matches!(entry.event.entry_type, EntryType::Info) && !should_ignore_message(entry.event.message.as_str(), ignore_regexes)) {
+                if let Some(url) = context.source_url(entry) {
+                    let location = format!("{}:{}", entry.filename, entry.line_number);
+                    if !linked.iter().any(|(linked_location, _)| *linked_location == location) {
+                        linked.push((location, url));
+                    }
+                }
+            }
+            for (location, url) in linked {
+                writeln!(out, "- [{}]({})", location, url)?;
+            }
+            for file in context.exported_comparisons(test) {
+                writeln!(out, "- `{}`", file.display())?;
+            }
+            writeln!(out)?;
+            writeln!(out, "</details>")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use crate::comparisons::ComparisonExport;
+use crate::csvprofile::CsvProfileSummary;
+use crate::datetime::DisplayZone;
+use crate::lang::Lang;
+use crate::logexcerpts::LogExcerpt;
+use crate::theme::Theme;
+use crate::{paths, Entry, RunMetadata, Test, TestConfiguration, TestPass};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+mod buildkite;
+mod console;
+mod ctrf;
+mod gitlab;
+mod html;
+mod json;
+mod junit;
+mod markdown;
+
+pub const FORMAT_NAMES: &[&str] = &["console", "json", "junit", "markdown", "html", "gitlab", "buildkite", "ctrf"];
+
+pub struct RenderOptions {
+    pub plain: bool,
+    pub durations: bool,
+    pub timestamps: bool,
+    pub timeline: bool,
+    pub warnings_by_file: bool,
+    pub show_ignored: bool,
+    pub summary_only: bool, // entries are going to a details file instead
+    pub width: Option<usize>, // the console wraps messages and shortens paths to this many columns
+}
+
+// where the tested source came from, every structured export carries all of it
+#[derive(Serialize)]
+pub struct VcsInfo<'a> {
+    pub changelist: Option<&'a str>,
+    pub commit: Option<&'a str>,
+    pub branch: Option<&'a str>,
+    pub dirty: Option<bool>,
+}
+
+impl VcsInfo<'_> {
+    // as name/value pairs for formats without structure of their own, ie junit properties
+    pub fn properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = Vec::new();
+        if let Some(changelist) = self.changelist {
+            properties.push(("changelist", changelist.to_owned()));
+        }
+        if let Some(commit) = self.commit {
+            properties.push(("commit", commit.to_owned()));
+        }
+        if let Some(branch) = self.branch {
+            properties.push(("branch", branch.to_owned()));
+        }
+        if let Some(dirty) = self.dirty {
+            properties.push(("dirty", dirty.to_string()));
+        }
+        properties
+    }
+}
+
+// everything a formatter may need to render a report
+pub struct ReportContext<'a> {
+    pub test_pass: &'a TestPass,
+    pub config: &'a TestConfiguration,
+    pub run_metadata: &'a RunMetadata,
+    pub report_directory: &'a Path,
+    pub theme: Theme,
+    pub zone: DisplayZone,
+    pub lang: Lang,
+    pub csv_profiles: Vec<CsvProfileSummary>, // empty unless --csv-profiles
+    pub comparison_export: Option<ComparisonExport>,
+    pub log_excerpts: Vec<LogExcerpt>, // empty unless --log-excerpts
+    pub options: RenderOptions,
+}
+
+impl ReportContext<'_> {
+    // the engine log lines around one of a test's entries, None unless --log-excerpts found them
+    pub fn log_excerpt(&self, test: &Test, entry_index: usize) -> Option<&LogExcerpt> {
+        self.log_excerpts.iter().find(|excerpt| excerpt.entry_index == entry_index && excerpt.test_path == test.full_test_path)
+    }
+
+    // the files the engine exported for a test's screenshot comparisons, empty without an export
+    pub fn exported_comparisons(&self, test: &Test) -> Vec<PathBuf> {
+        self.comparison_export.as_ref().map_or_else(Vec::new, |comparison_export| comparison_export.files_for(test))
+    }
+
+    pub fn vcs(&self) -> VcsInfo<'_> {
+        let git = self.run_metadata.git.as_ref();
+        VcsInfo {
+            changelist: self.run_metadata.changelist.as_deref(),
+            commit: git.map(|git| git.commit.as_str()),
+            branch: git.map(|git| git.branch.as_str()),
+            dirty: git.map(|git| git.dirty),
+        }
+    }
+
+    // where an entry's file:line is on source hosting, None without a source_url_template
+    pub fn source_url(&self, entry: &Entry) -> Option<String> {
+        let template = self.config.source_url_template.as_ref()?;
+        let source_root = match &self.config.source_root {
+            Some(source_root) => source_root.clone(),
+            None => crate::project_directory(self.config).display().to_string(),
+        };
+        paths::source_url(template.as_str(), source_root.as_str(), entry.filename.as_str(), entry.line_number)
+    }
+}
+
+pub trait OutputFormatter {
+    fn write(&self, context: &ReportContext, out: &mut dyn Write) -> io::Result<()>;
+}
+
+pub fn formatter(name: &str) -> Option<Box<dyn OutputFormatter>> {
+    match name {
+        "console" => Some(Box::new(console::ConsoleFormatter)),
+        "json" => Some(Box::new(json::JsonFormatter)),
+        "junit" => Some(Box::new(junit::JunitFormatter)),
+        "markdown" | "md" => Some(Box::new(markdown::MarkdownFormatter)),
+        "html" => Some(Box::new(html::HtmlFormatter)),
+        "gitlab" => Some(Box::new(gitlab::GitlabFormatter)),
+        "buildkite" => Some(Box::new(buildkite::BuildkiteFormatter)),
+        "ctrf" => Some(Box::new(ctrf::CtrfFormatter)),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct FormatSpec {
+    pub name: String,
+    pub destination: Option<PathBuf>, // None writes to stdout
+}
+
+// name or name=path, ie --format console --format junit=results.xml
+pub fn parse_format_spec(spec: &str) -> FormatSpec {
+    match spec.split_once('=') {
+        Some((name, destination)) => FormatSpec { name: name.trim().to_lowercase(), destination: Some(PathBuf::from(destination)) },
+        None => FormatSpec { name: spec.trim().to_lowercase(), destination: None },
+    }
+}
+
+pub fn write_output(spec: &FormatSpec, context: &ReportContext) -> io::Result<()> {
+    let formatter = formatter(spec.name.as_str()).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown format {}", spec.name)))?;
+    match &spec.destination {
+        Some(destination) => {
+            let mut file = io::BufWriter::new(File::create(destination)?);
+            formatter.write(context, &mut file)?;
+            file.flush()
+        }
+        // inside a buildkite job the annotation goes to the build page rather than the log
+        None if spec.name == "buildkite" && buildkite::on_agent() => buildkite::annotate(context),
+        None => {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            formatter.write(context, &mut handle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_specs() {
+        let spec = parse_format_spec(" JUnit=out/results.xml");
+        assert_eq!(spec.name, "junit");
+        assert_eq!(spec.destination, Some(PathBuf::from("out/results.xml")));
+        assert_eq!(parse_format_spec("console").destination, None);
+        assert!(FORMAT_NAMES.iter().all(|name| formatter(name).is_some()));
+        assert!(formatter("yaml").is_none());
+    }
+
+    #[test]
+    fn junit_splits_class_and_name() {
+        let mut test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "duration": 1, "entries": [],
+                    "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Fail", "duration": 2, "warnings": 0, "errors": 1,
+                    "artifacts": [], "entries": [{"event": {"type": "Error", "message": "Door <stuck>", "context": "", "artifact": ""},
+                    "filename": "Door.cpp", "lineNumber": 12, "timestamp": ""}]}"#);
+        test_pass.report_created_on = "2023.10.01-12.34.56".to_owned();
+        test_pass.succeeded = 1;
+        test_pass.failed = 1;
+        test_pass.total_duration = 3.0;
+        let config: TestConfiguration = toml::from_str(r#"
+            path_to_unrealengine = ""
+            path_to_project = ""
+            path_to_reports = ""
+            run_tests = ""
+            test_exit = ""
+            ignore_regexes = []"#).unwrap();
+        let run_metadata = RunMetadata {
+            session_name: Some("nightly-win64".to_owned()),
+            changelist: Some("48213".to_owned()),
+            git: Some(crate::vcs::GitInfo { commit: "9f3c2e1".to_owned(), branch: "main".to_owned(), dirty: true }),
+            meta: vec![("gpu".to_owned(), "rtx 4090".to_owned())].into_iter().collect(),
+            ..RunMetadata::default()
+        };
+        let context = ReportContext {
+            test_pass: &test_pass,
+            config: &config,
+            run_metadata: &run_metadata,
+            report_directory: Path::new("."),
+            theme: Theme::from_config(None, Lang::En),
+            zone: DisplayZone::Utc,
+            lang: Lang::En,
+            csv_profiles: Vec::new(),
+            comparison_export: None,
+            log_excerpts: Vec::new(),
+            options: RenderOptions { plain: true, durations: false, timestamps: false, timeline: false, warnings_by_file: false, show_ignored: false, summary_only: false, width: None },
+        };
+
+        let mut out = Vec::new();
+        formatter("junit").unwrap().write(&context, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains(r#"<testcase classname="Project.Door" name="Opens" time="1"/>"#));
+        assert!(xml.contains(r#"<failure message="Door &lt;stuck&gt;">"#));
+        assert!(xml.contains("Door.cpp:12"));
+        assert!(xml.contains(r#"<property name="session_name" value="nightly-win64"/>"#));
+        assert!(xml.contains(r#"<property name="meta.gpu" value="rtx 4090"/>"#));
+        assert!(xml.contains(r#"<property name="changelist" value="48213"/>"#));
+        assert!(xml.contains(r#"<property name="branch" value="main"/>"#));
+        assert!(xml.contains(r#"<property name="dirty" value="true"/>"#));
+
+        let mut out = Vec::new();
+        formatter("json").unwrap().write(&context, &mut out).unwrap();
+        let json_report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json_report["changelist"], "48213");
+        assert_eq!(json_report["commit"], "9f3c2e1");
+        assert_eq!(json_report["branch"], "main");
+        assert_eq!(json_report["dirty"], true);
+
+        let mut out = Vec::new();
+        formatter("buildkite").unwrap().write(&context, &mut out).unwrap();
+        let annotation = String::from_utf8(out).unwrap();
+        assert!(annotation.contains("<code>nightly-win64</code>: 1 passed, 1 failed, 0 other"));
+        assert!(annotation.contains("<details><summary><code>Project.Door.Closes</code> — Door &lt;stuck&gt;</summary>"));
+        assert!(!annotation.contains("Project.Door.Opens"));
+
+        let mut out = Vec::new();
+        formatter("gitlab").unwrap().write(&context, &mut out).unwrap();
+        let issues: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(issues[0]["location"]["path"], "Door.cpp");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 12);
+        assert_eq!(issues[0]["description"], "Project.Door.Closes: Door <stuck>");
+
+        let mut out = Vec::new();
+        formatter("ctrf").unwrap().write(&context, &mut out).unwrap();
+        let ctrf: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(ctrf["reportFormat"], "CTRF");
+        assert_eq!(ctrf["results"]["summary"]["passed"], 1);
+        assert_eq!(ctrf["results"]["summary"]["failed"], 1);
+        assert_eq!(ctrf["results"]["tests"][1]["status"], "failed");
+        assert_eq!(ctrf["results"]["tests"][1]["suite"], "Project.Door");
+        assert_eq!(ctrf["results"]["tests"][1]["message"], "Door <stuck>");
+        assert_eq!(ctrf["results"]["environment"]["buildName"], "nightly-win64");
+        assert_eq!(ctrf["results"]["environment"]["branchName"], "main");
+        assert_eq!(ctrf["results"]["environment"]["extra"]["changelist"], "48213");
+        assert_eq!(ctrf["results"]["environment"]["extra"]["dirty"], "true");
+    }
+}
@@ -0,0 +1,92 @@
+use crate::http;
+use serde::Deserialize;
+
+// with [gitlab] merge_request_comment the markdown summary is posted to the merge request the pipeline runs for,
+// and updated in place by later runs of the same session instead of piling up comments
+#[derive(Debug, Deserialize)]
+pub struct GitlabConfiguration {
+    #[serde(default)]
+    pub merge_request_comment: bool,
+    #[serde(default = "default_token_env")]
+    pub token_env: String, // environment variable holding a token with api scope, CI_JOB_TOKEN can't write notes
+}
+
+pub(crate) fn default_token_env() -> String {
+    "GITLAB_TOKEN".to_owned()
+}
+
+// what gitlab tells a merge request pipeline about itself
+struct MergeRequest {
+    api_url: String,
+    project_id: String,
+    iid: String,
+}
+
+fn merge_request_from_env() -> Option<MergeRequest> {
+    Some(MergeRequest {
+        api_url: std::env::var("CI_API_V4_URL").ok()?,
+        project_id: std::env::var("CI_PROJECT_ID").ok()?,
+        iid: std::env::var("CI_MERGE_REQUEST_IID").ok()?,
+    })
+}
+
+#[derive(Deserialize)]
+struct Note {
+    id: u64,
+    body: String,
+}
+
+// hidden in the rendered comment, finds the comment to update on the next run
+fn marker(session_name: Option<&str>) -> String {
+    match session_name {
+        Some(session_name) => format!("<!-- runtests:{} -->", session_name.replace("--", "-")),
+        None => "<!-- runtests -->".to_owned(),
+    }
+}
+
+fn find_note(notes: &[Note], marker: &str) -> Option<u64> {
+    notes.iter().find(|note| note.body.contains(marker)).map(|note| note.id)
+}
+
+// Ok says whether the comment was created or updated, pipelines that don't belong to a merge request are an error
+// so a misconfigured job doesn't silently post nothing
+pub fn post_merge_request_comment(config: &GitlabConfiguration, summary: &str, session_name: Option<&str>) -> Result<&'static str, String> {
+    let merge_request = merge_request_from_env().ok_or_else(|| "not a merge request pipeline (CI_API_V4_URL, CI_PROJECT_ID or CI_MERGE_REQUEST_IID is unset)".to_owned())?;
+    let token = std::env::var(config.token_env.as_str()).map_err(|_| format!("{} is unset", config.token_env))?;
+    let headers = [("PRIVATE-TOKEN", token.as_str())];
+    let notes_url = format!("{}/projects/{}/merge_requests/{}/notes", merge_request.api_url.trim_end_matches('/'), merge_request.project_id, merge_request.iid);
+
+    let marker = marker(session_name);
+    let body = serde_json::json!({ "body": format!("{}\n{}", summary.trim_end(), marker) });
+    // the newest hundred comments, an older one of ours is buried deep enough that a new one is better anyway
+    let notes = http::send("GET", format!("{}?sort=desc&order_by=updated_at&per_page=100", notes_url).as_str(), &headers, None)?;
+    let notes: Vec<Note> = serde_json::from_str(notes.as_str()).map_err(|error| format!("unexpected notes response: {}", error))?;
+    match find_note(&notes, marker.as_str()) {
+        Some(note_id) => {
+            http::send_json("PUT", format!("{}/{}", notes_url, note_id).as_str(), &headers, &body)?;
+            Ok("updated")
+        }
+        None => {
+            http::send_json("POST", notes_url.as_str(), &headers, &body)?;
+            Ok("posted")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_are_found_by_session() {
+        let notes = vec![
+            Note { id: 1, body: "looks good".to_owned() },
+            Note { id: 2, body: "## Test report\n<!-- runtests:nightly -->".to_owned() },
+            Note { id: 3, body: "## Test report\n<!-- runtests -->".to_owned() },
+        ];
+        assert_eq!(find_note(&notes, marker(Some("nightly")).as_str()), Some(2));
+        assert_eq!(find_note(&notes, marker(None).as_str()), Some(3));
+        assert_eq!(find_note(&notes, marker(Some("smoke")).as_str()), None);
+        assert_eq!(marker(Some("a-->b")), "<!-- runtests:a->b -->");
+    }
+}
@@ -0,0 +1,488 @@
+use crate::perf::Metric;
+use crate::theme::Theme;
+use crate::{RunMetadata, TestPass, TestResult};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryTest {
+    pub path: String,
+    pub state: TestResult,
+    pub duration: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>, // failed tests only, see analysis::failure_signature
+}
+
+// one line of the history file per recorded run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryRun {
+    pub report_created_on: String,
+    pub changelist: Option<String>,
+    pub commit: Option<String>,
+    pub branch: Option<String>,
+    pub dirty: Option<bool>,
+    pub engine_version: Option<String>,
+    pub session_name: Option<String>,
+    #[serde(default)]
+    pub meta: BTreeMap<String, String>,
+    pub warmup_seconds: Option<f32>,
+    pub tests: Vec<HistoryTest>,
+    #[serde(default)]
+    pub metrics: Vec<Metric>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DurationRegressionConfiguration {
+    pub threshold_percent: f32,
+    #[serde(default = "default_min_duration")]
+    pub min_duration: f32, // tests faster than this are too noisy to compare
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "default_window")]
+    pub window: usize, // number of most recent runs the mean is taken over
+    #[serde(default)]
+    pub fail_run: bool,
+}
+
+pub(crate) fn default_min_duration() -> f32 {
+    1.0
+}
+
+pub(crate) fn default_min_samples() -> usize {
+    3
+}
+
+pub(crate) fn default_window() -> usize {
+    10
+}
+
+pub fn load_history(history_file: &Path) -> Vec<HistoryRun> {
+    let contents = match fs::read_to_string(history_file) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    contents.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(run) => Some(run),
+            Err(error) => {
+                eprintln!("{}{}", "skipping unreadable history line: ".yellow(), error);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn append_run(history_file: &Path, run: &HistoryRun) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(history_file)?;
+    let line = serde_json::to_string(run).expect("failed to serialize history");
+    writeln!(file, "{}", line)
+}
+
+// replaces the whole file, written next to it first so an interrupted write can't lose the history
+pub fn write_history(history_file: &Path, runs: &[HistoryRun]) -> std::io::Result<()> {
+    let temporary = history_file.with_extension("jsonl.tmp");
+    let mut contents = String::new();
+    for run in runs {
+        contents.push_str(serde_json::to_string(run).expect("failed to serialize history").as_str());
+        contents.push('\n');
+    }
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, history_file)
+}
+
+pub fn history_run(test_pass: &TestPass, run_metadata: &RunMetadata, metrics: Vec<Metric>) -> HistoryRun {
+    let git = run_metadata.git.as_ref();
+    HistoryRun {
+        report_created_on: test_pass.report_created_on.clone(),
+        changelist: run_metadata.changelist.clone(),
+        commit: git.map(|git| git.commit.clone()),
+        branch: git.map(|git| git.branch.clone()),
+        dirty: git.map(|git| git.dirty),
+        engine_version: run_metadata.engine_version.as_ref().map(|engine_version| engine_version.to_string()),
+        session_name: run_metadata.session_name.clone(),
+        meta: run_metadata.meta.clone(),
+        warmup_seconds: run_metadata.warmup.as_ref().map(|warmup| warmup.seconds),
+        tests: test_pass.tests.iter()
+            .map(|test| HistoryTest { path: test.full_test_path.clone(), state: test.state, duration: test.duration, signature: test.signature.clone() })
+            .collect(),
+        metrics,
+    }
+}
+
+pub struct DurationRegression {
+    pub path: String,
+    pub mean: f32,
+    pub duration: f32,
+}
+
+// mean duration of each test's successful runs within the most recent `window` runs
+fn mean_durations(history: &[HistoryRun], window: usize) -> HashMap<&str, (f32, usize)> {
+    let mut totals: HashMap<&str, (f32, usize)> = HashMap::new();
+    for run in history.iter().rev().take(window) {
+        for test in run.tests.iter().filter(|test| test.state == TestResult::Success) {
+            let total = totals.entry(test.path.as_str()).or_insert((0.0, 0));
+            total.0 += test.duration;
+            total.1 += 1;
+        }
+    }
+    totals.into_iter().map(|(path, (sum, count))| (path, (sum / count as f32, count))).collect()
+}
+
+pub fn duration_regressions(history: &[HistoryRun], test_pass: &TestPass, config: &DurationRegressionConfiguration) -> Vec<DurationRegression> {
+    let means = mean_durations(history, config.window);
+    let mut regressions: Vec<DurationRegression> = test_pass.tests.iter()
+        .filter(|test| test.state == TestResult::Success && test.duration >= config.min_duration)
+        .filter_map(|test| {
+            let (mean, samples) = *means.get(test.full_test_path.as_str())?;
+            if samples < config.min_samples || test.duration <= mean * (1.0 + config.threshold_percent / 100.0) {
+                return None;
+            }
+            Some(DurationRegression { path: test.full_test_path.clone(), mean, duration: test.duration })
+        })
+        .collect();
+    regressions.sort_by(|a, b| (b.duration / b.mean).partial_cmp(&(a.duration / a.mean)).unwrap_or(std::cmp::Ordering::Equal));
+    regressions
+}
+
+pub fn print_duration_regressions(regressions: &[DurationRegression], theme: &Theme) {
+    if regressions.is_empty() {
+        return;
+    }
+    eprintln!("{}", theme.warning.paint("performance regressions"));
+    for regression in regressions {
+        let percent = (regression.duration / regression.mean - 1.0) * 100.0;
+        eprintln!("{}{:.2}s -> {:.2}s (+{:.0}%) {}", theme.spacer(), regression.mean, regression.duration, percent, regression.path);
+    }
+}
+
+// listing every test of a suite that vanished wholesale doesn't help, the count says enough
+const MAX_LISTED_TEST_CHANGES: usize = 20;
+
+pub struct TestSetChanges<'a> {
+    pub added: Vec<&'a str>,
+    pub removed: Vec<&'a str>,
+    pub failing_differently: Vec<&'a str>, // failed both times, with another first error
+}
+
+// the failures of the most recent run of the same session by test, with their signatures when it recorded them
+fn previous_failures<'a>(history: &'a [HistoryRun], session_name: Option<&str>) -> HashMap<&'a str, Option<&'a str>> {
+    history.iter().rev().find(|run| run.session_name.as_deref() == session_name)
+        .map(|run| run.tests.iter().filter(|test| test.state == TestResult::Fail).map(|test| (test.path.as_str(), test.signature.as_deref())).collect())
+        .unwrap_or_default()
+}
+
+// tests that appeared or disappeared since the most recent run of the same session (runs of other
+// sessions select other tests), None when there's no such run to compare with
+pub fn test_set_changes<'a>(history: &'a [HistoryRun], test_pass: &'a TestPass, session_name: Option<&str>) -> Option<TestSetChanges<'a>> {
+    let previous = history.iter().rev().find(|run| run.session_name.as_deref() == session_name)?;
+    let previous_tests: HashSet<&str> = previous.tests.iter().map(|test| test.path.as_str()).collect();
+    let current_tests: HashSet<&str> = test_pass.tests.iter().map(|test| test.full_test_path.as_str()).collect();
+    let mut added: Vec<&str> = current_tests.difference(&previous_tests).copied().collect();
+    let mut removed: Vec<&str> = previous_tests.difference(&current_tests).copied().collect();
+    added.sort_unstable();
+    removed.sort_unstable();
+    let previous_failures = previous_failures(history, session_name);
+    let failing_differently = test_pass.tests.iter()
+        .filter(|test| test.state == TestResult::Fail)
+        .filter(|test| matches!((previous_failures.get(test.full_test_path.as_str()), test.signature.as_deref()), (Some(Some(previous)), Some(current)) if *previous != current))
+        .map(|test| test.full_test_path.as_str())
+        .collect();
+    Some(TestSetChanges { added, removed, failing_differently })
+}
+
+// failures the most recent run of the same session didn't have, every failure is new without one; a test that
+// failed then too is new when its failure signature changed, history from before signatures compares by test only
+pub fn new_failures<'a>(history: &[HistoryRun], test_pass: &'a TestPass, session_name: Option<&str>) -> Vec<&'a str> {
+    let previous_failures = previous_failures(history, session_name);
+    test_pass.tests.iter()
+        .filter(|test| test.state == TestResult::Fail)
+        .filter(|test| match previous_failures.get(test.full_test_path.as_str()) {
+            Some(Some(previous)) => test.signature.as_deref().is_some_and(|current| current != *previous),
+            Some(None) => false,
+            None => true,
+        })
+        .map(|test| test.full_test_path.as_str())
+        .collect()
+}
+
+pub struct FailingSince<'a> {
+    pub path: &'a str,
+    pub first_failing: &'a HistoryRun,
+    pub last_passing: Option<&'a HistoryRun>, // None when it failed in every run of the session that ran it
+}
+
+// for each test failing in the most recent run of the session, the run its current streak of failures began
+// at and the run before that where it passed, ie the changes that broke it lie between the two; runs that
+// didn't run the test (not run, filtered out) don't end the streak
+pub fn failing_since<'a>(history: &'a [HistoryRun], session_name: Option<&str>) -> Vec<FailingSince<'a>> {
+    let runs: Vec<(&HistoryRun, HashMap<&str, TestResult>)> = history.iter()
+        .filter(|run| run.session_name.as_deref() == session_name)
+        .map(|run| (run, run.tests.iter().map(|test| (test.path.as_str(), test.state)).collect()))
+        .collect();
+    let (latest, _) = match runs.last() {
+        Some(latest) => latest,
+        None => return Vec::new(),
+    };
+    latest.tests.iter()
+        .filter(|test| test.state == TestResult::Fail)
+        .map(|test| {
+            let mut failing_since = FailingSince { path: test.path.as_str(), first_failing: latest, last_passing: None };
+            for (run, states) in runs.iter().rev().skip(1) {
+                match states.get(test.path.as_str()) {
+                    Some(TestResult::Fail) => failing_since.first_failing = run,
+                    Some(TestResult::Success) => {
+                        failing_since.last_passing = Some(run);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            failing_since
+        })
+        .collect()
+}
+
+// the changelist, else the commit, else when the report was written
+fn change_label(run: &HistoryRun) -> String {
+    match (&run.changelist, &run.commit) {
+        (Some(changelist), _) => format!("changelist {}", changelist),
+        (None, Some(commit)) => format!("commit {}", commit),
+        (None, None) => format!("the run of {}", run.report_created_on),
+    }
+}
+
+pub fn print_failing_since(failing: &[FailingSince], theme: &Theme) {
+    for failing_since in failing {
+        let since = match failing_since.last_passing {
+            Some(last_passing) => format!("failing since {}, passed at {}", change_label(failing_since.first_failing), change_label(last_passing)),
+            None => format!("failing since {} or earlier, no recorded pass", change_label(failing_since.first_failing)),
+        };
+        println!("{} {}", theme.fail.paint(failing_since.path), since);
+    }
+}
+
+pub fn print_test_set_changes(changes: &TestSetChanges, theme: &Theme) {
+    for (tests, label, style) in [(&changes.added, "added", &theme.success), (&changes.removed, "removed", &theme.fail), (&changes.failing_differently, "failing differently", &theme.warning)] {
+        if tests.is_empty() {
+            continue;
+        }
+        eprintln!("{}", style.paint(format!("{} tests {} since the last run", tests.len(), label).as_str()));
+        for test in tests.iter().take(MAX_LISTED_TEST_CHANGES) {
+            eprintln!("{}{}", theme.spacer(), test);
+        }
+        if tests.len() > MAX_LISTED_TEST_CHANGES {
+            eprintln!("{}and {} more", theme.spacer(), tests.len() - MAX_LISTED_TEST_CHANGES);
+        }
+    }
+}
+
+pub const ORDER_NAMES: &[&str] = &["fastest-first", "slowest-first", "risk-first"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestOrder {
+    FastestFirst,
+    SlowestFirst, // longest first also balances --jobs best
+    RiskFirst,    // tests that failed recently first, a failing run finds out sooner
+}
+
+pub fn parse_order(name: &str) -> Option<TestOrder> {
+    match name {
+        "fastest-first" => Some(TestOrder::FastestFirst),
+        "slowest-first" => Some(TestOrder::SlowestFirst),
+        "risk-first" => Some(TestOrder::RiskFirst),
+        _ => None,
+    }
+}
+
+// what a name on the command line covers, a prefix covers every recorded test under it
+fn covers(test: &str, path: &str) -> bool {
+    path.starts_with(test)
+}
+
+// failures within the most recent `window` runs, the latest run weighs the most
+fn failure_scores(history: &[HistoryRun], window: usize) -> HashMap<&str, usize> {
+    let mut scores: HashMap<&str, usize> = HashMap::new();
+    for (age, run) in history.iter().rev().take(window).enumerate() {
+        for test in run.tests.iter().filter(|test| test.state == TestResult::Fail) {
+            *scores.entry(test.path.as_str()).or_insert(0) += window - age;
+        }
+    }
+    scores
+}
+
+// reorders the tests by their recorded durations or failures; the sort is stable, so tests the history knows
+// nothing about keep their order, after the known ones
+pub fn order_tests(tests: &mut [String], history: &[HistoryRun], order: TestOrder) {
+    let window = default_window();
+    match order {
+        TestOrder::FastestFirst | TestOrder::SlowestFirst => {
+            let means = mean_durations(history, window);
+            let duration = |test: &str| -> Option<f32> {
+                let covered: Vec<f32> = means.iter().filter(|(path, _)| covers(test, path)).map(|(_, (mean, _))| *mean).collect();
+                if covered.is_empty() { None } else { Some(covered.iter().sum()) }
+            };
+            let mut durations: HashMap<String, Option<f32>> = tests.iter().map(|test| (test.clone(), duration(test.as_str()))).collect();
+            if order == TestOrder::SlowestFirst {
+                durations.values_mut().for_each(|duration| *duration = duration.map(|duration| -duration));
+            }
+            tests.sort_by(|first, second| match (durations[first], durations[second]) {
+                (Some(first), Some(second)) => first.total_cmp(&second),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+        }
+        TestOrder::RiskFirst => {
+            let scores = failure_scores(history, window);
+            let risks: HashMap<String, usize> = tests.iter()
+                .map(|test| (test.clone(), scores.iter().filter(|(path, _)| covers(test, path)).map(|(_, score)| *score).sum()))
+                .collect();
+            tests.sort_by(|first, second| risks[second].cmp(&risks[first]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(durations: &[(&str, f32)]) -> HistoryRun {
+        HistoryRun {
+            report_created_on: String::new(),
+            changelist: None,
+            commit: None,
+            branch: None,
+            dirty: None,
+            engine_version: None,
+            session_name: None,
+            meta: BTreeMap::new(),
+            warmup_seconds: None,
+            tests: durations.iter().map(|(path, duration)| HistoryTest { path: path.to_string(), state: TestResult::Success, duration: *duration, signature: None }).collect(),
+            metrics: Vec::new(),
+        }
+    }
+
+    fn test_pass(durations: &[(&str, f32)]) -> TestPass {
+        let tests: Vec<String> = durations.iter()
+            .map(|(path, duration)| format!(r#"{{"testDisplayName": "", "fullTestPath": "{}", "state": "Success", "duration": {},
+                "entries": [], "warnings": 0, "errors": 0, "artifacts": []}}"#, path, duration))
+            .collect();
+        crate::test_pass_from_tests(tests.join(",").as_str())
+    }
+
+    fn config() -> DurationRegressionConfiguration {
+        DurationRegressionConfiguration { threshold_percent: 50.0, min_duration: 1.0, min_samples: 3, window: 10, fail_run: false }
+    }
+
+    #[test]
+    fn slower_than_the_mean_by_the_threshold() {
+        let history: Vec<HistoryRun> = (0..3).map(|_| run(&[("Slow", 2.0), ("Steady", 2.0), ("Fast", 0.2)])).collect();
+        let regressions = duration_regressions(&history, &test_pass(&[("Slow", 3.5), ("Steady", 2.5), ("Fast", 0.9)]), &config());
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].path, "Slow");
+        assert_eq!((regressions[0].mean, regressions[0].duration), (2.0, 3.5));
+    }
+
+    #[test]
+    fn tests_added_and_removed_since_the_same_session() {
+        let mut nightly = run(&[("Door.Opens", 1.0), ("Door.Closes", 1.0)]);
+        nightly.session_name = Some("nightly".to_owned());
+        let history = vec![nightly, run(&[("Door.Opens", 1.0)])];
+        let current = test_pass(&[("Door.Opens", 1.0), ("Window.Opens", 1.0)]);
+
+        let changes = test_set_changes(&history, &current, Some("nightly")).unwrap();
+        assert_eq!(changes.added, ["Window.Opens"]);
+        assert_eq!(changes.removed, ["Door.Closes"]);
+        let changes = test_set_changes(&history, &current, None).unwrap();
+        assert_eq!(changes.added, ["Window.Opens"]);
+        assert!(changes.removed.is_empty());
+        assert!(test_set_changes(&history, &current, Some("smoke")).is_none());
+    }
+
+    #[test]
+    fn failures_traced_back_to_the_run_they_began_at() {
+        let states = |changelist: &str, test_states: &[(&str, TestResult)]| {
+            let mut run = run(&test_states.iter().map(|(path, _)| (*path, 1.0)).collect::<Vec<_>>());
+            run.changelist = Some(changelist.to_owned());
+            run.tests.iter_mut().zip(test_states).for_each(|(test, (_, state))| test.state = *state);
+            run
+        };
+        let mut other_session = states("104", &[("Door.Opens", TestResult::Success)]);
+        other_session.session_name = Some("smoke".to_owned());
+        let history = vec![
+            states("100", &[("Door.Opens", TestResult::Success), ("Gate.Opens", TestResult::Fail), ("Wall.Stands", TestResult::Success)]),
+            states("101", &[("Door.Opens", TestResult::Fail), ("Gate.Opens", TestResult::Fail), ("Wall.Stands", TestResult::Success)]),
+            states("102", &[("Door.Opens", TestResult::NotRun), ("Gate.Opens", TestResult::Fail), ("Wall.Stands", TestResult::Success)]),
+            other_session,
+            states("105", &[("Door.Opens", TestResult::Fail), ("Gate.Opens", TestResult::Fail), ("Wall.Stands", TestResult::Fail)]),
+        ];
+
+        let failing = failing_since(&history, None);
+        let summary: Vec<(&str, Option<&str>, Option<&str>)> = failing.iter()
+            .map(|failing| (failing.path, failing.first_failing.changelist.as_deref(), failing.last_passing.and_then(|run| run.changelist.as_deref())))
+            .collect();
+        assert_eq!(summary, [("Door.Opens", Some("101"), Some("100")), ("Gate.Opens", Some("100"), None), ("Wall.Stands", Some("105"), Some("102"))]);
+        assert!(failing_since(&history, Some("smoke")).is_empty());
+        assert!(failing_since(&[], None).is_empty());
+    }
+
+    #[test]
+    fn tests_ordered_by_history() {
+        let mut failed = run(&[("Door.Opens", 1.0), ("Gate.Opens", 2.0), ("Gate.Closes", 3.0)]);
+        failed.tests[1].state = TestResult::Fail;
+        let history = vec![run(&[("Door.Opens", 1.0), ("Window.Opens", 4.0), ("Gate.Opens", 2.0), ("Gate.Closes", 3.0)]), failed];
+        let tests = || vec!["New.Test".to_owned(), "Window.Opens".to_owned(), "Gate.".to_owned(), "Door.Opens".to_owned()];
+
+        let mut fastest = tests();
+        order_tests(&mut fastest, &history, TestOrder::FastestFirst);
+        assert_eq!(fastest, ["Door.Opens", "Window.Opens", "Gate.", "New.Test"]);
+        let mut slowest = tests();
+        order_tests(&mut slowest, &history, TestOrder::SlowestFirst);
+        assert_eq!(slowest, ["Gate.", "Window.Opens", "Door.Opens", "New.Test"]);
+        let mut risky = tests();
+        order_tests(&mut risky, &history, TestOrder::RiskFirst);
+        assert_eq!(risky, ["Gate.", "New.Test", "Window.Opens", "Door.Opens"]);
+    }
+
+    #[test]
+    fn failures_the_previous_run_did_not_have() {
+        let mut previous = run(&[("Door.Opens", 1.0), ("Door.Closes", 1.0)]);
+        previous.tests[1].state = TestResult::Fail;
+        let mut current = test_pass(&[("Door.Opens", 1.0), ("Door.Closes", 1.0), ("Window.Opens", 1.0)]);
+        current.tests.iter_mut().filter(|test| test.full_test_path != "Window.Opens").for_each(|test| test.state = TestResult::Fail);
+        assert_eq!(new_failures(&[previous], &current, None), ["Door.Opens"]);
+        assert_eq!(new_failures(&[], &current, None), ["Door.Opens", "Door.Closes"]);
+    }
+
+    #[test]
+    fn failures_with_another_signature_are_new() {
+        let mut previous = run(&[("Door.Opens", 1.0), ("Door.Closes", 1.0)]);
+        for test in previous.tests.iter_mut() {
+            test.state = TestResult::Fail;
+            test.signature = Some(format!("{}-stuck", test.path));
+        }
+        let mut current = test_pass(&[("Door.Opens", 1.0), ("Door.Closes", 1.0)]);
+        for test in current.tests.iter_mut() {
+            test.state = TestResult::Fail;
+            test.signature = Some(format!("{}-stuck", test.full_test_path));
+        }
+        current.tests[1].signature = Some("Door.Closes-missing".to_owned());
+        let history = [previous];
+        assert_eq!(new_failures(&history, &current, None), ["Door.Closes"]);
+        assert_eq!(test_set_changes(&history, &current, None).unwrap().failing_differently, ["Door.Closes"]);
+    }
+
+    #[test]
+    fn too_few_samples_in_the_window() {
+        let mut history: Vec<HistoryRun> = (0..3).map(|_| run(&[("Slow", 2.0)])).collect();
+        history.push(run(&[]));
+        let window = DurationRegressionConfiguration { window: 3, ..config() };
+        assert!(duration_regressions(&history, &test_pass(&[("Slow", 4.0)]), &window).is_empty());
+        assert_eq!(duration_regressions(&history, &test_pass(&[("Slow", 4.0)]), &config()).len(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// requests go through curl, it's on every CI image and ships with windows 10 and later; the url, headers
+// and body are handed over as a curl config on stdin so tokens don't show up in the process list
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn curl_config(options: &[(&str, String)]) -> String {
+    options.iter().map(|(name, value)| format!("{} = {}\n", name, quote(value.as_str()))).collect()
+}
+
+// the response body, or curl's error (which includes the http status for 4xx and 5xx responses)
+fn run_curl(options: &[(&str, String)]) -> Result<String, String> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("failed to start curl: {}", error))?;
+    child.stdin.take().expect("curl stdin").write_all(curl_config(options).as_bytes()).map_err(|error| format!("failed to pass the request to curl: {}", error))?;
+    let output = child.wait_with_output().map_err(|error| format!("failed to wait for curl: {}", error))?;
+    if !output.status.success() {
+        let url = options.iter().find(|(name, _)| *name == "url").map_or("", |(_, url)| url.as_str());
+        return Err(format!("{}: {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn request_options(method: &str, url: &str, headers: &[(&str, &str)]) -> Vec<(&'static str, String)> {
+    let mut options = vec![("request", method.to_owned()), ("url", url.to_owned())];
+    options.extend(headers.iter().map(|(name, value)| ("header", format!("{}: {}", name, value))));
+    options
+}
+
+pub fn send(method: &str, url: &str, headers: &[(&str, &str)], body: Option<&str>) -> Result<String, String> {
+    let mut options = request_options(method, url, headers);
+    if let Some(body) = body {
+        options.push(("data-binary", body.to_owned()));
+    }
+    run_curl(&options)
+}
+
+// an url encoded form post with basic authentication, which is what jenkins' endpoints take
+pub fn post_form(url: &str, user: Option<&str>, fields: &[(&str, &str)]) -> Result<String, String> {
+    let mut options = request_options("POST", url, &[]);
+    if let Some(user) = user {
+        options.push(("user", user.to_owned()));
+    }
+    options.extend(fields.iter().map(|(name, value)| ("data-urlencode", format!("{}={}", name, value))));
+    run_curl(&options)
+}
+
+// binaries are written straight to destination, the response body of run_curl is text
+pub fn download(url: &str, headers: &[(&str, &str)], destination: &std::path::Path) -> Result<(), String> {
+    let mut options = request_options("GET", url, headers);
+    options.push(("output", destination.display().to_string()));
+    run_curl(&options).map(|_| ())
+}
+
+pub fn send_json(method: &str, url: &str, headers: &[(&str, &str)], body: &serde_json::Value) -> Result<String, String> {
+    let mut headers = headers.to_vec();
+    headers.push(("Content-Type", "application/json"));
+    send(method, url, &headers, Some(body.to_string().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_passed_as_a_curl_config() {
+        let mut options = request_options("PUT", "https://gitlab.example.com/api/v4/notes/1", &[("PRIVATE-TOKEN", "secret")]);
+        options.push(("data-binary", "{\"body\": \"a\\nb\"}\nnext".to_owned()));
+        assert_eq!(curl_config(&options), "request = \"PUT\"\nurl = \"https://gitlab.example.com/api/v4/notes/1\"\nheader = \"PRIVATE-TOKEN: secret\"\n\
+            data-binary = \"{\\\"body\\\": \\\"a\\\\nb\\\"}\\nnext\"\n");
+    }
+}
@@ -0,0 +1,58 @@
+use crate::theme::Theme;
+use crate::{matching_ignore_regex, TestPass};
+use std::io::{self, Write};
+
+// how many entries each ignore pattern suppressed, in config order (a message is attributed to the first pattern it matches)
+pub fn ignore_pattern_counts<'a>(test_pass: &TestPass, ignore_regexes: &'a [String]) -> Vec<(&'a str, usize)> {
+    let mut counts: Vec<(&str, usize)> = ignore_regexes.iter().map(|ignore_regex| (ignore_regex.as_str(), 0)).collect();
+    for entry in test_pass.tests.iter().flat_map(|test| test.entries.iter()) {
+        if let Some(ignore_regex) = matching_ignore_regex(entry.event.message.as_str(), ignore_regexes) {
+            if let Some(count) = counts.iter_mut().find(|(pattern, _)| *pattern == ignore_regex) {
+                count.1 += 1;
+            }
+        }
+    }
+    counts
+}
+
+pub fn write_ignore_pattern_counts(out: &mut dyn Write, counts: &[(&str, usize)], theme: &Theme) -> io::Result<()> {
+    if counts.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "ignored entries by pattern")?;
+    for (pattern, count) in counts.iter() {
+        writeln!(out, "{}{}", theme.spacer(), theme.ignored.paint(format!("{:>6}  {}", count, pattern).as_str()))?;
+    }
+    Ok(())
+}
+
+// patterns that hid nothing this run, a pattern whose matches were all claimed by an earlier pattern counts as unused too
+pub fn write_unused_ignore_patterns(out: &mut dyn Write, counts: &[(&str, usize)], theme: &Theme) -> io::Result<()> {
+    let unused: Vec<&str> = counts.iter().filter(|(_, count)| *count == 0).map(|(pattern, _)| *pattern).collect();
+    if unused.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "{}", theme.warning.paint(format!("{} ignore patterns matched nothing in this run", unused.len()).as_str()))?;
+    for pattern in unused {
+        writeln!(out, "{}{}", theme.spacer(), pattern)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_count_toward_the_first_matching_pattern() {
+        let entries: Vec<String> = ["LogViewport: resized", "LogViewport: LogUIActionRouter: bound", "LogUIActionRouter: bound", "Door stuck"].iter()
+            .map(|message| format!(r#"{{"event": {{"type": "Warning", "message": "{}", "context": "", "artifact": ""}},
+                "filename": "", "lineNumber": 0, "timestamp": ""}}"#, message))
+            .collect();
+        let test_pass = crate::test_pass_from_tests(format!(r#"{{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Success", "entries": [{}],
+            "warnings": 4, "errors": 0, "artifacts": []}}"#, entries.join(",")).as_str());
+
+        let ignore_regexes = vec!["LogViewport:".to_owned(), "LogUIActionRouter:".to_owned(), "LogNet:".to_owned()];
+        assert_eq!(ignore_pattern_counts(&test_pass, &ignore_regexes), [("LogViewport:", 2), ("LogUIActionRouter:", 1), ("LogNet:", 0)]);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::runner::{self, Instance, TestRunner, TestSelection};
+use crate::telemetry::EngineLaunch;
+use crate::{datetime, pipeline, report, testinfo, TestConfiguration, TestPass};
+use colored::*;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how often running engines are polled for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// one explicit test per selection, tests are only known individually when they were named (a prefix like
+// Project. stays one selection, unreal expands it inside the engine)
+pub fn individual_tests(run_tests: &str) -> Vec<String> {
+    run_tests.split(|c: char| c.is_whitespace() || c == '+').filter(|test| !test.is_empty()).map(|test| test.to_owned()).collect()
+}
+
+// test paths piped in one or more per line, blank lines and # comments are skipped so a saved list can be fed back
+pub fn read_tests(reader: impl std::io::BufRead) -> Vec<String> {
+    reader.lines()
+        .map_while(Result::ok)
+        .flat_map(|line| individual_tests(line.split('#').next().unwrap_or_default()))
+        .collect()
+}
+
+// deals the tests round robin into at most `jobs` groups, one engine process each
+pub fn split_tests(tests: Vec<String>, jobs: usize) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = (0..jobs.clamp(1, tests.len().max(1))).map(|_| Vec::new()).collect();
+    let group_count = groups.len();
+    for (index, test) in tests.into_iter().enumerate() {
+        groups[index % group_count].push(test);
+    }
+    groups.retain(|group| !group.is_empty());
+    groups
+}
+
+// the selections --isolate (one engine per named test) or --jobs (the named tests spread over that many engines)
+// launch, None when the run is a single engine; instances run on this machine, so not with [remote]
+pub fn instance_selections(selection: &TestSelection, isolate: bool, jobs: Option<usize>, remote: bool) -> Result<Option<Vec<TestSelection>>, String> {
+    if !isolate && !jobs.is_some_and(|jobs| jobs > 1) {
+        return Ok(None);
+    }
+    match selection {
+        TestSelection::Tests(_) if remote => Err("--isolate and --jobs only run engines on this machine, they can't be combined with [remote]".to_owned()),
+        TestSelection::Tests(run_tests) => {
+            let tests = individual_tests(run_tests);
+            let groups = if isolate { tests.into_iter().map(|test| vec![test]).collect() } else { split_tests(tests, jobs.unwrap_or(1)) };
+            Ok(Some(groups.into_iter().map(|group| TestSelection::Tests(group.join(" "))).collect()))
+        }
+        TestSelection::Filter(_) => Err("--isolate and --jobs need named tests, unreal only expands --filter inside the engine".to_owned()),
+    }
+}
+
+// windows caps a command line at 32767 characters, a longer -ExecCmds fails the launch or reaches the engine
+// cut short; what's left under this is for the engine path and the other arguments
+pub const MAX_EXEC_CMDS_LENGTH: usize = 30000;
+
+// the named tests of a selection whose -ExecCmds would be too long, spread in order over as few selections as
+// fit; None when it fits already. A single test name too long by itself is left to fail on its own
+pub fn split_oversized(config: &TestConfiguration, selection: &TestSelection) -> Option<Vec<TestSelection>> {
+    let run_tests = match selection {
+        TestSelection::Tests(run_tests) if runner::exec_cmds(config, selection).len() > MAX_EXEC_CMDS_LENGTH => run_tests,
+        _ => return None,
+    };
+    let budget = MAX_EXEC_CMDS_LENGTH.saturating_sub(runner::exec_cmds(config, &TestSelection::Tests(String::new())).len());
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut length = 0;
+    for test in individual_tests(run_tests) {
+        match groups.last_mut() {
+            Some(group) if length + 1 + test.len() <= budget => {
+                length += 1 + test.len();
+                group.push(test);
+            }
+            _ => {
+                length = test.len();
+                groups.push(vec![test]);
+            }
+        }
+    }
+    Some(groups.into_iter().map(|group| TestSelection::Tests(group.join(" "))).collect())
+}
+
+// an instance that can't be started or waited on ends the run, the ones already going aren't left unattended
+fn stop_instances(running: Vec<(usize, Instance, Child, chrono::DateTime<chrono::Utc>)>) {
+    for (index, _, mut child, _) in running {
+        eprintln!("{}{}", "stopping instance ".yellow(), index);
+        child.kill().ok();
+        child.wait().ok();
+    }
+}
+
+// runs every selection in its own engine process, at most max_parallel at a time, waiting at least
+// stagger between launches; results are in selection order
+pub fn run_instances(runner: &dyn TestRunner, config: &TestConfiguration, selections: &[TestSelection], max_parallel: usize, stagger: Duration) -> Result<Vec<(Instance, ExitStatus, EngineLaunch)>, String> {
+    let max_parallel = max_parallel.max(1);
+    let mut results: Vec<Option<(Instance, ExitStatus, EngineLaunch)>> = selections.iter().map(|_| None).collect();
+    let mut running: Vec<(usize, Instance, Child, chrono::DateTime<chrono::Utc>)> = Vec::new();
+    let mut pending = selections.iter().enumerate();
+    let mut next = pending.next();
+    let mut last_launch: Option<Instant> = None;
+
+    while next.is_some() || !running.is_empty() {
+        while running.len() < max_parallel {
+            let (index, selection) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            if let Some(last_launch) = last_launch {
+                let since_launch = last_launch.elapsed();
+                if since_launch < stagger {
+                    thread::sleep(stagger - since_launch);
+                }
+            }
+            let instance = Instance::numbered(config, index);
+            // a report left over from an earlier run must not be mistaken for this one
+            fs::remove_dir_all(instance.report_directory.as_str()).ok();
+            let mut command = runner.command(config, selection, &instance);
+            let child = match command.stdout(std::io::stderr()).spawn() {
+                Ok(child) => child,
+                Err(error) => {
+                    stop_instances(running);
+                    return Err(format!("failed to start instance {} ({}): {}", index, command.get_program().to_string_lossy(), error));
+                }
+            };
+            eprintln!("instance {} started: {} ({})", index, selection, runner.name());
+            running.push((index, instance, child, chrono::Utc::now()));
+            last_launch = Some(Instant::now());
+            next = pending.next();
+        }
+
+        let mut still_running = Vec::with_capacity(running.len());
+        let mut polled = running.into_iter();
+        while let Some((index, instance, mut child, started_at)) = polled.next() {
+            let exited = match child.try_wait() {
+                Ok(exited) => exited,
+                Err(error) => {
+                    stop_instances(still_running.into_iter().chain(std::iter::once((index, instance, child, started_at))).chain(polled).collect());
+                    return Err(format!("failed to wait for instance {}: {}", index, error));
+                }
+            };
+            match exited {
+                Some(exit_status) => {
+                    eprintln!("instance {} finished", index);
+                    let launch = EngineLaunch { name: format!("instance-{}", index), started_at, finished_at: chrono::Utc::now(), exit_code: exit_status.code() };
+                    results[index] = Some((instance, exit_status, launch));
+                }
+                None => still_running.push((index, instance, child, started_at)),
+            }
+        }
+        running = still_running;
+        if !running.is_empty() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+// artifact files are relative to the report they came from, the merged report lives elsewhere
+fn rebase_artifacts(test_pass: &mut TestPass, report_directory: &Path) {
+    for artifact in test_pass.tests.iter_mut().flat_map(|test| test.artifacts.iter_mut()) {
+        for file in artifact.files.values_mut() {
+            *file = report_directory.join(file.as_str()).display().to_string();
+        }
+    }
+}
+
+// combines the reports every instance wrote since the run started into path_to_reports/merged/index.json
+pub fn merge_reports(instances: &[(Instance, ExitStatus)], config: &TestConfiguration, started_at: chrono::DateTime<chrono::Utc>) -> Result<PathBuf, String> {
+    let mut merged: Option<TestPass> = None;
+    for (index, (instance, _)) in instances.iter().enumerate() {
+        let report_file = match report::find_report_file(instance.report_directory.as_str()) {
+            Some(report_file) if report::written_since(&report_file, started_at) => report_file,
+            _ => {
+                eprintln!("{}{}", "no report from instance ".yellow(), index);
+                continue;
+            }
+        };
+        let mut test_pass = report::read_test_pass(&report_file)?;
+        for schema_warning in test_pass.schema_warnings.iter() {
+            eprintln!("{}{}", format!("schema warning in instance {}'s report: ", index).yellow(), schema_warning);
+        }
+        // the per-test jsons stay in the instance's directory, the merged index carries what they said
+        testinfo::read_test_info(&mut test_pass, report_file.parent().unwrap_or_else(|| Path::new(".")), pipeline::worker_count(config.postprocess_workers));
+        rebase_artifacts(&mut test_pass, report_file.parent().unwrap_or_else(|| Path::new(".")));
+        merged = Some(match merged {
+            None => test_pass,
+            Some(mut merged) => {
+                merged.succeeded += test_pass.succeeded;
+                merged.succeeded_with_warnings += test_pass.succeeded_with_warnings;
+                merged.failed += test_pass.failed;
+                merged.not_run += test_pass.not_run;
+                merged.in_process = match (merged.in_process, test_pass.in_process) {
+                    (None, None) => None,
+                    (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+                };
+                if datetime::parse_timestamp(test_pass.report_created_on.as_str()) > datetime::parse_timestamp(merged.report_created_on.as_str()) {
+                    merged.report_created_on = test_pass.report_created_on;
+                }
+                merged.tests.extend(test_pass.tests);
+                merged
+            }
+        });
+    }
+
+    let mut merged = merged.ok_or_else(|| "no instance wrote a report".to_owned())?;
+    // instances overlap, the wall clock time is what the run actually took
+    merged.total_duration = (chrono::Utc::now() - started_at).num_milliseconds() as f32 / 1000.0;
+
+    let merged_directory = Path::new(config.path_to_reports.as_str()).join("merged");
+    fs::create_dir_all(&merged_directory).map_err(|error| format!("failed to create {}: {}", merged_directory.display(), error))?;
+    let merged_file = merged_directory.join("index.json");
+    let writer = BufWriter::new(File::create(&merged_file).map_err(|error| format!("failed to create {}: {}", merged_file.display(), error))?);
+    serde_json::to_writer_pretty(writer, &merged).map_err(|error| format!("failed to write {}: {}", merged_file.display(), error))?;
+    Ok(merged_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_tests_are_split() {
+        assert_eq!(individual_tests(" Project.Door+Project.Wall  Project.Roof "), ["Project.Door", "Project.Wall", "Project.Roof"]);
+        assert_eq!(individual_tests("Project."), ["Project."]);
+    }
+
+    #[test]
+    fn piped_tests_are_read_per_line() {
+        let piped = "Project.Combat.Melee\n\n# ranged is flaky\nProject.Combat.Magic+Project.Combat.Block  # both\n";
+        assert_eq!(read_tests(piped.as_bytes()), ["Project.Combat.Melee", "Project.Combat.Magic", "Project.Combat.Block"]);
+    }
+
+    #[test]
+    fn tests_are_dealt_round_robin() {
+        let tests: Vec<String> = (0..5).map(|index| format!("Project.Test{}", index)).collect();
+        assert_eq!(split_tests(tests.clone(), 2), [
+            vec!["Project.Test0", "Project.Test2", "Project.Test4"],
+            vec!["Project.Test1", "Project.Test3"],
+        ]);
+        assert_eq!(split_tests(tests[..2].to_vec(), 8).len(), 2);
+        assert!(split_tests(Vec::new(), 4).is_empty());
+    }
+
+    #[test]
+    fn instances_need_named_tests_run_locally() {
+        let selection = TestSelection::Tests("Project.Door Project.Gate Project.Wall".to_owned());
+        assert!(matches!(instance_selections(&selection, false, Some(1), false), Ok(None)));
+        assert_eq!(instance_selections(&selection, true, None, false).unwrap().unwrap().len(), 3);
+        assert_eq!(instance_selections(&selection, false, Some(2), false).unwrap().unwrap().len(), 2);
+        assert!(instance_selections(&selection, true, None, true).unwrap_err().contains("[remote]"));
+        assert!(instance_selections(&selection, false, Some(2), true).unwrap_err().contains("[remote]"));
+        assert!(instance_selections(&selection, false, None, true).unwrap().is_none());
+        assert!(instance_selections(&TestSelection::Filter("Smoke"), false, Some(2), false).is_err());
+    }
+
+    #[test]
+    fn oversized_test_lists_are_split_in_order() {
+        let config: TestConfiguration = toml::from_str(r#"
+            path_to_unrealengine = ""
+            path_to_project = ""
+            path_to_reports = ""
+            run_tests = ""
+            test_exit = ""
+            pre_exec_cmds = ["r.ScreenPercentage 100"]
+            ignore_regexes = []"#).unwrap();
+        let tests: Vec<String> = (0..2000).map(|index| format!("Project.Combat.Scenarios.Case{:04}", index)).collect();
+        let split = split_oversized(&config, &TestSelection::Tests(tests.join(" "))).unwrap();
+        assert_eq!(split.len(), 3);
+        assert!(split.iter().all(|launched| runner::exec_cmds(&config, launched).len() <= MAX_EXEC_CMDS_LENGTH));
+        let rejoined: Vec<String> = split.iter().flat_map(|launched| match launched {
+            TestSelection::Tests(run_tests) => individual_tests(run_tests),
+            TestSelection::Filter(_) => Vec::new(),
+        }).collect();
+        assert_eq!(rejoined, tests);
+        assert!(split_oversized(&config, &TestSelection::Tests(tests[..10].join(" "))).is_none());
+        assert!(split_oversized(&config, &TestSelection::Filter("Smoke")).is_none());
+    }
+
+    fn write_instance_report(config: &TestConfiguration, index: usize, test_path: &str, state: &str) -> Instance {
+        let instance = Instance::numbered(config, index);
+        fs::create_dir_all(instance.report_directory.as_str()).unwrap();
+        let (succeeded, failed) = if state == "Success" { (1, 0) } else { (0, 1) };
+        let mut test_pass = crate::test_pass_from_tests(format!(r#"{{"testDisplayName": "", "fullTestPath": "{}", "state": "{}", "entries": [],
+            "warnings": 0, "errors": 0, "artifacts": [{{"id": "1", "name": "shot", "type": "Image", "files": {{"unapproved": "shot.png"}}}}]}}"#,
+            test_path, state).as_str());
+        test_pass.report_created_on = format!("2023.10.01-12.00.0{}", index);
+        test_pass.succeeded = succeeded;
+        test_pass.failed = failed;
+        test_pass.total_duration = 10.0;
+        fs::write(Path::new(instance.report_directory.as_str()).join("index.json"), serde_json::to_string(&test_pass).unwrap()).unwrap();
+        instance
+    }
+
+    #[test]
+    fn instance_reports_are_merged() {
+        let path_to_reports = std::env::temp_dir().join(format!("runtests-instances-{}", std::process::id()));
+        let config: TestConfiguration = toml::from_str(format!(r#"
+            path_to_unrealengine = ""
+            path_to_project = ""
+            path_to_reports = "{}"
+            run_tests = ""
+            test_exit = ""
+            ignore_regexes = []"#, path_to_reports.display().to_string().replace('\\', "/")).as_str()).unwrap();
+        let started_at = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let instances = vec![
+            (write_instance_report(&config, 0, "Project.Door", "Success"), ExitStatus::default()),
+            (write_instance_report(&config, 1, "Project.Wall", "Fail"), ExitStatus::default()),
+            (write_instance_report(&config, 2, "Project.Stale", "Fail"), ExitStatus::default()),
+        ];
+        let stale_report = Path::new(instances[2].0.report_directory.as_str()).join("index.json");
+        File::options().write(true).open(stale_report).unwrap().set_modified(std::time::SystemTime::now() - Duration::from_secs(3600)).unwrap();
+
+        let merged_file = merge_reports(&instances, &config, started_at).unwrap();
+        let merged = report::read_test_pass(&merged_file).unwrap();
+        fs::remove_dir_all(&path_to_reports).unwrap();
+        let paths: Vec<&str> = merged.tests.iter().map(|test| test.full_test_path.as_str()).collect();
+        assert_eq!(paths, ["Project.Door", "Project.Wall"]);
+        assert_eq!((merged.succeeded, merged.failed), (1, 1));
+        assert_eq!(merged.report_created_on, "2023.10.01-12.00.01");
+        let artifact_file = &merged.tests[1].artifacts[0].files["unapproved"];
+        assert_eq!(Path::new(artifact_file), Path::new(instances[1].0.report_directory.as_str()).join("shot.png"));
+    }
+}
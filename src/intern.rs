@@ -0,0 +1,111 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+thread_local! {
+    static INTERNED: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+// a shared, immutable string; the same message or filename repeated across 100k entries is stored once
+#[derive(Clone, Eq)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    pub fn new(text: &str) -> Interned {
+        INTERNED.with(|interned| {
+            let mut interned = interned.borrow_mut();
+            match interned.get(text) {
+                Some(existing) => Interned(existing.clone()),
+                None => {
+                    let text: Arc<str> = Arc::from(text);
+                    interned.insert(text.clone());
+                    Interned(text)
+                }
+            }
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// drops the lookup table once a report is loaded, the strings live on in the entries that share them
+pub fn clear() {
+    INTERNED.with(|interned| interned.borrow_mut().clear());
+}
+
+impl PartialEq for Interned {
+    // interned copies of the same text share one allocation, so most equal pairs are found without comparing bytes
+    fn eq(&self, other: &Interned) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Interned {
+    fn from(text: String) -> Interned {
+        Interned::new(text.as_str())
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Interned, D::Error> {
+        let text = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Ok(Interned::new(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_text_shares_one_allocation() {
+        let messages: Vec<Interned> = serde_json::from_str(r#"["Door stuck", "Door stuck", "Door open"]"#).unwrap();
+        assert!(Arc::ptr_eq(&messages[0].0, &messages[1].0));
+        assert!(Arc::ptr_eq(&messages[0].0, &Interned::new("Door stuck").0));
+        assert_ne!(messages[0], messages[2]);
+
+        clear();
+        let after_clear = Interned::new("Door stuck");
+        assert!(!Arc::ptr_eq(&messages[0].0, &after_clear.0));
+        assert_eq!(messages[0], after_clear);
+        assert_eq!(serde_json::to_string(&after_clear).unwrap(), r#""Door stuck""#);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::lang::{self, Lang, Message};
+use crate::{http, RunMetadata, TestPass, TestResult};
+use serde::Deserialize;
+
+// with [jenkins] the build page's description shows the summary
+#[derive(Debug, Deserialize)]
+pub struct JenkinsConfiguration {
+    pub build_url: Option<String>, // BUILD_URL of the running job by default
+    pub user: Option<String>,      // JENKINS_USER by default
+    #[serde(default = "default_token_env")]
+    pub token_env: String,         // environment variable holding the user's api token
+    pub checks: Option<ChecksConfiguration>,
+}
+
+// jenkins' checks api has no http endpoint, its github checks plugin publishes a check run for the built commit
+// from inside a pipeline; with [jenkins.checks] the same check run is posted to github directly, linking back
+// to the build
+#[derive(Debug, Deserialize)]
+pub struct ChecksConfiguration {
+    pub repository: String, // owner/name on github
+    #[serde(default = "default_checks_api_url")]
+    pub api_url: String,
+    #[serde(default = "default_checks_token_env")]
+    pub token_env: String, // environment variable holding a github app installation token, personal tokens can't create check runs
+    #[serde(default = "default_check_name")]
+    pub name: String,
+}
+
+pub(crate) fn default_token_env() -> String {
+    "JENKINS_API_TOKEN".to_owned()
+}
+
+pub(crate) fn default_checks_api_url() -> String {
+    "https://api.github.com".to_owned()
+}
+
+pub(crate) fn default_checks_token_env() -> String {
+    "GITHUB_TOKEN".to_owned()
+}
+
+pub(crate) fn default_check_name() -> String {
+    "runtests".to_owned()
+}
+
+// build descriptions are shown in the build history too, only the first few failures fit there
+const MAX_DESCRIBED_FAILURES: usize = 5;
+
+pub fn build_description(test_pass: &TestPass, run_metadata: &RunMetadata, lang: Lang) -> String {
+    let mut description = lang::format(lang, Message::Summary, &[&test_pass.succeeded, &test_pass.failed, &(test_pass.not_run + test_pass.succeeded_with_warnings)]);
+    if let Some(session_name) = &run_metadata.session_name {
+        description = format!("{} ({})", description, lang::format(lang, Message::Session, &[session_name]));
+    }
+    if run_metadata.crashed {
+        description.push('\n');
+        description.push_str(lang::text(lang, Message::CrashedDuringExecution));
+    }
+    let failed: Vec<&str> = test_pass.tests.iter().filter(|test| test.state == TestResult::Fail).map(|test| test.full_test_path.as_str()).collect();
+    for test in failed.iter().take(MAX_DESCRIBED_FAILURES) {
+        description.push('\n');
+        description.push_str(test);
+    }
+    if failed.len() > MAX_DESCRIBED_FAILURES {
+        description.push('\n');
+        description.push_str(lang::format(lang, Message::AndMore, &[&(failed.len() - MAX_DESCRIBED_FAILURES)]).as_str());
+    }
+    description
+}
+
+fn build_url(config: &JenkinsConfiguration) -> Option<String> {
+    config.build_url.clone().or_else(|| std::env::var("BUILD_URL").ok())
+}
+
+pub fn set_build_description(config: &JenkinsConfiguration, description: &str) -> Result<(), String> {
+    let build_url = build_url(config).ok_or_else(|| "no build_url and BUILD_URL is unset".to_owned())?;
+    let user = config.user.clone().or_else(|| std::env::var("JENKINS_USER").ok()).ok_or_else(|| "no user and JENKINS_USER is unset".to_owned())?;
+    let token = std::env::var(config.token_env.as_str()).map_err(|_| format!("{} is unset", config.token_env))?;
+    let url = format!("{}/submitDescription", build_url.trim_end_matches('/'));
+    http::post_form(url.as_str(), Some(format!("{}:{}", user, token).as_str()), &[("description", description)])?;
+    Ok(())
+}
+
+// the build description's first line is the check's title and the rest its summary
+fn check_run(checks: &ChecksConfiguration, test_pass: &TestPass, run_metadata: &RunMetadata, head_sha: &str, description: &str, details_url: Option<&str>) -> serde_json::Value {
+    let (title, summary) = description.split_once('\n').unwrap_or((description, description));
+    let conclusion = if test_pass.failed > 0 || run_metadata.crashed { "failure" } else { "success" };
+    let mut check_run = serde_json::json!({
+        "name": checks.name,
+        "head_sha": head_sha,
+        "status": "completed",
+        "conclusion": conclusion,
+        "output": { "title": title, "summary": summary },
+    });
+    if let Some(details_url) = details_url {
+        check_run["details_url"] = serde_json::Value::from(details_url);
+    }
+    check_run
+}
+
+// the commit is the one jenkins checked out (GIT_COMMIT), or the project's when the job doesn't set it
+pub fn publish_check_run(config: &JenkinsConfiguration, checks: &ChecksConfiguration, test_pass: &TestPass, run_metadata: &RunMetadata, description: &str) -> Result<(), String> {
+    let head_sha = std::env::var("GIT_COMMIT").ok()
+        .or_else(|| run_metadata.git.as_ref().map(|git| git.commit.clone()))
+        .ok_or_else(|| "GIT_COMMIT is unset and the project isn't a git checkout".to_owned())?;
+    let token = std::env::var(checks.token_env.as_str()).map_err(|_| format!("{} is unset", checks.token_env))?;
+    let authorization = format!("Bearer {}", token);
+    let headers = [("Authorization", authorization.as_str()), ("Accept", "application/vnd.github+json")];
+    let url = format!("{}/repos/{}/check-runs", checks.api_url.trim_end_matches('/'), checks.repository);
+    let build_url = build_url(config);
+    http::send_json("POST", url.as_str(), &headers, &check_run(checks, test_pass, run_metadata, head_sha.as_str(), description, build_url.as_deref()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_lists_the_first_failures() {
+        let tests: Vec<String> = (0..7)
+            .map(|index| format!(r#"{{"testDisplayName": "", "fullTestPath": "Project.Test{}", "state": "Fail", "entries": [], "warnings": 0,
+                "errors": 0, "artifacts": []}}"#, index))
+            .collect();
+        let mut test_pass = crate::test_pass_from_tests(tests.join(",").as_str());
+        test_pass.succeeded = 3;
+        test_pass.failed = 7;
+        test_pass.not_run = 1;
+        let run_metadata = RunMetadata { session_name: Some("nightly".to_owned()), ..RunMetadata::default() };
+
+        let description = build_description(&test_pass, &run_metadata, Lang::En);
+        let lines: Vec<&str> = description.lines().collect();
+        assert_eq!(lines[0], "3 passed, 7 failed, 1 other (session nightly)");
+        assert_eq!(lines[1], "Project.Test0");
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[6], "and 2 more");
+    }
+
+    #[test]
+    fn check_run_concludes_from_the_failures() {
+        let mut test_pass = crate::test_pass_from_tests("");
+        test_pass.succeeded = 3;
+        test_pass.failed = 1;
+        let checks: ChecksConfiguration = toml::from_str(r#"repository = "castle/adventure""#).unwrap();
+        let run_metadata = RunMetadata::default();
+
+        let failed = check_run(&checks, &test_pass, &run_metadata, "9f3c2e1", "3 passed, 1 failed, 0 other\nProject.Door", Some("https://jenkins.example.com/job/1/"));
+        assert_eq!(failed["name"], "runtests");
+        assert_eq!(failed["head_sha"], "9f3c2e1");
+        assert_eq!(failed["conclusion"], "failure");
+        assert_eq!(failed["output"]["title"], "3 passed, 1 failed, 0 other");
+        assert_eq!(failed["output"]["summary"], "Project.Door");
+        assert_eq!(failed["details_url"], "https://jenkins.example.com/job/1/");
+
+        test_pass.failed = 0;
+        let passed = check_run(&checks, &test_pass, &run_metadata, "9f3c2e1", "4 passed, 0 failed, 0 other", None);
+        assert_eq!(passed["conclusion"], "success");
+        assert_eq!(passed["output"]["summary"], "4 passed, 0 failed, 0 other");
+        assert!(passed.get("details_url").is_none());
+    }
+}
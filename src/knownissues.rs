@@ -0,0 +1,104 @@
+use crate::theme::Theme;
+use crate::TestPass;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::io::{self, Write};
+
+// [[known_issues]] are ignore patterns with a ticket, so what's hidden can be traced back to why; one past its
+// expiry date stops applying and is listed until someone fixes the issue or pushes the date back
+#[derive(Debug, Deserialize)]
+pub struct KnownIssue {
+    pub pattern: String, // a regex like the ones in ignore_regexes
+    pub issue: String, // the tracker id, ie CASTLE-1234
+    pub expires: Option<String>, // YYYY-MM-DD, the last day it applies
+}
+
+pub fn expiry_date(known_issue: &KnownIssue) -> Result<Option<NaiveDate>, String> {
+    match &known_issue.expires {
+        Some(expires) => NaiveDate::parse_from_str(expires.as_str(), "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("known issue {} expires on {}, which isn't a YYYY-MM-DD date", known_issue.issue, expires)),
+        None => Ok(None),
+    }
+}
+
+pub fn is_expired(known_issue: &KnownIssue, today: NaiveDate) -> bool {
+    expiry_date(known_issue).ok().flatten().is_some_and(|expires| expires < today)
+}
+
+// the patterns that still apply, checked so a typo fails the config instead of a run
+pub fn active_patterns(known_issues: &[KnownIssue], today: NaiveDate) -> Result<Vec<String>, String> {
+    let mut patterns = Vec::new();
+    for known_issue in known_issues {
+        regex::Regex::new(known_issue.pattern.as_str()).map_err(|error| format!("known issue {} has an invalid pattern: {}", known_issue.issue, error))?;
+        expiry_date(known_issue)?;
+        if !is_expired(known_issue, today) {
+            patterns.push(known_issue.pattern.clone());
+        }
+    }
+    Ok(patterns)
+}
+
+// the entries a known issue's pattern matches whether or not an earlier ignore pattern matched them too, the
+// per-pattern ignore counts only credit the first match and known issues come after the other patterns
+pub fn hidden_count(known_issue: &KnownIssue, test_pass: &TestPass) -> usize {
+    let regex = match regex::Regex::new(known_issue.pattern.as_str()) {
+        Ok(regex) => regex,
+        Err(_) => return 0,
+    };
+    test_pass.tests.iter().flat_map(|test| test.entries.iter()).filter(|entry| regex.is_match(entry.event.message.as_str())).count()
+}
+
+// every known issue with its ticket and how many entries it hid in this report, expired ones as a warning
+pub fn write_known_issues(out: &mut dyn Write, known_issues: &[KnownIssue], test_pass: &TestPass, today: NaiveDate, theme: &Theme) -> io::Result<()> {
+    if known_issues.is_empty() {
+        return Ok(());
+    }
+    let (expired, active): (Vec<&KnownIssue>, Vec<&KnownIssue>) = known_issues.iter().partition(|known_issue| is_expired(known_issue, today));
+    if !active.is_empty() {
+        writeln!(out, "known issues suppressing messages")?;
+        for known_issue in active {
+            let count = hidden_count(known_issue, test_pass);
+            let expires = known_issue.expires.as_ref().map_or_else(String::new, |expires| format!(", until {}", expires));
+            writeln!(out, "{}{}", theme.spacer(), theme.ignored.paint(format!("{} ({} hidden{}) {}", known_issue.issue, count, expires, known_issue.pattern).as_str()))?;
+        }
+    }
+    if !expired.is_empty() {
+        writeln!(out, "{}", theme.warning.paint(format!("{} known issues expired and no longer suppress anything", expired.len()).as_str()))?;
+        for known_issue in expired {
+            writeln!(out, "{}{} (expired {}) {}", theme.spacer(), known_issue.issue, known_issue.expires.as_deref().unwrap_or_default(), known_issue.pattern)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_issue(pattern: &str, expires: Option<&str>) -> KnownIssue {
+        KnownIssue { pattern: pattern.to_owned(), issue: "CASTLE-1234".to_owned(), expires: expires.map(str::to_owned) }
+    }
+
+    #[test]
+    fn expired_known_issues_stop_applying() {
+        let today = NaiveDate::from_ymd_opt(2023, 10, 6).unwrap();
+        let known_issues = [known_issue("LogNet: timed out", None), known_issue("LogAudio:", Some("2023-10-06")), known_issue("LogAI:", Some("2023-10-05"))];
+        assert_eq!(active_patterns(&known_issues, today), Ok(vec!["LogNet: timed out".to_owned(), "LogAudio:".to_owned()]));
+        assert!(active_patterns(&[known_issue("LogNet: (", None)], today).is_err());
+        assert!(active_patterns(&[known_issue("LogNet:", Some("10/06/2023"))], today).is_err());
+    }
+
+    #[test]
+    fn known_issues_count_what_earlier_patterns_also_hide() {
+        let test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Success", "warnings": 2, "errors": 0, "artifacts": [], "entries": [
+                    {"event": {"type": "Warning", "message": "LogNet: timed out", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""},
+                    {"event": {"type": "Warning", "message": "LogNet: timed out again", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""},
+                    {"event": {"type": "Warning", "message": "LogAudio: underrun", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]}"#);
+        let ignore_regexes = ["LogNet:".to_owned(), "LogNet: timed out".to_owned()];
+        assert_eq!(crate::ignores::ignore_pattern_counts(&test_pass, &ignore_regexes)[1].1, 0);
+        assert_eq!(hidden_count(&known_issue("LogNet: timed out", None), &test_pass), 2);
+        assert_eq!(hidden_count(&known_issue("LogAI:", None), &test_pass), 0);
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt::Display;
+
+pub const LANG_NAMES: &[&str] = &["en", "ja"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Ja,
+}
+
+pub fn parse_lang(name: &str) -> Option<Lang> {
+    match name.trim().to_lowercase().as_str() {
+        "en" | "english" => Some(Lang::En),
+        "ja" | "jp" | "japanese" => Some(Lang::Ja),
+        _ => None,
+    }
+}
+
+// every sentence the report formatters print, the {} in a template are filled in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    TestReport,
+    Summary,              // passed, failed, other
+    Elapsed,              // seconds
+    Warmup,               // seconds, details
+    ShaderCompileSeconds, // seconds
+    DdcMisses,            // count
+    ReportCreated,        // time, age
+    ReportCreatedRaw,     // timestamp that couldn't be parsed
+    ReportOlderThanRun,
+    AtChangelist,
+    AtCommit,
+    OnEngine,
+    ShuffledWithSeed,     // seed, seed
+    Session,
+    Labels,
+    CrashedDuringExecution,
+    CrashedDuringExecutionIn, // test path
+    CrashedDuringThisTest,
+    IncompleteRun,        // in process count
+    ExitedDuringThisTest,
+    CrashReport,
+    On,                   // machine
+    ExpectedMessages,     // expected, unexpected
+    DefinedIn,            // location
+    OwnedBy,              // owner
+    FirstError,
+    State,
+    Test,
+    Missing,
+    CreatedOn,            // time
+    CachedPass,           // test path
+    InTheFuture,
+    JustNow,
+    MinutesAgo,           // minutes
+    HoursAgo,             // hours, minutes
+    DaysAgo,              // days
+    GroundTruth,
+    Incoming,
+    Delta,
+    SuccessLabel,
+    FailLabel,
+    WarningLabel,
+    InfoLabel,
+    ErrorLabel,
+    ExpectedLabel,
+    IgnoredLabel,
+    WarningEntry,         // message
+    ErrorEntry,           // message
+    AndMore,              // count
+    AndMoreFailedTests,   // count
+}
+
+fn template(message: Message, lang: Lang) -> &'static str {
+    use Message::*;
+    match lang {
+        Lang::En => match message {
+            TestReport => "Test report",
+            Summary => "{} passed, {} failed, {} other",
+            Elapsed => "{}s elapsed",
+            Warmup => "{}s warmup before the first test{}, not counted in test durations",
+            ShaderCompileSeconds => "{}s compiling shaders",
+            DdcMisses => "{} DDC misses",
+            ReportCreated => "report created {} ({})",
+            ReportCreatedRaw => "report created {}",
+            ReportOlderThanRun => "the report is older than this run, the engine may not have written a new one",
+            AtChangelist => "at changelist {}",
+            AtCommit => "at commit {}",
+            OnEngine => "on engine {}",
+            ShuffledWithSeed => "shuffled with seed {}, rerun with --shuffle={} for the same order",
+            Session => "session {}",
+            Labels => "labels {}",
+            CrashedDuringExecution => "crashed during execution",
+            CrashedDuringExecutionIn => "crashed during execution, last running test {}",
+            CrashedDuringThisTest => "the engine crashed during this test",
+            IncompleteRun => "the run is incomplete, the engine exited with {} tests still running",
+            ExitedDuringThisTest => "the engine exited during this test",
+            CrashReport => "crash report {}",
+            On => "on {}",
+            ExpectedMessages => "{} expected, {} unexpected messages",
+            DefinedIn => "defined in {}",
+            OwnedBy => "owned by {}",
+            FirstError => "First error",
+            State => "State",
+            Test => "Test",
+            Missing => "missing",
+            CreatedOn => "created {}",
+            CachedPass => "{} skipped (cached pass)",
+            InTheFuture => "in the future",
+            JustNow => "just now",
+            MinutesAgo => "{}m ago",
+            HoursAgo => "{}h {}m ago",
+            DaysAgo => "{}d ago",
+            GroundTruth => "Ground truth",
+            Incoming => "Incoming",
+            Delta => "Delta",
+            SuccessLabel => "Success",
+            FailLabel => "Fail",
+            WarningLabel => "Warning",
+            InfoLabel => "Info",
+            ErrorLabel => "Error",
+            ExpectedLabel => "Expected",
+            IgnoredLabel => "Ignored",
+            WarningEntry => "Warning: {}",
+            ErrorEntry => "Error: {}",
+            AndMore => "and {} more",
+            AndMoreFailedTests => "and {} more failed tests",
+        },
+        Lang::Ja => match message {
+            TestReport => "テストレポート",
+            Summary => "成功 {}、失敗 {}、その他 {}",
+            Elapsed => "経過時間 {}秒",
+            Warmup => "最初のテストまでのウォームアップ {}秒{}（テスト時間には含まれません）",
+            ShaderCompileSeconds => "シェーダーコンパイル {}秒",
+            DdcMisses => "DDCミス {}件",
+            ReportCreated => "レポート作成 {}（{}）",
+            ReportCreatedRaw => "レポート作成 {}",
+            ReportOlderThanRun => "レポートがこの実行より古いため、エンジンが新しいレポートを書き込んでいない可能性があります",
+            AtChangelist => "チェンジリスト {}",
+            AtCommit => "コミット {}",
+            OnEngine => "エンジン {}",
+            ShuffledWithSeed => "シード {} でシャッフル、同じ順序で再実行するには --shuffle={}",
+            Session => "セッション {}",
+            Labels => "ラベル {}",
+            CrashedDuringExecution => "実行中にクラッシュしました",
+            CrashedDuringExecutionIn => "実行中にクラッシュしました、最後に実行中のテスト {}",
+            CrashedDuringThisTest => "このテスト中にエンジンがクラッシュしました",
+            IncompleteRun => "実行は未完了です、{} 件のテストが実行中のままエンジンが終了しました",
+            ExitedDuringThisTest => "このテスト中にエンジンが終了しました",
+            CrashReport => "クラッシュレポート {}",
+            On => "環境 {}",
+            ExpectedMessages => "想定メッセージ {}件、想定外メッセージ {}件",
+            DefinedIn => "定義 {}",
+            OwnedBy => "担当 {}",
+            FirstError => "最初のエラー",
+            State => "状態",
+            Test => "テスト",
+            Missing => "なし",
+            CreatedOn => "作成 {}",
+            CachedPass => "{} スキップ（キャッシュ済みの成功）",
+            InTheFuture => "未来の日時",
+            JustNow => "たった今",
+            MinutesAgo => "{}分前",
+            HoursAgo => "{}時間{}分前",
+            DaysAgo => "{}日前",
+            GroundTruth => "正解",
+            Incoming => "今回",
+            Delta => "差分",
+            SuccessLabel => "成功",
+            FailLabel => "失敗",
+            WarningLabel => "警告",
+            InfoLabel => "情報",
+            ErrorLabel => "エラー",
+            ExpectedLabel => "想定内",
+            IgnoredLabel => "無視",
+            WarningEntry => "警告: {}",
+            ErrorEntry => "エラー: {}",
+            AndMore => "他 {}件",
+            AndMoreFailedTests => "他に失敗したテスト {}件",
+        },
+    }
+}
+
+// fills the template's {} in order, missing arguments leave their {} as is
+pub fn format(lang: Lang, message: Message, args: &[&dyn Display]) -> String {
+    let mut pieces = template(message, lang).split("{}");
+    let mut text = pieces.next().unwrap_or_default().to_owned();
+    let mut args = args.iter();
+    for piece in pieces {
+        match args.next() {
+            Some(arg) => text.push_str(arg.to_string().as_str()),
+            None => text.push_str("{}"),
+        }
+        text.push_str(piece);
+    }
+    text
+}
+
+pub fn text(lang: Lang, message: Message) -> &'static str {
+    template(message, lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_are_filled_in_order() {
+        assert_eq!(format(Lang::En, Message::Summary, &[&3, &1, &0]), "3 passed, 1 failed, 0 other");
+        assert_eq!(format(Lang::Ja, Message::Summary, &[&3, &1, &0]), "成功 3、失敗 1、その他 0");
+        assert_eq!(format(Lang::En, Message::AtCommit, &[]), "at commit {}");
+        assert_eq!(text(Lang::Ja, Message::TestReport), "テストレポート");
+    }
+
+    #[test]
+    fn every_language_has_the_same_placeholders() {
+        use Message::*;
+        let messages = [TestReport, Summary, Elapsed, Warmup, ShaderCompileSeconds, DdcMisses, ReportCreated, ReportCreatedRaw, ReportOlderThanRun, AtChangelist,
+            AtCommit, OnEngine, ShuffledWithSeed, Session, Labels, CrashedDuringExecution, CrashedDuringExecutionIn, CrashedDuringThisTest,
+            IncompleteRun, ExitedDuringThisTest, CrashReport, On, ExpectedMessages, DefinedIn, OwnedBy, FirstError, State, Test, Missing, CreatedOn, CachedPass,
+            InTheFuture, JustNow, MinutesAgo, HoursAgo, DaysAgo, GroundTruth, Incoming, Delta, SuccessLabel, FailLabel, WarningLabel, InfoLabel, ErrorLabel,
+            ExpectedLabel, IgnoredLabel, WarningEntry, ErrorEntry, AndMore, AndMoreFailedTests];
+        for message in messages {
+            assert_eq!(template(message, Lang::En).matches("{}").count(), template(message, Lang::Ja).matches("{}").count(), "{:?}", message);
+        }
+        assert!(LANG_NAMES.iter().all(|name| parse_lang(name).is_some()));
+        assert_eq!(parse_lang("fr"), None);
+    }
+}
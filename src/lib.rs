@@ -0,0 +1,457 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use intern::Interned;
+
+pub mod analysis;
+pub mod archive;
+#[cfg(feature = "runner")]
+pub mod bisect;
+pub mod branches;
+#[cfg(feature = "runner")]
+pub mod build;
+#[cfg(feature = "runner")]
+pub mod cache;
+pub mod comparisons;
+#[cfg(feature = "runner")]
+pub mod completions;
+#[cfg(feature = "runner")]
+pub mod confighelp;
+pub mod crash;
+pub mod critical;
+pub mod csvprofile;
+pub mod datetime;
+pub mod details;
+pub mod discord;
+#[cfg(feature = "runner")]
+pub mod display;
+#[cfg(feature = "runner")]
+pub mod diskspace;
+#[cfg(feature = "runner")]
+pub mod editor;
+pub mod engine;
+pub mod expected;
+pub mod filters;
+pub mod find;
+pub mod history;
+#[cfg(feature = "runner")]
+pub mod instances;
+pub mod formats;
+pub mod gitlab;
+pub mod http;
+pub mod ignores;
+pub mod intern;
+pub mod knownissues;
+pub mod jenkins;
+pub mod lang;
+#[cfg(feature = "runner")]
+pub mod lock;
+pub mod logexcerpts;
+pub mod manifest;
+pub mod noisyfiles;
+pub mod paths;
+pub mod perf;
+pub mod pipeline;
+pub mod placeholders;
+pub mod platforms;
+#[cfg(feature = "runner")]
+pub mod plugins;
+pub mod projectignores;
+#[cfg(feature = "runner")]
+pub mod remote;
+pub mod report;
+pub mod rerun;
+pub mod retention;
+#[cfg(feature = "runner")]
+pub mod runner;
+pub mod schedule;
+pub mod screenshots;
+pub mod selfupdate;
+pub mod sha256;
+pub mod shuffle;
+pub mod telemetry;
+pub mod testinfo;
+#[cfg(feature = "runner")]
+pub mod testmap;
+pub mod testnames;
+pub mod theme;
+pub mod timeline;
+#[cfg(feature = "runner")]
+pub mod validate;
+pub mod vcs;
+pub mod warmup;
+pub mod width;
+
+// the command line, everything that launches the engine or runs other processes for a run
+#[cfg(feature = "runner")]
+pub mod cli;
+
+pub const EXIT_BUILD_FAILED: i32 = 2;
+pub const EXIT_REPORT_NOT_FOUND: i32 = 3;
+pub const EXIT_ARCHIVE_FAILED: i32 = 4;
+pub const EXIT_DURATION_REGRESSION: i32 = 5;
+pub const EXIT_INVALID_ARGUMENTS: i32 = 6;
+pub const EXIT_INVALID_CONFIG: i32 = 7;
+pub const EXIT_ENGINE_CRASHED: i32 = 8;
+pub const EXIT_PERF_REGRESSION: i32 = 9;
+pub const EXIT_NO_TESTS: i32 = 10;
+pub const EXIT_TOO_FEW_TESTS: i32 = 11;
+pub const EXIT_LOCKED: i32 = 12;
+pub const EXIT_LOW_DISK_SPACE: i32 = 13;
+pub const EXIT_UPDATE_FAILED: i32 = 14;
+pub const EXIT_CRITICAL_TESTS_FAILED: i32 = 15;
+pub const EXIT_INCOMPLETE_RUN: i32 = 16;
+pub const EXIT_INVALID_REPORT: i32 = 17;
+pub const EXIT_ENGINE_LAUNCH_FAILED: i32 = 18;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum EntryType {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    #[serde(rename = "type")]
+    pub entry_type: EntryType,
+    pub message: Interned,
+    pub context: Interned,
+    pub artifact: Interned,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub event: Event,
+    pub filename: Interned,
+    pub line_number: i32,
+    pub timestamp: String,
+    #[serde(skip_deserializing)]
+    pub expected: bool, // matched one of the test's expected messages
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum TestResult {
+    NotRun,                // Automation test was not run
+    InProcess,             // Automation test is running now
+    Fail,                  // Automation test was run and failed
+    Success,               // Automation test was run and succeeded
+    NotEnoughParticipants, // Automation test was not run due to number of participan
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub enum ArtifactType {
+    None,
+    Image,
+    Comparison,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub artifact_type: ArtifactType,
+    #[serde(default)]
+    pub files: std::collections::HashMap<String, String>, // approved / unapproved / difference, relative to the report
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Test {
+    pub test_display_name: String,
+    pub full_test_path: String,
+    pub state: TestResult,
+    #[serde(default)]
+    pub duration: f32,
+    pub entries: Vec<Entry>,
+    pub warnings: i32,
+    pub errors: i32,
+    pub artifacts: Vec<Artifact>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<testinfo::TestInfo>, // from the test's own json, see testinfo::read_test_info
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>, // failed tests only, see analysis::failure_signature
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    pub device_name: String,
+    pub instance: String,
+    pub platform: String,
+    #[serde(rename = "oSVersion")]
+    pub os_version: String,
+    pub model: String,
+    #[serde(rename = "gPU")]
+    pub gpu: String,
+    #[serde(rename = "cPUModel")]
+    pub cpu_model: String,
+    #[serde(rename = "rAMInGB")]
+    pub ram_in_gb: i32,
+    pub render_mode: String,
+    #[serde(rename = "rHI")]
+    pub rhi: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestPass {
+    pub devices: Option<Vec<Device>>,
+    pub report_created_on: String,
+    pub succeeded: i32,
+    pub succeeded_with_warnings: i32,
+    pub failed: i32,
+    pub not_run: i32,
+    pub in_process: Option<i32>,
+    pub total_duration: f32,
+    pub comparison_exported: bool,
+    pub comparison_export_directory: String,
+    #[serde(deserialize_with = "report::deserialize_tests")]
+    pub tests: Vec<Test>,
+    #[serde(skip)]
+    pub schema_warnings: Vec<String>, // what didn't fit the schema and was left out, see report::read_report
+}
+
+// a report holding just these tests, the totals are left at zero for the tests that need them to set
+#[cfg(test)]
+pub(crate) fn test_pass_from_tests(tests_json: &str) -> TestPass {
+    let json = format!(r#"{{"devices": null, "reportCreatedOn": "", "succeeded": 0, "succeededWithWarnings": 0, "failed": 0,
+        "notRun": 0, "inProcess": null, "totalDuration": 0, "comparisonExported": false, "comparisonExportDirectory": "",
+        "tests": [{}]}}"#, tests_json);
+    serde_json::from_str(json.as_str()).unwrap()
+}
+
+pub fn utf_from_bytes(buffer: &[u8]) -> String {
+    let index_json_string = String::from_utf8_lossy(buffer).into_owned();
+    if index_json_string.starts_with('\u{feff}') {
+        String::from_utf8_lossy(&buffer[3..]).into_owned()
+    } else {
+        index_json_string
+    }
+}
+
+// the config with its placeholders expanded, also as toml for the run manifest
+// branch picks the [branch] profile, it's detected from the project's git branch or p4 stream when None
+pub fn parse_config(config_toml: &str, config_file_path: &str, branch: Option<&str>) -> Result<(TestConfiguration, toml::Value), String> {
+    let mut expanded_config: toml::Value = toml::from_str(config_toml).map_err(|error| error.to_string())?;
+    placeholders::expand_config(&mut expanded_config, std::path::Path::new(config_file_path))?;
+    let branch = match branch {
+        Some(branch) => Some(branch.to_owned()),
+        None if expanded_config.get("branch").is_some() => branches::detect_branch(&expanded_config),
+        None => None,
+    };
+    let branch_profile = branches::apply_branch_profile(&mut expanded_config, branch.as_deref())?;
+    let mut config: TestConfiguration = expanded_config.clone().try_into().map_err(|error: toml::de::Error| error.to_string())?;
+    config.branch_profile = branch.zip(branch_profile);
+    let path_separator = platforms::apply_profile(&mut config, platforms::host_platform())?;
+    analysis::Normalizer::new(config.normalization.as_ref())?;
+    datetime::parse_display_zone(config.timezone.as_deref())?;
+    if let Some(markers) = &config.expected_error_markers {
+        expected::check_markers(markers)?;
+    }
+    if let Some(perf_config) = &config.perf {
+        perf::check_metric_patterns(&perf_config.metric_patterns)?;
+    }
+    screenshots::check_tolerances(&config.screenshot_tolerances)?;
+    if let Some(exec_cmd) = config.pre_exec_cmds.iter().chain(config.post_exec_cmds.iter()).find(|exec_cmd| exec_cmd.contains(',')) {
+        return Err(format!("exec commands can't contain commas, the engine would split \"{}\" into separate commands", exec_cmd));
+    }
+    let config_dir = std::path::Path::new(config_file_path).parent().unwrap_or_else(|| std::path::Path::new(""));
+    // with [remote] the paths are on the remote machine, nothing local to resolve them against
+    #[cfg(feature = "runner")]
+    let local_paths = config.remote.is_none();
+    #[cfg(not(feature = "runner"))]
+    let local_paths = true;
+    if local_paths {
+        for path in [&mut config.path_to_project, &mut config.path_to_reports] {
+            *path = paths::resolve_relative(path.as_str(), config_dir);
+        }
+        // a bare executable name is looked up on PATH
+        if config.path_to_unrealengine.contains(['/', '\\']) {
+            config.path_to_unrealengine = paths::resolve_relative(config.path_to_unrealengine.as_str(), config_dir);
+        }
+        // an engine that can't be found only matters to a run, parse and the like go ahead without one
+        if config.path_to_unrealengine.is_empty() {
+            config.engine_discovery = Some(match engine::discover_editor(config.path_to_project.as_str()) {
+                Ok((association, editor)) => {
+                    config.path_to_unrealengine = editor.display().to_string();
+                    let association = if association.is_empty() { "none, the engine around the project".to_owned() } else { association };
+                    Ok(format!("{} (EngineAssociation {})", config.path_to_unrealengine, association))
+                }
+                Err(error) => Err(error),
+            });
+        }
+        let configured = config.project_ignore_file.is_some();
+        let project_ignore_file = projectignores::project_ignore_file(project_directory(&config), config.project_ignore_file.as_deref());
+        if let Some(project_ignores) = projectignores::load_project_ignores(&project_ignore_file, configured)? {
+            config.ignore_regexes.extend(project_ignores.ignore_regexes);
+            config.known_issues.extend(project_ignores.known_issues);
+        }
+    }
+    let known_issue_patterns = knownissues::active_patterns(&config.known_issues, chrono::Local::now().date_naive())?;
+    config.ignore_regexes.extend(known_issue_patterns);
+    if let Some(history_file) = config.history_file.as_mut() {
+        *history_file = paths::resolve_relative(history_file.as_str(), config_dir);
+    }
+    if let Some(test_map_file) = config.test_map_file.as_mut() {
+        *test_map_file = paths::resolve_relative(test_map_file.as_str(), config_dir);
+    }
+    if let Some(source_root) = config.source_root.as_mut() {
+        *source_root = paths::resolve_relative(source_root.as_str(), config_dir);
+    }
+    if let Some(separator) = path_separator {
+        for path in [&mut config.path_to_unrealengine, &mut config.path_to_project, &mut config.path_to_reports] {
+            *path = platforms::with_separator(path.as_str(), separator);
+        }
+    }
+    Ok((config, expanded_config))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestConfiguration {
+    #[serde(default)]
+    pub path_to_unrealengine: String, // found through the .uproject's EngineAssociation when omitted
+    #[serde(skip)]
+    pub engine_discovery: Option<Result<String, String>>, // how an omitted path_to_unrealengine was found, or why it wasn't
+    #[serde(skip)]
+    pub branch_profile: Option<(String, String)>, // the branch and the [branch] pattern it matched
+    pub path_to_project: String,
+    pub path_to_reports: String,
+    pub run_tests: String,
+    pub warmup_tests: Option<String>, // run in a throwaway engine launch before the measured one
+    pub test_exit: String,
+    #[serde(default)]
+    pub pre_exec_cmds: Vec<String>, // console commands run in the engine before the automation command, ie "r.ScreenPercentage 100"
+    #[serde(default)]
+    pub post_exec_cmds: Vec<String>,
+    pub startup_map: Option<String>, // ie /Game/Maps/TestArena, opened at startup instead of the project's default map
+    #[serde(default = "default_max_parallel_engines")]
+    pub max_parallel_engines: usize, // engine processes running at once for --isolate, --jobs overrides it
+    #[serde(default = "default_engine_start_stagger")]
+    pub engine_start_stagger: f32, // seconds between engine launches, simultaneous startups fight over the DDC and shader compilers
+    #[serde(default)]
+    pub acceptable_exit_codes: Vec<i32>, // nonzero engine exit codes that still go on to parse the report
+    #[serde(default)]
+    pub include_tests: Vec<String>, // only these tests are reported, applied to the report after the run
+    #[serde(default)]
+    pub exclude_tests: Vec<String>,
+    pub expected_min_tests: Option<usize>, // fewer tests running means some went missing, ie a module failed to load
+    #[serde(default)]
+    pub critical_tests: Vec<String>, // must pass in every run, failing, not running or missing is a critical failure
+    pub min_free_disk_gb: Option<f64>, // checked on the report and log volumes before launching, screenshot suites write gigabytes
+    pub ignore_regexes: Vec<String>, // the project ignore file's and the known_issues that haven't expired are added, see parse_config
+    pub project_ignore_file: Option<String>, // relative to the project directory, projectignores::DEFAULT_PROJECT_IGNORE_FILE when unset
+    #[serde(default)]
+    pub known_issues: Vec<knownissues::KnownIssue>,
+    #[serde(default)]
+    pub warn_unused_ignores: bool,
+    #[cfg(feature = "runner")]
+    pub runner: Option<runner::RunnerConfiguration>,
+    #[cfg(feature = "runner")]
+    pub display: Option<display::DisplayConfiguration>, // rendering instead of -NullRHI, at a fixed resolution
+    #[serde(default)]
+    pub screenshot_tests: Vec<String>, // test path prefixes that compare screenshots, on top of the ones the last report shows
+    #[serde(default)]
+    pub fail_null_rhi_screenshots: bool, // refuse to run screenshot tests under -NullRHI rather than warn
+    #[cfg(feature = "runner")]
+    pub build: Option<build::BuildConfiguration>,
+    #[cfg(feature = "runner")]
+    pub remote: Option<remote::RemoteConfiguration>,
+    pub symbolizer: Option<crash::SymbolizerConfiguration>,
+    p4: Option<vcs::P4Configuration>,
+    #[serde(default)]
+    pub path_map: Vec<paths::PathMapping>,
+    pub source_url_template: Option<String>, // links file:line locations to source hosting, with {path} and {line}
+    pub source_root: Option<String>, // what {path} is relative to, the project directory when unset
+    #[serde(default)]
+    pub screenshot_tolerances: Vec<screenshots::ScreenshotTolerance>,
+    pub theme: Option<theme::ThemeConfiguration>,
+    pub timezone: Option<String>, // local (default), utc or a fixed offset like +09:00
+    pub expected_error_markers: Option<Vec<String>>,
+    #[serde(default = "default_culprit_min_tests")]
+    pub culprit_min_tests: usize,
+    pub normalization: Option<analysis::NormalizationConfiguration>,
+    pub history_file: Option<String>,
+    pub test_map_file: Option<String>, // written by map-tests, fills in where tests are defined when their report doesn't say
+    #[serde(default)]
+    pub functional_test_maps: Vec<String>, // maps placing functional tests, every map under Content/ naming FunctionalTest when empty
+    pub duration_regressions: Option<history::DurationRegressionConfiguration>,
+    pub perf: Option<perf::PerfConfiguration>,
+    pub postprocess_workers: Option<usize>, // threads used to read per-test json and encode screenshots, one per core when unset
+    pub gitlab: Option<gitlab::GitlabConfiguration>,
+    pub jenkins: Option<jenkins::JenkinsConfiguration>,
+    pub discord: Option<discord::DiscordConfiguration>,
+    pub telemetry: Option<telemetry::TelemetryConfiguration>,
+    pub retention: Option<retention::RetentionConfiguration>,
+    pub schedule: Option<schedule::ScheduleConfiguration>,
+    pub platform: Option<platforms::PlatformConfiguration>, // only the host's profile is applied, see parse_config
+    pub test_names: Option<testnames::TestNamesConfiguration>,
+    pub updates: Option<selfupdate::UpdatesConfiguration>,
+    #[cfg(feature = "runner")]
+    pub plugins: Option<plugins::PluginsConfiguration>,
+    #[serde(default)]
+    pub report_headers: BTreeMap<String, String>, // sent when parse downloads a report, ie a token for the artifact server
+    #[serde(default = "logexcerpts::default_log_excerpt_lines")]
+    pub log_excerpt_lines: usize, // engine log lines shown before and after each error with --log-excerpts
+}
+
+fn default_culprit_min_tests() -> usize {
+    3
+}
+
+fn default_max_parallel_engines() -> usize {
+    1
+}
+
+fn default_engine_start_stagger() -> f32 {
+    5.0
+}
+
+#[derive(Debug, Default)]
+pub struct RunMetadata {
+    pub changelist: Option<String>,
+    pub git: Option<vcs::GitInfo>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub engine_version: Option<engine::BuildVersion>,
+    pub shuffle_seed: Option<u64>,
+    pub session_name: Option<String>,
+    pub meta: BTreeMap<String, String>, // --meta key=value labels, sorted so exports are stable
+    pub crashed: bool, // the engine exited with an unaccepted code or a signal, the report may be partial
+    pub callstack: Vec<crash::StackFrame>, // of the crash, from the crash context or the engine log
+    pub crash_context: Option<crash::CrashContext>,
+    pub warmup: Option<warmup::Warmup>,
+    pub requested_tests: Vec<String>, // named on the command line or in run_tests, empty for --filter
+    pub launches: Vec<telemetry::EngineLaunch>, // every engine process of the run, including the warmup
+    pub cached_tests: Vec<String>, // left out by --cache, they passed with the same inputs before
+    pub cache_inputs: Option<String>, // with --cache, this run's passes are remembered under it
+}
+
+// path_to_reports, or where [remote] copies the reports to
+pub fn local_reports_directory(config: &TestConfiguration) -> std::path::PathBuf {
+    #[cfg(feature = "runner")]
+    if let Some(remote_config) = &config.remote {
+        return remote_config.local_reports();
+    }
+    std::path::PathBuf::from(config.path_to_reports.as_str())
+}
+
+pub fn project_directory(config: &TestConfiguration) -> &std::path::Path {
+    std::path::Path::new(config.path_to_project.as_str()).parent().unwrap_or_else(|| std::path::Path::new("."))
+}
+
+pub fn should_ignore_message(message: &str, ignore_regexes: &[String]) -> bool {
+    matching_ignore_regex(message, ignore_regexes).is_some()
+}
+
+pub fn matching_ignore_regex<'a>(message: &str, ignore_regexes: &'a [String]) -> Option<&'a str> {
+    for ignore_regex in ignore_regexes.iter() {
+        let re = regex::Regex::new(ignore_regex).unwrap();
+        if re.is_match(message) {
+            return Some(ignore_regex.as_str());
+        }
+    }
+    None
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// runtests.lock in the report directory, two editors on the same project overwrite each other's reports
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+    pub started_at: String,
+}
+
+thread_local! {
+    static HELD: RefCell<Option<PathBuf>> = RefCell::new(None);
+}
+
+pub enum LockError {
+    Held(LockOwner),
+    Stale(LockOwner), // the owner's process is gone, --force takes the lock over
+    Io(String),
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_else(|_| "unknown".to_owned())
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    Command::new("tasklist").args(["/FI", format!("PID eq {}", pid).as_str(), "/NH"]).output()
+        .map_or(true, |output| String::from_utf8_lossy(&output.stdout).split_whitespace().any(|word| word == pid.to_string()))
+}
+
+#[cfg(not(windows))]
+fn process_alive(pid: u32) -> bool {
+    Command::new("kill").args(["-0", pid.to_string().as_str()]).output().map_or(true, |output| output.status.success())
+}
+
+// a lock taken on another machine (reports on a share) can't be checked, it counts as held
+fn is_stale(owner: &LockOwner) -> bool {
+    owner.host == host_name() && !process_alive(owner.pid)
+}
+
+fn create(lock_file: &Path) -> std::io::Result<()> {
+    if let Some(parent) = lock_file.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let owner = LockOwner { pid: std::process::id(), host: host_name(), started_at: chrono::Utc::now().to_rfc3339() };
+    let mut file = OpenOptions::new().write(true).create_new(true).open(lock_file)?;
+    file.write_all(serde_json::to_string_pretty(&owner).expect("failed to serialize the lock").as_bytes())
+}
+
+// held until release, which every exit of a run goes through; Ok names the owner of a lock --force broke
+pub fn acquire(lock_file: &Path, force: bool) -> Result<Option<LockOwner>, LockError> {
+    let broken = match create(lock_file) {
+        Ok(()) => None,
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+            // unreadable means it's being written right now or was left half written, neither is ours to take without --force
+            let owner = fs::read_to_string(lock_file).ok().and_then(|contents| serde_json::from_str::<LockOwner>(contents.as_str()).ok())
+                .unwrap_or(LockOwner { pid: 0, host: "unknown".to_owned(), started_at: "unknown".to_owned() });
+            if !force {
+                return Err(if is_stale(&owner) { LockError::Stale(owner) } else { LockError::Held(owner) });
+            }
+            fs::remove_file(lock_file).map_err(|error| LockError::Io(error.to_string()))?;
+            create(lock_file).map_err(|error| LockError::Io(error.to_string()))?;
+            Some(owner)
+        }
+        Err(error) => return Err(LockError::Io(error.to_string())),
+    };
+    HELD.with(|held| *held.borrow_mut() = Some(lock_file.to_path_buf()));
+    Ok(broken)
+}
+
+pub fn release() {
+    if let Some(lock_file) = HELD.with(|held| held.borrow_mut().take()) {
+        fs::remove_file(lock_file).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_second_run_is_refused_until_forced() {
+        let lock_file = std::env::temp_dir().join(format!("runtests-lock-{}", std::process::id())).join("runtests.lock");
+        assert!(matches!(acquire(&lock_file, false), Ok(None)));
+        // this process is still alive, so the lock is held rather than stale
+        assert!(matches!(acquire(&lock_file, false), Err(LockError::Held(owner)) if owner.pid == std::process::id()));
+        assert!(matches!(acquire(&lock_file, true), Ok(Some(owner)) if owner.pid == std::process::id()));
+        release();
+        assert!(!lock_file.exists());
+        fs::remove_dir_all(lock_file.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{datetime, should_ignore_message, EntryType, TestPass, TestResult};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the engine log around an error, an automation entry alone often lacks the warnings that led up to it
+#[derive(Debug)]
+pub struct LogExcerpt {
+    pub test_path: String,
+    pub entry_index: usize,
+    pub log_file: String,
+    pub first_line_number: usize, // 1-based
+    pub matched: usize,           // index into lines of the line the entry was found at
+    pub lines: Vec<String>,
+}
+
+pub(crate) fn default_log_excerpt_lines() -> usize {
+    5
+}
+
+struct LogFile {
+    name: String,
+    lines: Vec<String>,
+    timestamps: Vec<Option<DateTime<Utc>>>,
+}
+
+// [2023.10.01-12.34.56:789][frame], unreal writes log times in utc unless told otherwise
+fn log_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let timestamp = line.strip_prefix('[')?.get(..23)?;
+    NaiveDateTime::parse_from_str(timestamp, "%Y.%m.%d-%H.%M.%S:%3f").ok().map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+fn read_log(path: &Path) -> Option<LogFile> {
+    let bytes = fs::read(path).ok()?;
+    let lines: Vec<String> = String::from_utf8_lossy(&bytes).lines().map(str::to_owned).collect();
+    let timestamps = lines.iter().map(|line| log_timestamp(line)).collect();
+    Some(LogFile { name: path.display().to_string(), lines, timestamps })
+}
+
+// runtests.log, or runtests-N.log of each --jobs or --isolate instance; the warmup's log is never the one
+pub fn log_files(engine_log: &Path) -> Vec<PathBuf> {
+    let mut logs: Vec<PathBuf> = engine_log.parent().and_then(|directory| fs::read_dir(directory).ok())
+        .map(|dir_entries| dir_entries.filter_map(Result::ok).map(|dir_entry| dir_entry.path())
+            .filter(|path| path.file_name().and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("runtests-")?.strip_suffix(".log"))
+                .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit())))
+            .collect())
+        .unwrap_or_default();
+    logs.sort();
+    if engine_log.is_file() {
+        logs.insert(0, engine_log.to_path_buf());
+    }
+    logs
+}
+
+fn distance(log: &LogFile, index: usize, at: Option<DateTime<Utc>>) -> i64 {
+    match (log.timestamps[index], at) {
+        (Some(logged), Some(at)) => (logged - at).num_milliseconds().abs(),
+        _ => i64::MAX,
+    }
+}
+
+// the line holding the message, the one nearest the entry's time if it was logged more than once
+fn find_message(log: &LogFile, message: &str, at: Option<DateTime<Utc>>) -> Option<usize> {
+    let needle: String = message.lines().next().unwrap_or("").trim().chars().take(120).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    log.lines.iter().enumerate()
+        .filter(|(_, line)| line.contains(needle.as_str()))
+        .map(|(index, _)| index)
+        .min_by_key(|index| distance(log, *index, at))
+}
+
+// entries reworded by the automation framework aren't in the log verbatim, the line logged nearest their time is
+fn nearest_line(log: &LogFile, at: DateTime<Utc>) -> Option<usize> {
+    (0..log.lines.len()).filter(|index| log.timestamps[*index].is_some()).min_by_key(|index| distance(log, *index, Some(at)))
+}
+
+// every unexpected error of every failed test
+pub fn find_excerpts(test_pass: &TestPass, log_paths: &[PathBuf], context_lines: usize, ignore_regexes: &[String]) -> Vec<LogExcerpt> {
+    let logs: Vec<LogFile> = log_paths.iter().filter_map(|path| read_log(path)).collect();
+    let mut excerpts = Vec::new();
+    for test in test_pass.tests.iter().filter(|test| test.state == TestResult::Fail) {
+        let unexpected_errors = test.entries.iter().enumerate().filter(|(_, entry)| {
+            matches!(entry.event.entry_type, EntryType::Error) && !entry.expected && !should_ignore_message(entry.event.message.as_str(), ignore_regexes)
+        });
+        for (entry_index, entry) in unexpected_errors {
+            let at = datetime::parse_timestamp(entry.timestamp.as_str());
+            let found = logs.iter().find_map(|log| find_message(log, entry.event.message.as_str(), at).map(|index| (log, index)))
+                .or_else(|| logs.iter()
+                    .filter_map(|log| nearest_line(log, at?).map(|index| (log, index)))
+                    .min_by_key(|(log, index)| distance(log, *index, at)));
+            if let Some((log, index)) = found {
+                let first = index.saturating_sub(context_lines);
+                let last = (index + context_lines + 1).min(log.lines.len());
+                excerpts.push(LogExcerpt {
+                    test_path: test.full_test_path.clone(),
+                    entry_index,
+                    log_file: log.name.clone(),
+                    first_line_number: first + 1,
+                    matched: index - first,
+                    lines: log.lines[first..last].to_vec(),
+                });
+            }
+        }
+    }
+    excerpts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_found_in_the_log_with_their_context() {
+        let log = "\
+[2023.10.01-12.00.00:000][  0]LogInit: Display: Running engine for game: Castle
+[2023.10.01-12.00.01:000][ 10]LogTemp: Warning: Door hinge missing
+[2023.10.01-12.00.02:000][ 11]LogTemp: Error: Door stuck
+[2023.10.01-12.00.03:000][ 12]LogAutomationController: Display: Test Completed. Name={Door}
+";
+        let directory = std::env::temp_dir().join(format!("runtests-excerpts-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let log_path = directory.join("runtests.log");
+        fs::write(&log_path, log).unwrap();
+        fs::write(directory.join("runtests-1.log"), log).unwrap();
+        fs::write(directory.join("runtests-warmup.log"), log).unwrap();
+        let test_pass = crate::test_pass_from_tests(r#"{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Fail", "duration": 1.5, "warnings": 0, "errors": 1,
+            "artifacts": [], "entries": [
+                {"event": {"type": "Info", "message": "Opening", "context": "", "artifact": ""},
+                    "filename": "", "lineNumber": 0, "timestamp": "2023-10-01T12:00:00Z"},
+                {"event": {"type": "Error", "message": "Door stuck", "context": "", "artifact": ""},
+                    "filename": "Door.cpp", "lineNumber": 12, "timestamp": "2023-10-01T12:00:02Z"}]}"#);
+
+        let log_paths = log_files(&log_path);
+        let excerpts = find_excerpts(&test_pass, &log_paths, 1, &[]);
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(log_paths, [log_path.clone(), directory.join("runtests-1.log")]);
+        assert_eq!(excerpts.len(), 1);
+        assert_eq!(excerpts[0].entry_index, 1);
+        assert_eq!(excerpts[0].first_line_number, 2);
+        assert_eq!(excerpts[0].lines.len(), 3);
+        assert!(excerpts[0].lines[excerpts[0].matched].ends_with("Error: Door stuck"));
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// run-manifest.json is written next to the reports when the invocation ends, however it ends, so later
+// pipeline steps read one stable file instead of scraping the console
+#[derive(Debug, Default, Serialize)]
+pub struct RunManifest {
+    pub tool_version: &'static str,
+    pub command_line: Vec<String>,
+    pub config_file: String,
+    pub config: Option<serde_json::Value>, // as loaded, with its placeholders expanded
+    pub engine_version: Option<String>,
+    pub report_file: Option<String>,
+    pub exports: Vec<Export>,
+    pub tests: Option<TestCounts>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub exit_code: i32,
+    pub exit_classification: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Export {
+    pub kind: String, // a --format name, details or archive
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestCounts {
+    pub succeeded: i32,
+    pub failed: i32,
+    pub not_run: i32,
+}
+
+thread_local! {
+    static MANIFEST: RefCell<Option<(PathBuf, RunManifest)>> = RefCell::new(None);
+}
+
+// what each of main's exit codes means
+pub fn exit_classification(exit_code: i32, failed_tests: bool) -> &'static str {
+    match exit_code {
+        0 if failed_tests => "tests_failed",
+        0 => "passed",
+        crate::EXIT_BUILD_FAILED => "build_failed",
+        crate::EXIT_REPORT_NOT_FOUND => "report_not_found",
+        crate::EXIT_ARCHIVE_FAILED => "archive_failed",
+        crate::EXIT_DURATION_REGRESSION => "duration_regression",
+        crate::EXIT_INVALID_ARGUMENTS => "invalid_arguments",
+        crate::EXIT_INVALID_CONFIG => "invalid_config",
+        crate::EXIT_ENGINE_CRASHED => "engine_crashed",
+        crate::EXIT_PERF_REGRESSION => "perf_regression",
+        crate::EXIT_NO_TESTS => "no_tests",
+        crate::EXIT_TOO_FEW_TESTS => "too_few_tests",
+        crate::EXIT_LOCKED => "locked",
+        crate::EXIT_LOW_DISK_SPACE => "low_disk_space",
+        crate::EXIT_UPDATE_FAILED => "update_failed",
+        crate::EXIT_CRITICAL_TESTS_FAILED => "critical_tests_failed",
+        crate::EXIT_INCOMPLETE_RUN => "incomplete_run",
+        crate::EXIT_INVALID_REPORT => "invalid_report",
+        crate::EXIT_ENGINE_LAUNCH_FAILED => "engine_launch_failed",
+        _ => "unknown",
+    }
+}
+
+// from here on the invocation leaves a manifest at path
+pub fn begin(path: PathBuf, manifest: RunManifest) {
+    MANIFEST.with(|current| *current.borrow_mut() = Some((path, manifest)));
+}
+
+pub fn update(change: impl FnOnce(&mut RunManifest)) {
+    MANIFEST.with(|current| {
+        if let Some((_, manifest)) = current.borrow_mut().as_mut() {
+            change(manifest);
+        }
+    });
+}
+
+fn write_manifest(path: &Path, manifest: &RunManifest) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(manifest).expect("failed to serialize the run manifest");
+    fs::write(path, json)
+}
+
+// writes the manifest if one was begun, only the first call writes
+pub fn finish(exit_code: i32) {
+    let taken = MANIFEST.with(|current| current.borrow_mut().take());
+    if let Some((path, mut manifest)) = taken {
+        let failed_tests = manifest.tests.as_ref().map_or(false, |tests| tests.failed > 0);
+        manifest.finished_at = chrono::Utc::now().to_rfc3339();
+        manifest.exit_code = exit_code;
+        manifest.exit_classification = exit_classification(exit_code, failed_tests);
+        if let Err(error) = write_manifest(&path, &manifest) {
+            eprintln!("failed to write {}: {}", path.display(), error);
+        }
+    }
+}
+
+// std::process::exit for everything after the config is loaded
+pub fn exit(exit_code: i32) -> ! {
+    finish(exit_code);
+    std::process::exit(exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_manifest_is_written_once() {
+        let path = std::env::temp_dir().join(format!("runtests-manifest-{}", std::process::id())).join("run-manifest.json");
+        begin(path.clone(), RunManifest { config_file: "testconfig.toml".to_owned(), ..RunManifest::default() });
+        update(|manifest| manifest.exports.push(Export { kind: "junit".to_owned(), path: "results.xml".to_owned() }));
+        update(|manifest| manifest.tests = Some(TestCounts { succeeded: 3, failed: 1, not_run: 0 }));
+        finish(0);
+        finish(crate::EXIT_NO_TESTS);
+
+        let manifest: serde_json::Value = serde_json::from_str(fs::read_to_string(&path).unwrap().as_str()).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(manifest["exit_code"], 0);
+        assert_eq!(manifest["exit_classification"], "tests_failed");
+        assert_eq!(manifest["exports"][0]["kind"], "junit");
+        assert_eq!(manifest["config_file"], "testconfig.toml");
+    }
+}
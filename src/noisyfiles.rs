@@ -0,0 +1,75 @@
+use crate::theme::Theme;
+use crate::{should_ignore_message, EntryType, TestPass};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Debug, PartialEq)]
+pub struct FileNoise {
+    pub filename: String,
+    pub warnings: usize,
+    pub errors: usize,
+    pub tests: usize, // how many tests logged from the file
+}
+
+// warnings and errors of every test grouped by the file that logged them, noisiest first; ignored and
+// expected messages aren't noise, entries without a file can't be attributed to anyone
+pub fn noisy_files(test_pass: &TestPass, ignore_regexes: &[String]) -> Vec<FileNoise> {
+    let mut by_file: HashMap<&str, (usize, usize, Vec<&str>)> = HashMap::new();
+    for test in test_pass.tests.iter() {
+        for entry in test.entries.iter().filter(|entry| !entry.expected && !entry.filename.as_str().is_empty()) {
+            if matches!(entry.event.entry_type, EntryType::Info) || should_ignore_message(entry.event.message.as_str(), ignore_regexes) {
+                continue;
+            }
+            let (warnings, errors, tests) = by_file.entry(entry.filename.as_str()).or_default();
+            match entry.event.entry_type {
+                EntryType::Warning => *warnings += 1,
+                _ => *errors += 1,
+            }
+            if tests.last() != Some(&test.full_test_path.as_str()) {
+                tests.push(test.full_test_path.as_str());
+            }
+        }
+    }
+    let mut noisy_files: Vec<FileNoise> = by_file.into_iter()
+        .map(|(filename, (warnings, errors, tests))| FileNoise { filename: filename.to_owned(), warnings, errors, tests: tests.len() })
+        .collect();
+    noisy_files.sort_by(|a, b| (b.warnings + b.errors).cmp(&(a.warnings + a.errors)).then_with(|| a.filename.cmp(&b.filename)));
+    noisy_files
+}
+
+pub fn write_warnings_by_file(out: &mut dyn Write, test_pass: &TestPass, ignore_regexes: &[String], theme: &Theme) -> io::Result<()> {
+    let noisy_files = noisy_files(test_pass, ignore_regexes);
+    if noisy_files.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "warnings by file")?;
+    for file in noisy_files {
+        let counts = format!("{} errors, {} warnings in {} tests", file.errors, file.warnings, file.tests);
+        let counts = if file.errors > 0 { theme.fail.paint(counts.as_str()) } else { theme.warning.paint(counts.as_str()) };
+        writeln!(out, "{}{} {}", theme.spacer(), theme.path.paint(file.filename.as_str()), counts)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_sorted_by_their_message_count() {
+        let test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "warnings": 2, "errors": 0, "artifacts": [], "entries": [
+                    {"event": {"type": "Warning", "message": "LogDoor: hinge missing", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Door.cpp", "lineNumber": 12, "timestamp": ""},
+                    {"event": {"type": "Warning", "message": "LogViewport: resized", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Viewport.cpp", "lineNumber": 3, "timestamp": ""}]},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Fail", "warnings": 1, "errors": 2, "artifacts": [], "entries": [
+                    {"event": {"type": "Error", "message": "LogDoor: stuck", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Door.cpp", "lineNumber": 40, "timestamp": ""},
+                    {"event": {"type": "Warning", "message": "LogDoor: hinge missing", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Door.cpp", "lineNumber": 12, "timestamp": ""},
+                    {"event": {"type": "Error", "message": "LogGate: locked", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Gate.cpp", "lineNumber": 7, "timestamp": ""},
+                    {"event": {"type": "Info", "message": "LogGate: opened", "context": "", "artifact": ""}, "filename": "F:/Castle/Source/Gate.cpp", "lineNumber": 9, "timestamp": ""}]}"#);
+        let noisy_files = noisy_files(&test_pass, &["LogViewport:".to_owned()]);
+        assert_eq!(noisy_files, vec![
+            FileNoise { filename: "F:/Castle/Source/Door.cpp".to_owned(), warnings: 2, errors: 1, tests: 2 },
+            FileNoise { filename: "F:/Castle/Source/Gate.cpp".to_owned(), warnings: 0, errors: 1, tests: 1 },
+        ]);
+    }
+}
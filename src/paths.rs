@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+pub struct PathMapping {
+    pub from: String,
+    pub to: String,
+}
+
+// rewrites a path from the machine that produced the report into one that exists locally,
+// the first mapping whose prefix matches whole path components wins (separators and ascii case are ignored
+// when matching), D:/Build doesn't match D:/BuildTools
+pub fn remap_path(filename: &str, path_map: &[PathMapping]) -> String {
+    let normalized = filename.replace('\\', "/");
+    for mapping in path_map {
+        let from = mapping.from.replace('\\', "/");
+        if let Some(prefix) = normalized.get(..from.len()) {
+            let rest = &normalized[from.len()..];
+            if prefix.eq_ignore_ascii_case(from.as_str()) && (from.ends_with('/') || rest.is_empty() || rest.starts_with('/')) {
+                return format!("{}{}", mapping.to, rest);
+            }
+        }
+    }
+    filename.to_owned()
+}
+
+// a relative path in the config is relative to the config file, not to wherever the tool was started from;
+// windows paths count as absolute on every platform since configs are shared between machines
+pub fn resolve_relative(path: &str, config_dir: &Path) -> String {
+    let bytes = path.as_bytes();
+    let windows_absolute = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && (bytes[2] == b'/' || bytes[2] == b'\\');
+    if path.is_empty() || windows_absolute || path.starts_with('/') || path.starts_with('\\') || Path::new(path).is_absolute() {
+        return path.to_owned();
+    }
+    config_dir.join(path).display().to_string()
+}
+
+// source_url_template with {path} filled in relative to source_root and {line}; files outside source_root,
+// ie engine sources, aren't in the hosted repository and get no link
+pub fn source_url(template: &str, source_root: &str, filename: &str, line_number: i32) -> Option<String> {
+    let normalized = filename.replace('\\', "/");
+    let root = format!("{}/", source_root.replace('\\', "/").trim_end_matches('/'));
+    let prefix = normalized.get(..root.len()).filter(|prefix| prefix.eq_ignore_ascii_case(root.as_str()))?;
+    let relative = &normalized[prefix.len()..];
+    if relative.is_empty() {
+        return None;
+    }
+    Some(template.replace("{path}", relative).replace("{line}", line_number.max(1).to_string().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(from: &str, to: &str) -> PathMapping {
+        PathMapping { from: from.to_owned(), to: to.to_owned() }
+    }
+
+    #[test]
+    fn first_match_wins() {
+        let path_map = [mapping("D:/Build/Plugins", "E:/Plugins"), mapping("D:/Build", "C:/Work")];
+        assert_eq!(remap_path("D:/Build/Plugins/A.cpp", &path_map), "E:/Plugins/A.cpp");
+        assert_eq!(remap_path("X:/Other/A.cpp", &path_map), "X:/Other/A.cpp");
+    }
+
+    #[test]
+    fn prefixes_match_whole_directories() {
+        let path_map = [mapping("D:/Build", "C:/Work")];
+        assert_eq!(remap_path("D:/BuildTools/A.cpp", &path_map), "D:/BuildTools/A.cpp");
+        assert_eq!(remap_path("d:\\build\\A.cpp", &path_map), "C:/Work/A.cpp");
+        assert_eq!(remap_path("D:/Build", &path_map), "C:/Work");
+        assert_eq!(remap_path("D:/BuildTools/A.cpp", &[mapping("D:/Build/", "C:/Work/")]), "D:/BuildTools/A.cpp");
+    }
+
+    #[test]
+    fn source_urls_are_relative_to_the_root() {
+        let template = "https://github.com/castle/castle/blob/main/{path}#L{line}";
+        assert_eq!(source_url(template, "F:/Castle", "f:\\Castle\\Source\\Door.cpp", 12).as_deref(), Some("https://github.com/castle/castle/blob/main/Source/Door.cpp#L12"));
+        assert_eq!(source_url(template, "F:/Castle/", "F:/Castle/Source/Door.cpp", 0).as_deref(), Some("https://github.com/castle/castle/blob/main/Source/Door.cpp#L1"));
+        assert_eq!(source_url(template, "F:/Castle", "C:/UE_5.3/Engine/Source/Runtime/Core.cpp", 3), None);
+        assert_eq!(source_url(template, "F:/Castle", "", 3), None);
+    }
+
+    #[test]
+    fn relative_paths_are_relative_to_the_config() {
+        let config_dir = Path::new("scripts/ci");
+        assert_eq!(Path::new(resolve_relative("../Castle.uproject", config_dir).as_str()), config_dir.join("../Castle.uproject"));
+        assert_eq!(resolve_relative("F:/Castle/Castle.uproject", config_dir), "F:/Castle/Castle.uproject");
+        assert_eq!(resolve_relative("\\\\builds\\Reports", config_dir), "\\\\builds\\Reports");
+        assert_eq!(resolve_relative("/srv/castle/Castle.uproject", config_dir), "/srv/castle/Castle.uproject");
+        assert_eq!(resolve_relative("Saved/Reports", Path::new("")), "Saved/Reports");
+    }
+}
@@ -0,0 +1,289 @@
+use crate::history::HistoryRun;
+use crate::theme::Theme;
+use crate::{filters, EntryType, TestPass};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+pub struct PerfConfiguration {
+    // matched against info entries, each needs a `name` and a `value` group
+    #[serde(default = "default_metric_patterns")]
+    pub metric_patterns: Vec<String>,
+    #[serde(default)]
+    pub budgets: Vec<PerfBudget>,
+    pub regression_percent: Option<f64>, // compared to the mean of earlier runs in history_file
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "default_window")]
+    pub window: usize,
+    #[serde(default)]
+    pub higher_is_better: Vec<String>, // metrics like fps where a drop is the regression
+    #[serde(default)]
+    pub fail_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerfBudget {
+    pub metric: String,
+    #[serde(default)]
+    pub tests: Vec<String>, // test path patterns as in include_tests, every test when empty
+    pub max: Option<f64>,
+    pub min: Option<f64>,
+}
+
+// "FrameTime = 16.2 ms", the whole message has to be the metric so ordinary log lines aren't picked up
+pub(crate) fn default_metric_patterns() -> Vec<String> {
+    vec![r"^(?P<name>[A-Za-z][\w.]*)\s*=\s*(?P<value>-?\d+(?:\.\d+)?)\s*[A-Za-z%]*$".to_owned()]
+}
+
+// checked when the config is parsed so a typo or a missing group fails the config instead of a run
+pub fn check_metric_patterns(patterns: &[String]) -> Result<(), String> {
+    for pattern in patterns {
+        let regex = regex::Regex::new(pattern.as_str()).map_err(|error| format!("perf metric pattern {} is invalid: {}", pattern, error))?;
+        for group in ["name", "value"] {
+            if !regex.capture_names().flatten().any(|name| name == group) {
+                return Err(format!("perf metric pattern {} has no `{}` group", pattern, group));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn default_min_samples() -> usize {
+    3
+}
+
+pub(crate) fn default_window() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metric {
+    pub path: String,
+    pub name: String,
+    pub value: f64,
+}
+
+pub struct PerfRegression {
+    pub path: String,
+    pub name: String,
+    pub value: f64,
+    pub reason: String,
+}
+
+// every numeric column of a csv artifact (one row per frame or sample) becomes a metric holding the column's mean
+fn csv_metrics(csv_file: &Path, test_path: &str, metrics: &mut Vec<Metric>) {
+    let contents = match fs::read_to_string(csv_file) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let columns: Vec<&str> = match lines.next() {
+        Some(header) => header.split(',').map(str::trim).collect(),
+        None => return,
+    };
+    let mut totals = vec![(0.0, 0usize); columns.len()];
+    for line in lines {
+        for (total, cell) in totals.iter_mut().zip(line.split(',')) {
+            if let Ok(value) = cell.trim().parse::<f64>() {
+                total.0 += value;
+                total.1 += 1;
+            }
+        }
+    }
+    for (column, (sum, count)) in columns.iter().zip(totals) {
+        if count > 0 && !column.is_empty() {
+            metrics.push(Metric { path: test_path.to_owned(), name: column.to_string(), value: sum / count as f64 });
+        }
+    }
+}
+
+pub fn extract_metrics(test_pass: &TestPass, report_directory: &Path, config: &PerfConfiguration) -> Vec<Metric> {
+    let patterns: Vec<regex::Regex> = config.metric_patterns.iter()
+        .filter_map(|pattern| regex::Regex::new(pattern.as_str()).ok())
+        .collect();
+
+    let mut metrics = Vec::new();
+    for test in test_pass.tests.iter() {
+        for entry in test.entries.iter().filter(|entry| matches!(entry.event.entry_type, EntryType::Info)) {
+            let message = entry.event.message.as_str().trim();
+            let captures = match patterns.iter().find_map(|pattern| pattern.captures(message)) {
+                Some(captures) => captures,
+                None => continue,
+            };
+            if let (Some(name), Some(Ok(value))) = (captures.name("name"), captures.name("value").map(|value| value.as_str().parse::<f64>())) {
+                metrics.push(Metric { path: test.full_test_path.clone(), name: name.as_str().to_owned(), value });
+            }
+        }
+        for artifact in test.artifacts.iter() {
+            for file in artifact.files.values().filter(|file| file.to_ascii_lowercase().ends_with(".csv")) {
+                csv_metrics(&report_directory.join(file), test.full_test_path.as_str(), &mut metrics);
+            }
+        }
+    }
+    metrics
+}
+
+// mean of each (test, metric) within the most recent `window` runs
+fn mean_metrics(history: &[HistoryRun], window: usize) -> HashMap<(&str, &str), (f64, usize)> {
+    let mut totals: HashMap<(&str, &str), (f64, usize)> = HashMap::new();
+    for run in history.iter().rev().take(window) {
+        for metric in run.metrics.iter() {
+            let total = totals.entry((metric.path.as_str(), metric.name.as_str())).or_insert((0.0, 0));
+            total.0 += metric.value;
+            total.1 += 1;
+        }
+    }
+    totals.into_iter().map(|(key, (sum, count))| (key, (sum / count as f64, count))).collect()
+}
+
+pub fn perf_regressions(metrics: &[Metric], history: &[HistoryRun], config: &PerfConfiguration) -> Vec<PerfRegression> {
+    let means = mean_metrics(history, config.window);
+    let mut regressions = Vec::new();
+    for metric in metrics.iter() {
+        let budgets = config.budgets.iter()
+            .filter(|budget| budget.metric == metric.name && filters::is_selected(metric.path.as_str(), &budget.tests, &[]));
+        for budget in budgets {
+            if let Some(max) = budget.max.filter(|max| metric.value > *max) {
+                regressions.push(PerfRegression { path: metric.path.clone(), name: metric.name.clone(), value: metric.value, reason: format!("over budget {}", max) });
+            }
+            if let Some(min) = budget.min.filter(|min| metric.value < *min) {
+                regressions.push(PerfRegression { path: metric.path.clone(), name: metric.name.clone(), value: metric.value, reason: format!("under budget {}", min) });
+            }
+        }
+
+        let threshold = match config.regression_percent {
+            Some(threshold) => threshold / 100.0,
+            None => continue,
+        };
+        let (mean, samples) = match means.get(&(metric.path.as_str(), metric.name.as_str())) {
+            Some(mean) => *mean,
+            None => continue,
+        };
+        if samples < config.min_samples || mean == 0.0 {
+            continue;
+        }
+        let change = metric.value / mean - 1.0;
+        let regressed = if config.higher_is_better.contains(&metric.name) { change < -threshold } else { change > threshold };
+        if regressed {
+            regressions.push(PerfRegression { path: metric.path.clone(), name: metric.name.clone(), value: metric.value, reason: format!("{:+.0}% from {:.2}", change * 100.0, mean) });
+        }
+    }
+    regressions
+}
+
+pub fn print_perf_regressions(regressions: &[PerfRegression], theme: &Theme) {
+    if regressions.is_empty() {
+        return;
+    }
+    eprintln!("{}", theme.fail.paint("perf metric regressions"));
+    for regression in regressions {
+        eprintln!("{}{} {:.2} {} {}", theme.spacer(), regression.name, regression.value, regression.reason, regression.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_patterns_need_name_and_value_groups() {
+        assert!(check_metric_patterns(&default_metric_patterns()).is_ok());
+        let missing_value = check_metric_patterns(&[r"^(?P<name>\w+) = \d+$".to_owned()]).unwrap_err();
+        assert_eq!(missing_value, r"perf metric pattern ^(?P<name>\w+) = \d+$ has no `value` group");
+        assert!(check_metric_patterns(&["(?P<name>".to_owned()]).unwrap_err().contains("is invalid"));
+    }
+
+    fn metric(path: &str, name: &str, value: f64) -> Metric {
+        Metric { path: path.to_owned(), name: name.to_owned(), value }
+    }
+
+    fn run(metrics: Vec<Metric>) -> HistoryRun {
+        HistoryRun {
+            report_created_on: String::new(),
+            changelist: None,
+            commit: None,
+            branch: None,
+            dirty: None,
+            engine_version: None,
+            session_name: None,
+            meta: Default::default(),
+            warmup_seconds: None,
+            tests: Vec::new(),
+            metrics,
+        }
+    }
+
+    fn perf_config(toml: &str) -> PerfConfiguration {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn reasons(regressions: &[PerfRegression]) -> Vec<String> {
+        regressions.iter().map(|regression| format!("{} {} {}", regression.path, regression.name, regression.reason)).collect()
+    }
+
+    #[test]
+    fn budgets_flag_values_over_max_or_under_min() {
+        let config = perf_config(r#"
+            [[budgets]]
+            metric = "FrameTime"
+            max = 16.6
+            [[budgets]]
+            metric = "Fps"
+            tests = ["Project.Rendering"]
+            min = 30.0
+        "#);
+        let metrics = [
+            metric("Project.Rendering.Forest", "FrameTime", 20.0),
+            metric("Project.Rendering.Forest", "Fps", 25.0),
+            metric("Project.Rendering.Desert", "FrameTime", 12.0),
+            metric("Project.Gameplay.Door", "Fps", 10.0),
+        ];
+        let regressions = perf_regressions(&metrics, &[], &config);
+        assert_eq!(reasons(&regressions), ["Project.Rendering.Forest FrameTime over budget 16.6", "Project.Rendering.Forest Fps under budget 30"]);
+    }
+
+    #[test]
+    fn regressions_against_the_history_mean() {
+        let history: Vec<HistoryRun> = (0..3).map(|_| run(vec![metric("Project.Forest", "FrameTime", 10.0), metric("Project.Forest", "Fps", 60.0)])).collect();
+        let config = perf_config("regression_percent = 10.0\nhigher_is_better = [\"Fps\"]");
+        let slower = [metric("Project.Forest", "FrameTime", 12.0), metric("Project.Forest", "Fps", 50.0)];
+        assert_eq!(reasons(&perf_regressions(&slower, &history, &config)), ["Project.Forest FrameTime +20% from 10.00", "Project.Forest Fps -17% from 60.00"]);
+        let faster = [metric("Project.Forest", "FrameTime", 8.0), metric("Project.Forest", "Fps", 70.0)];
+        assert!(perf_regressions(&faster, &history, &config).is_empty());
+        let within = [metric("Project.Forest", "FrameTime", 10.5)];
+        assert!(perf_regressions(&within, &history, &config).is_empty());
+    }
+
+    #[test]
+    fn regressions_need_enough_samples_and_a_nonzero_mean() {
+        let history: Vec<HistoryRun> = (0..3).map(|_| run(vec![metric("Project.Forest", "FrameTime", 10.0), metric("Project.Forest", "Hitches", 0.0)])).collect();
+        let metrics = [metric("Project.Forest", "FrameTime", 20.0), metric("Project.Forest", "Hitches", 4.0)];
+        let config = perf_config("regression_percent = 10.0\nmin_samples = 4");
+        assert!(perf_regressions(&metrics, &history, &config).is_empty());
+        let config = perf_config("regression_percent = 10.0");
+        assert_eq!(reasons(&perf_regressions(&metrics, &history, &config)), ["Project.Forest FrameTime +100% from 10.00"]);
+        let config = perf_config("regression_percent = 10.0\nwindow = 2\nmin_samples = 3");
+        assert!(perf_regressions(&metrics, &history, &config).is_empty());
+    }
+
+    #[test]
+    fn csv_columns_become_mean_metrics() {
+        let csv_file = std::env::temp_dir().join(format!("runtests-perf-{}.csv", std::process::id()));
+        fs::write(&csv_file, "FrameTime, GameThread,\n10, 4.5,\n\n20, n/a,\n").unwrap();
+        let mut metrics = Vec::new();
+        csv_metrics(&csv_file, "Project.Forest", &mut metrics);
+        fs::write(&csv_file, "").unwrap();
+        let mut empty = Vec::new();
+        csv_metrics(&csv_file, "Project.Forest", &mut empty);
+        fs::remove_file(&csv_file).unwrap();
+        csv_metrics(&csv_file, "Project.Forest", &mut empty);
+
+        let means: Vec<(&str, f64)> = metrics.iter().map(|metric| (metric.name.as_str(), metric.value)).collect();
+        assert_eq!(means, [("FrameTime", 15.0), ("GameThread", 4.5)]);
+        assert!(metrics.iter().all(|metric| metric.path == "Project.Forest"));
+        assert!(empty.is_empty());
+    }
+}
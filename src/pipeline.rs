@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// postprocess_workers from the config, or one worker per core
+pub fn worker_count(configured: Option<usize>) -> usize {
+    match configured {
+        Some(workers) if workers > 0 => workers,
+        _ => thread::available_parallelism().map_or(4, |workers| workers.get()),
+    }
+}
+
+// applies work to every item on at most `workers` threads, results come back in the same order as the items;
+// meant for the slow per-artifact steps (reading per-test json, encoding screenshots, copying files) of large runs
+pub fn map_bounded<T, R, F>(items: &[T], workers: usize, work: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = workers.clamp(1, items.len().max(1));
+    if workers == 1 {
+        return items.iter().map(work).collect();
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers).map(|_| scope.spawn(|| {
+            let mut finished = Vec::new();
+            loop {
+                let index = next_item.fetch_add(1, Ordering::Relaxed);
+                match items.get(index) {
+                    Some(item) => finished.push((index, work(item))),
+                    None => return finished,
+                }
+            }
+        })).collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("post-processing worker panicked")).collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_keep_item_order() {
+        let items: Vec<u64> = (0..100).collect();
+        let squares = map_bounded(&items, 8, |item| {
+            thread::sleep(std::time::Duration::from_micros(100 - item));
+            item * item
+        });
+        assert_eq!(squares, items.iter().map(|item| item * item).collect::<Vec<u64>>());
+        assert!(map_bounded(&Vec::<u64>::new(), 8, |item| *item).is_empty());
+    }
+
+    #[test]
+    fn configured_worker_count() {
+        assert_eq!(worker_count(Some(3)), 3);
+        assert!(worker_count(Some(0)) >= 1);
+        assert!(worker_count(None) >= 1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+// every string in the config may use ${NAME} for an environment variable, {config_dir} for the directory of the
+// config file and {project_dir} for the directory of path_to_project, so one config serves every machine;
+// other {names} are left alone, [symbolizer] args and the like fill those in later
+pub fn expand_config(config: &mut toml::Value, config_file: &Path) -> Result<(), String> {
+    // absolute, a placeholder in a relative path must not be resolved against the config directory a second time
+    let config_dir = config_file.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let config_dir = std::env::current_dir().map_or_else(|_| config_dir.to_path_buf(), |current_dir| current_dir.join(config_dir));
+    let config_dir = config_dir.display().to_string().replace('\\', "/");
+    let mut missing = Vec::new();
+    expand_strings(config, &mut |text| {
+        let expanded = expand_env(text, &mut |name| std::env::var(name).ok(), &mut missing);
+        expanded.replace("{config_dir}", config_dir.as_str())
+    });
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        missing.dedup();
+        return Err(format!("unset environment variables: {}", missing.join(", ")));
+    }
+
+    // path_to_project may be built from the other placeholders, the rest can build on it
+    let project_dir = config.get("path_to_project").and_then(toml::Value::as_str)
+        .map(|path_to_project| Path::new(path_to_project).parent().map_or_else(|| ".".to_owned(), |parent| parent.display().to_string()));
+    if let Some(project_dir) = project_dir {
+        expand_strings(config, &mut |text| text.replace("{project_dir}", project_dir.as_str()));
+    }
+    Ok(())
+}
+
+fn expand_strings(value: &mut toml::Value, expand: &mut dyn FnMut(&str) -> String) {
+    match value {
+        toml::Value::String(text) => *text = expand(text.as_str()),
+        toml::Value::Array(values) => values.iter_mut().for_each(|value| expand_strings(value, expand)),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, value)| expand_strings(value, expand)),
+        _ => {}
+    }
+}
+
+fn expand_env(text: &str, lookup: &mut dyn FnMut(&str) -> Option<String>, missing: &mut Vec<String>) -> String {
+    let variable = regex::Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+    variable.replace_all(text, |captures: &regex::Captures| match lookup(&captures[1]) {
+        Some(value) => value,
+        None => {
+            missing.push(captures[1].to_owned());
+            String::new()
+        }
+    }).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_variables_are_replaced() {
+        let mut missing = Vec::new();
+        let mut lookup = |name: &str| if name == "UE_ROOT" { Some("C:/UE_5.3".to_owned()) } else { None };
+        assert_eq!(expand_env("${UE_ROOT}/Engine/Binaries", &mut lookup, &mut missing), "C:/UE_5.3/Engine/Binaries");
+        assert_eq!(expand_env("$UE_ROOT and {module}", &mut lookup, &mut missing), "$UE_ROOT and {module}");
+        assert!(missing.is_empty());
+        expand_env("${CASTLE_ROOT}/Castle.uproject", &mut lookup, &mut missing);
+        assert_eq!(missing, ["CASTLE_ROOT"]);
+    }
+
+    #[test]
+    fn directories_are_replaced_in_every_string() {
+        let mut config: toml::Value = toml::from_str(r#"
+            path_to_project = "{config_dir}/Castle/Castle.uproject"
+            path_to_reports = "{project_dir}/Saved/Automation"
+            [symbolizer]
+            args = ["--obj={project_dir}/Binaries/{module}", "{address}"]"#).unwrap();
+        let config_dir = std::env::temp_dir().join("castle");
+        expand_config(&mut config, &config_dir.join("testconfig.toml")).unwrap();
+        let config_dir = config_dir.display().to_string().replace('\\', "/");
+        assert_eq!(config["path_to_project"].as_str(), Some(format!("{}/Castle/Castle.uproject", config_dir).as_str()));
+        assert_eq!(config["path_to_reports"].as_str(), Some(format!("{}/Castle/Saved/Automation", config_dir).as_str()));
+        assert_eq!(config["symbolizer"]["args"][0].as_str(), Some(format!("--obj={}/Castle/Binaries/{{module}}", config_dir).as_str()));
+        assert_eq!(config["symbolizer"]["args"][1].as_str(), Some("{address}"));
+    }
+}
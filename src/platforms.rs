@@ -0,0 +1,109 @@
+#[cfg(feature = "runner")]
+use crate::runner;
+use crate::TestConfiguration;
+use serde::Deserialize;
+use std::path::Path;
+
+// [platform.windows], [platform.linux] and [platform.mac] adjust the config for the os runtests runs on,
+// so one checked-in config works on every build agent
+#[derive(Debug, Default, Deserialize)]
+pub struct PlatformConfiguration {
+    pub windows: Option<PlatformProfile>,
+    pub linux: Option<PlatformProfile>,
+    pub mac: Option<PlatformProfile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PlatformProfile {
+    pub path_to_unrealengine: Option<String>,
+    pub path_to_project: Option<String>,
+    pub path_to_reports: Option<String>,
+    pub engine_binary: Option<String>, // replaces the file name of path_to_unrealengine, ie UnrealEditor.exe or UnrealEditor
+    #[serde(default)]
+    pub extra_args: Vec<String>, // added to [runner] extra_args
+    pub path_separator: Option<String>, // / or \, the separator the path_to_* settings are rewritten to use
+}
+
+pub fn host_platform() -> &'static str {
+    if cfg!(windows) {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "mac"
+    } else {
+        "linux"
+    }
+}
+
+// applied before relative paths are resolved so the profile's paths are resolved the same way, the returned
+// path separator only once they are
+pub fn apply_profile(config: &mut TestConfiguration, platform: &str) -> Result<Option<char>, String> {
+    let platforms = config.platform.take().unwrap_or_default();
+    let profile = match platform {
+        "windows" => platforms.windows,
+        "mac" => platforms.mac,
+        _ => platforms.linux,
+    };
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+    if let Some(path_to_unrealengine) = profile.path_to_unrealengine {
+        config.path_to_unrealengine = path_to_unrealengine;
+    }
+    if let Some(path_to_project) = profile.path_to_project {
+        config.path_to_project = path_to_project;
+    }
+    if let Some(path_to_reports) = profile.path_to_reports {
+        config.path_to_reports = path_to_reports;
+    }
+    if let Some(engine_binary) = profile.engine_binary {
+        config.path_to_unrealengine = match Path::new(config.path_to_unrealengine.as_str()).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            Some(parent) => parent.join(engine_binary).display().to_string(),
+            None => engine_binary,
+        };
+    }
+    #[cfg(feature = "runner")]
+    if !profile.extra_args.is_empty() {
+        config.runner.get_or_insert_with(runner::RunnerConfiguration::default).extra_args.extend(profile.extra_args);
+    }
+    match profile.path_separator.as_deref() {
+        None => Ok(None),
+        Some("/") => Ok(Some('/')),
+        Some("\\") => Ok(Some('\\')),
+        Some(other) => Err(format!("[platform.{}] path_separator must be / or \\, not {}", platform, other)),
+    }
+}
+
+pub fn with_separator(path: &str, separator: char) -> String {
+    path.replace(['/', '\\'], separator.to_string().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_host_profile_overrides_the_config() {
+        let mut config: TestConfiguration = toml::from_str(r#"
+            path_to_unrealengine = "C:/UE_5.3/Engine/Binaries/Win64/UnrealEditor.exe"
+            path_to_project = "F:/Castle/Castle.uproject"
+            path_to_reports = "F:/Castle/Saved/Reports"
+            run_tests = ""
+            test_exit = ""
+            ignore_regexes = []
+            [platform.windows]
+            path_separator = "\\"
+            [platform.linux]
+            path_to_unrealengine = "/opt/UE_5.3/Engine/Binaries/Linux/UnrealEditor.exe"
+            path_to_project = "/srv/castle/Castle.uproject"
+            engine_binary = "UnrealEditor"
+            extra_args = ["-nullrhi"]"#).unwrap();
+        assert_eq!(apply_profile(&mut config, "linux"), Ok(None));
+        assert_eq!(Path::new(config.path_to_unrealengine.as_str()), Path::new("/opt/UE_5.3/Engine/Binaries/Linux/UnrealEditor"));
+        assert_eq!(config.path_to_project, "/srv/castle/Castle.uproject");
+        assert_eq!(config.path_to_reports, "F:/Castle/Saved/Reports");
+        #[cfg(feature = "runner")]
+        assert_eq!(config.runner.as_ref().unwrap().extra_args, ["-nullrhi"]);
+        assert_eq!(with_separator("F:/Castle/Saved\\Reports", '\\'), "F:\\Castle\\Saved\\Reports");
+    }
+}
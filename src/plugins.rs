@@ -0,0 +1,61 @@
+use crate::runner;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::Write;
+use std::process::Stdio;
+
+// bumped when the message changes in a way existing plugins would misread
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// [plugins] lists command lines that are run at points of a run, each gets one json message on stdin:
+// {"protocol": 1, "event": "run_finished", "new_failures": [...], "run": <the --format json report>}
+#[derive(Debug, Deserialize)]
+pub struct PluginsConfiguration {
+    #[serde(default)]
+    pub run_finished: Vec<String>, // after every run, passed or not
+    #[serde(default)]
+    pub new_failure: Vec<String>, // when tests fail that didn't in the previous run of the session
+}
+
+pub fn message(event: &str, new_failures: &[&str], run: serde_json::Value) -> serde_json::Value {
+    json!({ "protocol": PROTOCOL_VERSION, "event": event, "new_failures": new_failures, "run": run })
+}
+
+// a plugin that can't be started or fails only warns, integrations shouldn't fail the run
+pub fn notify(commands: &[String], event: &str, message: &serde_json::Value) -> Vec<String> {
+    let body = serde_json::to_vec(message).expect("failed to serialize the plugin message");
+    let mut failures = Vec::new();
+    for command_line in commands {
+        let mut child = match runner::shell_command(command_line.as_str()).env("RUNTESTS_EVENT", event).stdin(Stdio::piped()).stdout(std::io::stderr()).spawn() {
+            Ok(child) => child,
+            Err(error) => {
+                failures.push(format!("failed to run {}: {}", command_line, error));
+                continue;
+            }
+        };
+        // a plugin that exits without reading everything closes the pipe, that isn't a failure by itself
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&body).ok();
+        }
+        match child.wait() {
+            Ok(status) if status.success() => {}
+            Ok(status) => failures.push(format!("{} exited with {}", command_line, status)),
+            Err(error) => failures.push(format!("failed to wait for {}: {}", command_line, error)),
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugins_read_the_message_from_stdin() {
+        let message = message("new_failure", &["Project.Door.Opens"], json!({ "changelist": "12345" }));
+        assert_eq!(message["run"]["changelist"], "12345");
+        let reads_stdin = if cfg!(windows) { "findstr new_failure" } else { "grep -q '\"event\":\"new_failure\"'" };
+        assert!(notify(&[reads_stdin.to_owned()], "new_failure", &message).is_empty());
+        assert_eq!(notify(&["exit 3".to_owned()], "new_failure", &message).len(), 1);
+    }
+}
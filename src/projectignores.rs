@@ -0,0 +1,60 @@
+use crate::knownissues::KnownIssue;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// a file checked into the game project with its own ignore_regexes and [[known_issues]], added to the tool
+// config's so gameplay teams can manage their suppressions in their repo instead of the farm's config
+pub const DEFAULT_PROJECT_IGNORE_FILE: &str = "Config/automation-ignores.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectIgnores {
+    #[serde(default)]
+    pub ignore_regexes: Vec<String>,
+    #[serde(default)]
+    pub known_issues: Vec<KnownIssue>,
+}
+
+// project_ignore_file is relative to the project directory, like the project's other config files
+pub fn project_ignore_file(project_directory: &Path, configured: Option<&str>) -> PathBuf {
+    project_directory.join(configured.unwrap_or(DEFAULT_PROJECT_IGNORE_FILE))
+}
+
+// a project without the default file has nothing to add, one configured by name has to exist
+pub fn load_project_ignores(path: &Path, configured: bool) -> Result<Option<ProjectIgnores>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound && !configured => return Ok(None),
+        Err(error) => return Err(format!("failed to read the project ignore file {}: {}", path.display(), error)),
+    };
+    let project_ignores: ProjectIgnores = toml::from_str(contents.as_str()).map_err(|error| format!("{}: {}", path.display(), error))?;
+    for pattern in project_ignores.ignore_regexes.iter() {
+        regex::Regex::new(pattern.as_str()).map_err(|error| format!("{} has an invalid ignore pattern: {}", path.display(), error))?;
+    }
+    Ok(Some(project_ignores))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_ignores_are_optional_unless_configured() {
+        let project_directory = std::env::temp_dir().join(format!("runtests-projectignores-{}", std::process::id()));
+        let path = project_ignore_file(&project_directory, None);
+        assert!(matches!(load_project_ignores(&path, false), Ok(None)));
+        assert!(load_project_ignores(&path, true).is_err());
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "ignore_regexes = [\"LogGameplayTags:\"]\n[[known_issues]]\npattern = \"LogAI:\"\nissue = \"CASTLE-99\"\n").unwrap();
+        let project_ignores = load_project_ignores(&path, false).unwrap().unwrap();
+        assert_eq!(project_ignores.ignore_regexes, ["LogGameplayTags:"]);
+        assert_eq!(project_ignores.known_issues[0].issue, "CASTLE-99");
+
+        fs::write(&path, "ignore_regexes = [\"LogAI: (\"]\n").unwrap();
+        assert!(load_project_ignores(&path, false).is_err());
+        fs::remove_dir_all(&project_directory).unwrap();
+    }
+}
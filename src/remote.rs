@@ -0,0 +1,280 @@
+use crate::runner::{Instance, TestRunner, TestSelection};
+use crate::TestConfiguration;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// with [remote] the path_to_* settings describe the remote machine, the engine is started there over ssh
+// and its report directory is copied back before parsing
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteConfiguration {
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub local_reports: Option<String>, // where the report is copied to, a temp directory by default
+    pub fetch_with: Option<String>,    // scp (default) or robocopy
+    pub reports_share: Option<String>, // UNC path of path_to_reports, needed by robocopy
+    pub shell: Option<RemoteShell>,    // cmd when path_to_unrealengine is a windows path, sh otherwise
+}
+
+// what ssh starts the command line with on the remote host, windows openssh uses cmd.exe by default
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteShell {
+    Sh,
+    Cmd,
+}
+
+impl RemoteConfiguration {
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    fn ssh_command(&self) -> Command {
+        let mut ssh_command = Command::new("ssh");
+        if let Some(port) = self.port {
+            ssh_command.args(["-p", port.to_string().as_str()]);
+        }
+        if let Some(identity_file) = &self.identity_file {
+            ssh_command.args(["-i", identity_file.as_str()]);
+        }
+        ssh_command
+    }
+
+    fn remote_shell(&self, config: &TestConfiguration) -> RemoteShell {
+        let engine = config.path_to_unrealengine.as_str();
+        self.shell.unwrap_or(if engine.contains('\\') || engine.as_bytes().get(1) == Some(&b':') { RemoteShell::Cmd } else { RemoteShell::Sh })
+    }
+
+    pub fn local_reports(&self) -> PathBuf {
+        match &self.local_reports {
+            Some(local_reports) => PathBuf::from(local_reports),
+            None => std::env::temp_dir().join(format!("runtests-remote-{}", self.host)),
+        }
+    }
+}
+
+// quotes an argument for the remote shell; sh expands nothing inside single quotes, cmd.exe leaves its
+// operators alone inside double quotes
+fn quote(arg: &str, shell: RemoteShell) -> String {
+    match shell {
+        RemoteShell::Sh => {
+            if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:@,+%".contains(c)) {
+                return arg.to_owned();
+            }
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+        RemoteShell::Cmd => {
+            if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "\"&|;<>()^".contains(c)) {
+                return arg.to_owned();
+            }
+            format!("\"{}\"", arg.replace('"', "\\\""))
+        }
+    }
+}
+
+pub struct RemoteRunner {
+    remote: RemoteConfiguration,
+    inner: Box<dyn TestRunner>,
+}
+
+impl RemoteRunner {
+    pub fn new(remote: &RemoteConfiguration, inner: Box<dyn TestRunner>) -> RemoteRunner {
+        RemoteRunner { remote: remote.clone(), inner }
+    }
+}
+
+impl TestRunner for RemoteRunner {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    // the local runner's command line, run on the remote host; ssh forwards the process's output as it happens
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command {
+        let local_command = self.inner.command(config, selection, instance);
+        let shell = self.remote.remote_shell(config);
+        let remote_command_line: Vec<String> = std::iter::once(local_command.get_program())
+            .chain(local_command.get_args())
+            .map(|arg| quote(arg.to_string_lossy().as_ref(), shell))
+            .collect();
+
+        let mut ssh_command = self.remote.ssh_command();
+        ssh_command.arg(self.remote.destination()).arg(remote_command_line.join(" "));
+        ssh_command
+    }
+
+    fn supports_filters(&self) -> bool {
+        self.inner.supports_filters()
+    }
+}
+
+// left in local_reports by a fetch, a directory without it is only cleared when it holds nothing but runtests' own files
+const FETCHED_MARKER: &str = ".runtests-fetched";
+// the lock, manifest and cache of the run live in local_reports too and outlast a fetch
+const OWN_FILES: &[&str] = &["runtests.lock", "run-manifest.json", "runtests-cache.json", FETCHED_MARKER];
+
+// a local_reports pointed at a directory with other files in it (a project's Saved folder, a home directory)
+// is refused instead of emptied
+fn clear_local_reports(local_reports: &Path) -> Result<(), String> {
+    let entries: Vec<PathBuf> = match fs::read_dir(local_reports) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(format!("failed to read {}: {}", local_reports.display(), error)),
+    };
+    let fetched = local_reports.join(FETCHED_MARKER).is_file();
+    let previous: Vec<&PathBuf> = entries.iter()
+        .filter(|path| !path.file_name().is_some_and(|name| OWN_FILES.iter().any(|own_file| name == *own_file)))
+        .collect();
+    if !fetched && !previous.is_empty() {
+        return Err(format!("{} holds files runtests didn't fetch, empty it or point local_reports somewhere else", local_reports.display()));
+    }
+    for path in previous {
+        let removed = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+            _ => fs::remove_file(path),
+        };
+        removed.map_err(|error| format!("failed to remove {}: {}", path.display(), error))?;
+    }
+    Ok(())
+}
+
+// copies the remote path_to_reports into local_reports (emptied of the previous fetch first) and returns the local directory
+pub fn fetch_reports(remote: &RemoteConfiguration, remote_reports: &str) -> Result<PathBuf, String> {
+    let local_reports = remote.local_reports();
+    clear_local_reports(&local_reports)?;
+    fs::create_dir_all(&local_reports).map_err(|error| format!("failed to create {}: {}", local_reports.display(), error))?;
+    fs::write(local_reports.join(FETCHED_MARKER), b"").map_err(|error| format!("failed to write to {}: {}", local_reports.display(), error))?;
+    eprintln!("fetching {}:{} to {}", remote.host, remote_reports, local_reports.display());
+
+    let status = match remote.fetch_with.as_deref().unwrap_or("scp") {
+        "robocopy" => {
+            let share = remote.reports_share.as_ref().ok_or_else(|| "fetch_with robocopy needs [remote] reports_share".to_owned())?;
+            let status = Command::new("robocopy").arg(share).arg(&local_reports).args(["/E", "/NFL", "/NDL", "/NJH", "/NJS"]).stdout(std::io::stderr()).status()
+                .map_err(|error| format!("failed to run robocopy: {}", error))?;
+            // robocopy's exit codes below 8 all mean the copy succeeded
+            return match status.code() {
+                Some(code) if code < 8 => Ok(local_reports),
+                _ => Err(format!("robocopy exited with {}", status)),
+            };
+        }
+        "scp" => {
+            let mut scp_command = Command::new("scp");
+            scp_command.arg("-r");
+            if let Some(port) = remote.port {
+                scp_command.args(["-P", port.to_string().as_str()]);
+            }
+            if let Some(identity_file) = &remote.identity_file {
+                scp_command.args(["-i", identity_file.as_str()]);
+            }
+            // the trailing /. copies the directory's contents rather than the directory itself
+            scp_command.arg(format!("{}:{}/.", remote.destination(), remote_reports.trim_end_matches(['/', '\\'])))
+                .arg(&local_reports)
+                .stdout(std::io::stderr())
+                .status()
+                .map_err(|error| format!("failed to run scp: {}", error))?
+        }
+        other => return Err(format!("unknown fetch_with {}, expected scp or robocopy", other)),
+    };
+    if !status.success() {
+        return Err(format!("scp exited with {}", status));
+    }
+    Ok(local_reports)
+}
+
+// runs a no-op over ssh, used by validate
+pub fn check_connection(remote: &RemoteConfiguration) -> Result<String, String> {
+    let output = remote.ssh_command().args(["-o", "BatchMode=yes"]).arg(remote.destination()).arg("echo ok").output()
+        .map_err(|error| format!("failed to run ssh: {}", error))?;
+    if output.status.success() {
+        Ok(remote.destination())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_quoted_for_cmd() {
+        let quote = |arg| quote(arg, RemoteShell::Cmd);
+        assert_eq!(quote("-unattended"), "-unattended");
+        assert_eq!(quote("-ExecCmds=Automation RunTests Project"), "\"-ExecCmds=Automation RunTests Project\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote("a>b"), "\"a>b\"");
+        assert_eq!(quote("(a)"), "\"(a)\"");
+        assert_eq!(quote(""), "\"\"");
+    }
+
+    #[test]
+    fn arguments_quoted_for_sh() {
+        let quote = |arg| quote(arg, RemoteShell::Sh);
+        assert_eq!(quote("-unattended"), "-unattended");
+        assert_eq!(quote("/home/ci/Castle/Castle.uproject"), "/home/ci/Castle/Castle.uproject");
+        assert_eq!(quote("-ExecCmds=Automation RunTests Project"), "'-ExecCmds=Automation RunTests Project'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+        assert_eq!(quote("`reboot`"), "'`reboot`'");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+        assert_eq!(quote("<in >out"), "'<in >out'");
+        assert_eq!(quote("$(reboot)"), "'$(reboot)'");
+        assert_eq!(quote("say \"hi\""), "'say \"hi\"'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn only_fetched_reports_are_cleared() {
+        let local_reports = std::env::temp_dir().join(format!("runtests-remote-clear-{}", std::process::id()));
+        fs::create_dir_all(local_reports.join("Reports")).unwrap();
+        fs::write(local_reports.join("run-manifest.json"), "{}").unwrap();
+        assert!(clear_local_reports(&local_reports).is_ok(), "only runtests' own files");
+        fs::write(local_reports.join("Reports").join("index.json"), "{}").unwrap();
+        fs::write(local_reports.join("notes.txt"), "").unwrap();
+        let refused = clear_local_reports(&local_reports);
+        let kept = local_reports.join("notes.txt").is_file();
+        fs::write(local_reports.join(FETCHED_MARKER), "").unwrap();
+        let cleared = clear_local_reports(&local_reports);
+        let mut left: Vec<String> = fs::read_dir(&local_reports).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        left.sort();
+        fs::remove_dir_all(&local_reports).unwrap();
+
+        assert!(refused.unwrap_err().contains("holds files runtests didn't fetch"));
+        assert!(kept);
+        assert!(cleared.is_ok());
+        assert_eq!(left, [FETCHED_MARKER, "run-manifest.json"]);
+        assert!(clear_local_reports(&local_reports).is_ok());
+    }
+
+    #[test]
+    fn local_command_runs_over_ssh() {
+        let config: TestConfiguration = toml::from_str(r#"
+            path_to_unrealengine = "C:/UE_5.3/Engine/Binaries/Win64/UnrealEditor.exe"
+            path_to_project = "C:/Castle/Castle.uproject"
+            path_to_reports = "C:/Castle/Saved/Reports"
+            run_tests = "Project"
+            test_exit = "Automation Test Queue Empty"
+            ignore_regexes = []
+            [remote]
+            host = "testbox"
+            user = "ci"
+            port = 2222
+            identity_file = "ci_ed25519""#).unwrap();
+        let remote = config.remote.as_ref().unwrap();
+        let remote_runner = crate::runner::runner(&config).unwrap();
+        let command = remote_runner.command(&config, &TestSelection::Tests("Project.Door".to_owned()), &Instance::primary(&config));
+
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(remote_runner.name(), "remote");
+        assert_eq!(command.get_program(), "ssh");
+        assert_eq!(args[..5], ["-p", "2222", "-i", "ci_ed25519", "ci@testbox"]);
+        assert_eq!(args.len(), 6);
+        assert!(args[5].starts_with("C:/UE_5.3/Engine/Binaries/Win64/UnrealEditor.exe C:/Castle/Castle.uproject \"-ExecCmds=Automation RunTests Project.Door\" -unattended"));
+        assert_eq!(remote.local_reports(), std::env::temp_dir().join("runtests-remote-testbox"));
+    }
+}
@@ -0,0 +1,649 @@
+use crate::{archive, http, intern, Artifact, Device, Entry, Test, TestPass};
+use colored::*;
+use serde::de::{DeserializeOwned, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// what deserialize_tests left out of the report being read
+#[derive(Default)]
+struct SchemaReading {
+    checking: bool, // each test is buffered so one that doesn't fit can be salvaged, see parse_test
+    strict: bool,   // keys the report schema doesn't have are schema warnings too
+    warnings: Vec<String>,
+    dropped_tests: Vec<(String, bool)>, // the state of each test left out and whether it logged warnings
+}
+
+thread_local! {
+    static SCHEMA_READING: RefCell<SchemaReading> = RefCell::new(SchemaReading::default());
+}
+
+// every key unreal writes, including the ones this tool doesn't read, so a strict read of a real report only
+// flags what's new
+const REPORT_KEYS: &[&str] = &[
+    "devices",
+    "reportCreatedOn",
+    "succeeded",
+    "succeededWithWarnings",
+    "failed",
+    "notRun",
+    "inProcess",
+    "totalDuration",
+    "comparisonExported",
+    "comparisonExportDirectory",
+    "tests",
+];
+const DEVICE_KEYS: &[&str] = &["deviceName", "instance", "platform", "oSVersion", "model", "gPU", "cPUModel", "rAMInGB", "renderMode", "rHI", "appInstanceLog"];
+const TEST_KEYS: &[&str] = &[
+    "testDisplayName",
+    "fullTestPath",
+    "artifactName",
+    "state",
+    "deviceInstance",
+    "duration",
+    "dateTime",
+    "entries",
+    "warnings",
+    "errors",
+    "artifacts",
+    "info",      // written by this tool, see testinfo::read_test_info
+    "signature", // written by this tool, see analysis::failure_signature
+];
+const ENTRY_KEYS: &[&str] = &["event", "filename", "lineNumber", "timestamp", "expected"];
+const EVENT_KEYS: &[&str] = &["type", "message", "context", "artifact"];
+const ARTIFACT_KEYS: &[&str] = &["id", "name", "type", "files"];
+
+// states the report's totals count tests under, a test left out with one of them is taken off its total
+const COUNTED_STATES: &[&str] = &["Success", "Fail", "NotRun", "InProcess"];
+
+fn uncount_test(test_pass: &mut TestPass, state: &str, logged_warnings: bool) {
+    let count = match state {
+        "Success" if logged_warnings => &mut test_pass.succeeded_with_warnings,
+        "Success" => &mut test_pass.succeeded,
+        "Fail" => &mut test_pass.failed,
+        "NotRun" => &mut test_pass.not_run,
+        "InProcess" => match test_pass.in_process.as_mut() {
+            Some(in_process) => in_process,
+            None => return,
+        },
+        _ => return,
+    };
+    *count = (*count - 1).max(0);
+}
+
+pub fn read_test_pass(report_file: &Path) -> Result<TestPass, String> {
+    read_report(report_file, false)
+}
+
+fn open_report(report_file: &Path) -> Result<BufReader<File>, String> {
+    let file = File::open(report_file).map_err(|error| format!("failed to open {}: {}", report_file.display(), error))?;
+    let mut reader = BufReader::with_capacity(1 << 16, file);
+    // unreal writes index.json with a utf-8 byte order mark, which serde_json rejects
+    if reader.fill_buf().is_ok_and(|buffer| buffer.starts_with(b"\xef\xbb\xbf")) {
+        reader.consume(3);
+    }
+    Ok(reader)
+}
+
+fn parse_report(reader: BufReader<File>, checking: bool, strict: bool) -> (Result<TestPass, serde_json::Error>, SchemaReading) {
+    SCHEMA_READING.with(|schema_reading| *schema_reading.borrow_mut() = SchemaReading { checking, strict, ..SchemaReading::default() });
+    let test_pass = if checking {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        deserializer.deserialize_map(ReportVisitor).and_then(|test_pass| deserializer.end().map(|_| test_pass))
+    } else {
+        serde_json::from_reader(reader)
+    };
+    intern::clear();
+    (test_pass, SCHEMA_READING.with(|schema_reading| schema_reading.take()))
+}
+
+// reports from large suites are tens of MB, they're read through a buffer instead of into one string
+// and the tests are deserialized one at a time, see deserialize_tests. A report that fits the schema is
+// parsed once, straight into the tests; one that doesn't is read again checking each field and test so what
+// doesn't fit is defaulted or left out with a schema warning. strict always checks, also flags keys the schema
+// doesn't have and fails the read on any schema warning, for trying a new engine version before relying on it
+pub fn read_report(report_file: &Path, strict: bool) -> Result<TestPass, String> {
+    let invalid_json = |error: serde_json::Error| format!("invalid json in {}: {}", report_file.display(), error);
+    if !strict {
+        match parse_report(open_report(report_file)?, false, false) {
+            (Ok(test_pass), _) => return Ok(test_pass),
+            (Err(error), _) if !error.is_data() => return Err(invalid_json(error)),
+            (Err(_), _) => {}
+        }
+    }
+    let (test_pass, schema_reading) = parse_report(open_report(report_file)?, true, strict);
+    let mut test_pass = test_pass.map_err(invalid_json)?;
+    if strict && !schema_reading.warnings.is_empty() {
+        return Err(format!("{} doesn't match the report schema:\n{}", report_file.display(), schema_reading.warnings.join("\n")));
+    }
+    for (state, logged_warnings) in schema_reading.dropped_tests.iter() {
+        uncount_test(&mut test_pass, state.as_str(), *logged_warnings);
+    }
+    test_pass.schema_warnings = schema_reading.warnings;
+    Ok(test_pass)
+}
+
+fn schema_warning(warning: String) {
+    SCHEMA_READING.with(|schema_reading| schema_reading.borrow_mut().warnings.push(warning));
+}
+
+fn unknown_key(key: &str, known_keys: &[&str], location: &str) {
+    if !known_keys.contains(&key) && SCHEMA_READING.with(|schema_reading| schema_reading.borrow().strict) {
+        schema_warning(format!("{}: unknown key {}", location, key));
+    }
+}
+
+fn unknown_keys(object: &serde_json::Value, known_keys: &[&str], location: &str) {
+    for key in object.as_object().into_iter().flat_map(|object| object.keys()) {
+        unknown_key(key, known_keys, location);
+    }
+}
+
+// an object that doesn't fit T keeps the fields that do, the rest are read as T's defaults; a missing
+// optional field is fine as it is
+fn lenient_object<T: Default + Serialize + DeserializeOwned>(mut fields: serde_json::Map<String, serde_json::Value>, location: &str) -> T {
+    let mut accepted = match serde_json::to_value(T::default()) {
+        Ok(accepted @ serde_json::Value::Object(_)) => accepted,
+        _ => return T::default(),
+    };
+    let keys: Vec<String> = accepted.as_object().into_iter().flat_map(|defaults| defaults.keys().cloned()).collect();
+    for key in keys {
+        let default = accepted[&key].clone();
+        match fields.remove(&key) {
+            None if default.is_null() => {}
+            None => schema_warning(format!("{}: missing {}, read as {}", location, key, default)),
+            Some(value) => {
+                accepted[&key] = value;
+                if let Err(error) = T::deserialize(&accepted) {
+                    schema_warning(format!("{}: {} read as {}, {}", location, key, default, error));
+                    accepted[&key] = default;
+                }
+            }
+        }
+    }
+    T::deserialize(&accepted).unwrap_or_default()
+}
+
+// a device that isn't an object is left out, one missing fields keeps the rest
+fn lenient_devices(devices: Option<serde_json::Value>) -> Option<Vec<Device>> {
+    let devices = match devices {
+        None | Some(serde_json::Value::Null) => return None,
+        Some(serde_json::Value::Array(devices)) => devices,
+        Some(devices) => {
+            schema_warning(format!("left out devices, expected an array, found {}", devices));
+            return None;
+        }
+    };
+    let mut lenient = Vec::with_capacity(devices.len());
+    for (index, device) in devices.into_iter().enumerate() {
+        let location = format!("devices[{}]", index);
+        unknown_keys(&device, DEVICE_KEYS, location.as_str());
+        match device {
+            serde_json::Value::Object(fields) => lenient.push(lenient_object(fields, location.as_str())),
+            device => schema_warning(format!("{}: left out the device, expected an object, found {}", location, device)),
+        }
+    }
+    Some(lenient)
+}
+
+// the tests stream through deserialize_tests as they would for a report that fits, the rest of the top
+// level is small enough to buffer and check field by field
+struct ReportVisitor;
+
+impl<'de> Visitor<'de> for ReportVisitor {
+    type Value = TestPass;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an automation report")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TestPass, A::Error> {
+        let mut fields = serde_json::Map::new();
+        let mut tests = None;
+        while let Some(key) = map.next_key::<String>()? {
+            unknown_key(key.as_str(), REPORT_KEYS, "report");
+            if key == "tests" {
+                tests = Some(map.next_value::<Tests>()?.0);
+                fields.insert(key, serde_json::Value::Array(Vec::new()));
+            } else {
+                fields.insert(key, map.next_value()?);
+            }
+        }
+        let devices = lenient_devices(fields.remove("devices"));
+        let mut test_pass: TestPass = lenient_object(fields, "report");
+        test_pass.devices = devices;
+        test_pass.tests = tests.unwrap_or_default();
+        Ok(test_pass)
+    }
+}
+
+struct Tests(Vec<Test>);
+
+impl<'de> Deserialize<'de> for Tests {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Tests, D::Error> {
+        deserialize_tests(deserializer).map(Tests)
+    }
+}
+
+// leaves out the elements of an array field that don't deserialize, ie an entry type this tool doesn't know
+fn drop_invalid<T: DeserializeOwned>(test: &mut serde_json::Value, field: &str, test_name: &str) {
+    if let Some(elements) = test.get_mut(field).and_then(|elements| elements.as_array_mut()) {
+        let mut index = 0;
+        elements.retain(|element| {
+            index += 1;
+            match T::deserialize(element) {
+                Ok(_) => true,
+                Err(error) => {
+                    schema_warning(format!("{}: left out {}[{}], {}", test_name, field, index - 1, error));
+                    false
+                }
+            }
+        });
+    }
+}
+
+fn unknown_test_keys(test: &serde_json::Value, test_name: &str) {
+    unknown_keys(test, TEST_KEYS, test_name);
+    for (index, entry) in test["entries"].as_array().into_iter().flatten().enumerate() {
+        unknown_keys(entry, ENTRY_KEYS, format!("{}: entries[{}]", test_name, index).as_str());
+        if let Some(event) = entry.get("event") {
+            unknown_keys(event, EVENT_KEYS, format!("{}: entries[{}].event", test_name, index).as_str());
+        }
+    }
+    for (index, artifact) in test["artifacts"].as_array().into_iter().flatten().enumerate() {
+        unknown_keys(artifact, ARTIFACT_KEYS, format!("{}: artifacts[{}]", test_name, index).as_str());
+    }
+}
+
+// a test that doesn't fit the schema keeps what does, one that still doesn't is left out of the report and
+// off its totals; only a test that didn't deserialize as it is goes through a serde_json::Value
+fn parse_test(raw_test: &RawValue, index: usize) -> Option<Test> {
+    let strict = SCHEMA_READING.with(|schema_reading| schema_reading.borrow().strict);
+    if !strict {
+        if let Ok(parsed) = serde_json::from_str::<Test>(raw_test.get()) {
+            return Some(parsed);
+        }
+    }
+    let mut test: serde_json::Value = match serde_json::from_str(raw_test.get()) {
+        Ok(test) => test,
+        Err(error) => {
+            schema_warning(format!("tests[{}]: left out the test, {}", index, error));
+            return None;
+        }
+    };
+    let test_name = test.get("fullTestPath").and_then(|path| path.as_str()).map_or_else(|| format!("tests[{}]", index), str::to_owned);
+    if strict {
+        unknown_test_keys(&test, test_name.as_str());
+    }
+    drop_invalid::<Entry>(&mut test, "entries", test_name.as_str());
+    drop_invalid::<Artifact>(&mut test, "artifacts", test_name.as_str());
+    match Test::deserialize(&test) {
+        Ok(parsed) => Some(parsed),
+        Err(error) => {
+            let state = test.get("state").and_then(|state| state.as_str()).unwrap_or_default();
+            if COUNTED_STATES.contains(&state) {
+                let logged_warnings = test.get("warnings").and_then(|warnings| warnings.as_i64()).is_some_and(|warnings| warnings > 0);
+                SCHEMA_READING.with(|schema_reading| schema_reading.borrow_mut().dropped_tests.push((state.to_owned(), logged_warnings)));
+                schema_warning(format!("{}: left out the test, {}", test_name, error));
+            } else {
+                schema_warning(format!("{}: left out the test, {}; the report's totals still count it", test_name, error));
+            }
+            None
+        }
+    }
+}
+
+struct TestsVisitor;
+
+impl<'de> Visitor<'de> for TestsVisitor {
+    type Value = Vec<Test>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of tests")
+    }
+
+    // each test is trimmed to its final size as it arrives, so the spare capacity serde leaves behind in every
+    // entry list doesn't add up across thousands of tests; only a checking read buffers each test's json first
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Test>, A::Error> {
+        let mut tests = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        let checking = SCHEMA_READING.with(|schema_reading| schema_reading.borrow().checking);
+        let mut index = 0;
+        loop {
+            let test = if checking {
+                match seq.next_element::<Box<RawValue>>()? {
+                    Some(raw_test) => parse_test(&raw_test, index),
+                    None => break,
+                }
+            } else {
+                match seq.next_element::<Test>()? {
+                    Some(test) => Some(test),
+                    None => break,
+                }
+            };
+            if let Some(mut test) = test {
+                test.entries.shrink_to_fit();
+                test.artifacts.shrink_to_fit();
+                tests.push(test);
+            }
+            index += 1;
+        }
+        tests.shrink_to_fit();
+        Ok(tests)
+    }
+}
+
+pub fn deserialize_tests<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Test>, D::Error> {
+    deserializer.deserialize_seq(TestsVisitor)
+}
+
+fn collect_index_files(directory: &Path, found: &mut Vec<(SystemTime, PathBuf)>) {
+    let read_dir = match fs::read_dir(directory) {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+    for dir_entry in read_dir.flatten() {
+        let path = dir_entry.path();
+        if path.is_dir() {
+            collect_index_files(&path, found);
+        } else if path.file_name().is_some_and(|name| name == "index.json") {
+            let modified = dir_entry.metadata().and_then(|metadata| metadata.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((modified, path));
+        }
+    }
+}
+
+// every index.json under path_to_reports with when it was written, in no particular order
+pub fn report_files(path_to_reports: &str) -> Vec<(SystemTime, PathBuf)> {
+    let mut found = Vec::new();
+    collect_index_files(Path::new(path_to_reports), &mut found);
+    found
+}
+
+// unreal sometimes nests the report by platform/session, so when index.json isn't directly
+// in the report directory the most recently written one underneath it is used instead
+pub fn find_report_file(path_to_reports: &str) -> Option<PathBuf> {
+    let direct = Path::new(path_to_reports).join("index.json");
+    if direct.is_file() {
+        return Some(direct);
+    }
+
+    let mut found = Vec::new();
+    collect_index_files(Path::new(path_to_reports), &mut found);
+    found.into_iter().max_by_key(|(modified, _)| *modified).map(|(_, path)| path)
+}
+
+pub fn written_since(path: &Path, since: chrono::DateTime<chrono::Utc>) -> bool {
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => chrono::DateTime::<chrono::Utc>::from(modified) >= since,
+        Err(_) => false,
+    }
+}
+
+fn extract_archive(archive_path: &Path) -> Result<PathBuf, String> {
+    let file = File::open(archive_path).map_err(|error| format!("failed to open {}: {}", archive_path.display(), error))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|error| format!("invalid archive {}: {}", archive_path.display(), error))?;
+
+    let stem = archive_path.file_stem().map_or_else(|| "report".into(), |stem| stem.to_string_lossy());
+    let destination = std::env::temp_dir().join(format!("runtests-{}-{}", stem, std::process::id()));
+    archive.extract(&destination).map_err(|error| format!("failed to extract {}: {}", archive_path.display(), error))?;
+    eprintln!("extracted {} to {}", archive_path.display(), destination.display());
+
+    // archives written by --archive carry a manifest, zips from elsewhere usually don't
+    if destination.join("manifest.json").is_file() {
+        match archive::verify_extracted(&destination) {
+            Ok(problems) if problems.is_empty() => eprintln!("verified archive against its manifest"),
+            Ok(problems) => {
+                eprintln!("{}", format!("{} archived files are missing or corrupted:", problems.len()).red());
+                for problem in problems.iter() {
+                    eprintln!("    {}", problem.red());
+                }
+            }
+            Err(error) => eprintln!("{}{}", "could not verify archive: ".yellow(), error),
+        }
+    }
+    Ok(destination)
+}
+
+// accepts whatever CI archived: a report directory, an index.json, a gauntlet output folder
+// (the report is nested a few levels down) or a zipped copy of any of those
+pub fn resolve_report_input(input: &str) -> Result<PathBuf, String> {
+    let path = Path::new(input);
+    if path.is_dir() {
+        return find_report_file(input).ok_or_else(|| format!("no index.json found under {}", input));
+    }
+    if !path.is_file() {
+        return Err(format!("{} does not exist", input));
+    }
+
+    let is_zip = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+    if is_zip {
+        let extracted = extract_archive(path)?;
+        return find_report_file(extracted.to_str().expect("invalid temp path")).ok_or_else(|| format!("no index.json found in {}", input));
+    }
+    Ok(path.to_path_buf())
+}
+
+fn is_url(input: &str) -> bool {
+    let lowercase = input.to_ascii_lowercase();
+    lowercase.starts_with("http://") || lowercase.starts_with("https://")
+}
+
+fn input_directory(name: &str) -> Result<PathBuf, String> {
+    let directory = std::env::temp_dir().join(format!("runtests-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&directory).map_err(|error| format!("failed to create {}: {}", directory.display(), error))?;
+    Ok(directory)
+}
+
+// a piped report only has index.json, per-test json and screenshots next to it aren't there to be read
+fn save_piped_report(reader: &mut dyn Read, directory: &Path) -> Result<PathBuf, String> {
+    let report_file = directory.join("index.json");
+    let mut file = File::create(&report_file).map_err(|error| format!("failed to create {}: {}", report_file.display(), error))?;
+    io::copy(reader, &mut file).map_err(|error| format!("failed to read the report from stdin: {}", error))?;
+    Ok(report_file)
+}
+
+// - reads the report from stdin and an http(s) url downloads an index.json or a zipped report, with headers for
+// artifact servers that need a token; None for anything else, which resolve_report_input takes
+pub fn fetch_report_input(input: &str, headers: &[(&str, &str)]) -> Option<Result<PathBuf, String>> {
+    if input == "-" {
+        return Some(input_directory("stdin").and_then(|directory| save_piped_report(&mut io::stdin().lock(), &directory)));
+    }
+    if !is_url(input) {
+        return None;
+    }
+    let fetched = input_directory("download").and_then(|directory| {
+        let is_zip = input.split(['?', '#']).next().unwrap_or_default().to_ascii_lowercase().ends_with(".zip");
+        let download = directory.join(if is_zip { "report.zip" } else { "index.json" });
+        eprintln!("downloading {}", input);
+        http::download(input, headers, &download)?;
+        resolve_report_input(download.to_str().expect("invalid temp path"))
+    });
+    Some(fetched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("runtests-report-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn write_report(path: &Path, seconds: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "{}").unwrap();
+        fs::File::options().write(true).open(path).unwrap().set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+    }
+
+    #[test]
+    fn index_json_directly_in_the_reports() {
+        let directory = temp_directory("direct");
+        write_report(&directory.join("index.json"), 1);
+        write_report(&directory.join("Win64").join("index.json"), 2);
+        let found = find_report_file(directory.to_str().unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(found, Some(directory.join("index.json")));
+    }
+
+    #[test]
+    fn newest_nested_index_json() {
+        let directory = temp_directory("nested");
+        write_report(&directory.join("Win64").join("index.json"), 2);
+        write_report(&directory.join("Linux").join("Session").join("index.json"), 3);
+        write_report(&directory.join("Android").join("index.json"), 1);
+        let found = find_report_file(directory.to_str().unwrap());
+        let empty = temp_directory("empty");
+        let none = find_report_file(empty.to_str().unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+        fs::remove_dir_all(&empty).unwrap();
+        assert_eq!(found, Some(directory.join("Linux").join("Session").join("index.json")));
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn report_input_directory_file_or_missing() {
+        let directory = temp_directory("input");
+        write_report(&directory.join("Win64").join("index.json"), 1);
+        let input = directory.to_str().unwrap().to_owned();
+        let from_directory = resolve_report_input(input.as_str());
+        let index_json = directory.join("Win64").join("index.json");
+        let from_file = resolve_report_input(index_json.to_str().unwrap());
+        let missing = resolve_report_input(directory.join("missing").to_str().unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(from_directory, Ok(index_json.clone()));
+        assert_eq!(from_file, Ok(index_json));
+        assert!(missing.unwrap_err().ends_with("does not exist"));
+    }
+
+    #[test]
+    fn piped_and_downloaded_inputs() {
+        let directory = temp_directory("piped");
+        let saved = save_piped_report(&mut &b"{}"[..], &directory);
+        let contents = fs::read_to_string(directory.join("index.json"));
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(saved, Ok(directory.join("index.json")));
+        assert_eq!(contents.unwrap(), "{}");
+        assert!(is_url("HTTPS://ci.example.com/artifacts/index.json"));
+        assert!(!is_url("Saved/Automation/Reports"));
+        assert!(fetch_report_input("Saved/Automation/Reports", &[]).is_none());
+    }
+
+    #[test]
+    fn report_input_zip_is_extracted() {
+        let directory = temp_directory("zip");
+        let archive_path = directory.join("CastleReport.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        writer.start_file("Reports/Win64/index.json", zip::write::FileOptions::default()).unwrap();
+        std::io::Write::write_all(&mut writer, b"{}").unwrap();
+        writer.finish().unwrap();
+
+        let found = resolve_report_input(archive_path.to_str().unwrap()).unwrap();
+        let contents = fs::read_to_string(&found).unwrap();
+        fs::remove_dir_all(found.ancestors().nth(3).unwrap()).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(found.ends_with("Reports/Win64/index.json"));
+        assert_eq!(contents, "{}");
+    }
+
+    #[test]
+    fn report_with_byte_order_mark() {
+        let directory = temp_directory("bom");
+        let path = directory.join("index.json");
+        let json = r#"{"devices": null, "reportCreatedOn": "", "succeeded": 1, "succeededWithWarnings": 0, "failed": 0,
+            "notRun": 0, "inProcess": null, "totalDuration": 0, "comparisonExported": false, "comparisonExportDirectory": "",
+            "tests": [{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Success", "entries": [],
+            "warnings": 0, "errors": 0, "artifacts": []}]}"#;
+        fs::write(&path, [b"\xef\xbb\xbf".as_slice(), json.as_bytes()].concat()).unwrap();
+        let test_pass = read_test_pass(&path);
+        fs::write(&path, "{").unwrap();
+        let truncated = read_test_pass(&path);
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(test_pass.unwrap().tests[0].full_test_path, "Project.Door");
+        assert!(truncated.unwrap_err().starts_with("invalid json in "));
+    }
+
+    #[test]
+    fn schema_anomalies_are_left_out_unless_strict() {
+        let directory = temp_directory("schema");
+        let path = directory.join("index.json");
+        let json = r#"{"devices": null, "reportCreatedOn": "", "succeeded": 1, "succeededWithWarnings": 0, "failed": 1,
+            "notRun": 1, "inProcess": null, "totalDuration": 0, "comparisonExported": false, "comparisonExportDirectory": "",
+            "tests": [{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Success", "warnings": 0, "errors": 0, "artifacts": [], "entries": [
+                    {"event": {"type": "Info", "message": "opened", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""},
+                    {"event": {"type": "Display", "message": "hinge", "context": "", "artifact": ""}, "filename": "", "lineNumber": 0, "timestamp": ""}]},
+                {"testDisplayName": "", "fullTestPath": "Project.Gate", "state": "Skipped", "warnings": 0, "errors": 0, "artifacts": [], "entries": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Wall", "state": "Fail", "warnings": 0, "errors": "one", "artifacts": [], "entries": []}]}"#;
+        fs::write(&path, json).unwrap();
+        let lenient = read_report(&path, false);
+        let strict = read_report(&path, true);
+        fs::remove_dir_all(&directory).unwrap();
+
+        let test_pass = lenient.unwrap();
+        assert_eq!(test_pass.tests.len(), 1);
+        assert_eq!(test_pass.tests[0].entries.len(), 1);
+        assert_eq!(test_pass.schema_warnings.len(), 3);
+        assert!(test_pass.schema_warnings[0].starts_with("Project.Door: left out entries[1], unknown variant `Display`"));
+        assert!(test_pass.schema_warnings[1].starts_with("Project.Gate: left out the test, unknown variant `Skipped`"));
+        assert!(test_pass.schema_warnings[1].ends_with("the report's totals still count it"));
+        assert!(test_pass.schema_warnings[2].starts_with("Project.Wall: left out the test, invalid type"));
+        assert_eq!((test_pass.succeeded, test_pass.failed, test_pass.not_run), (1, 0, 1));
+        assert!(strict.unwrap_err().contains("doesn't match the report schema"));
+    }
+
+    #[test]
+    fn top_level_and_device_fields_are_defaulted_unless_strict() {
+        let directory = temp_directory("fields");
+        let path = directory.join("index.json");
+        let json = r#"{"devices": [{"deviceName": "PC", "instance": "PC-1", "platform": "Windows", "oSVersion": "11", "cPUModel": "",
+                "rAMInGB": 32, "renderMode": "", "rHI": "D3D12"}, 5],
+            "reportCreatedOn": "", "succeeded": 1, "succeededWithWarnings": 0, "failed": 0, "notRun": 0, "inProcess": "two",
+            "totalDuration": 0, "comparisonExportDirectory": "", "buildVersion": "5.4",
+            "tests": [{"testDisplayName": "", "fullTestPath": "Project.Door", "state": "Success", "warnings": 0, "errors": 0,
+                "artifacts": [], "entries": [], "tags": []}]}"#;
+        fs::write(&path, json).unwrap();
+        let lenient = read_report(&path, false);
+        let strict = read_report(&path, true);
+        fs::remove_dir_all(&directory).unwrap();
+
+        let test_pass = lenient.unwrap();
+        assert_eq!(test_pass.tests.len(), 1);
+        assert_eq!(test_pass.in_process, None);
+        assert!(!test_pass.comparison_exported);
+        let devices = test_pass.devices.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!((devices[0].rhi.as_str(), devices[0].model.as_str()), ("D3D12", ""));
+        let warnings = test_pass.schema_warnings;
+        assert_eq!(warnings.len(), 5);
+        assert!(warnings.contains(&"devices[0]: missing model, read as \"\"".to_owned()));
+        assert!(warnings.contains(&"devices[0]: missing gPU, read as \"\"".to_owned()));
+        assert!(warnings.contains(&"devices[1]: left out the device, expected an object, found 5".to_owned()));
+        assert!(warnings.contains(&"report: missing comparisonExported, read as false".to_owned()));
+        assert!(warnings.iter().any(|warning| warning.starts_with("report: inProcess read as null, invalid type")));
+
+        let strict = strict.unwrap_err();
+        assert!(strict.contains("report: unknown key buildVersion"));
+        assert!(strict.contains("Project.Door: unknown key tags"));
+        assert!(strict.contains("report: missing comparisonExported"));
+    }
+
+    #[test]
+    fn report_written_since_the_run_started() {
+        let path = std::env::temp_dir().join(format!("runtests-written-since-{}.json", std::process::id()));
+        let started_at = chrono::Utc::now() - chrono::Duration::seconds(5);
+        fs::write(&path, "{}").unwrap();
+        let written = written_since(&path, started_at);
+        let stale = written_since(&path, chrono::Utc::now() + chrono::Duration::hours(1));
+        fs::remove_file(&path).unwrap();
+        assert!(written);
+        assert!(!stale);
+        assert!(!written_since(&path, started_at));
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+// 3, 1-4 and 2,5 select tests by their number in the list (1-based)
+fn parse_selection(input: &str, count: usize) -> Result<BTreeSet<usize>, String> {
+    let mut selected = BTreeSet::new();
+    for part in input.split([',', ' ']).map(str::trim).filter(|part| !part.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (first.trim(), last.trim()),
+            None => (part, part),
+        };
+        let number = |text: &str| text.parse::<usize>().ok().filter(|number| (1..=count).contains(number)).ok_or_else(|| format!("{} isn't between 1 and {}", text, count));
+        let (first, last) = (number(first)?, number(last)?);
+        selected.extend((first.min(last)..=first.max(last)).map(|number| number - 1));
+    }
+    Ok(selected)
+}
+
+fn print_list(out: &mut dyn Write, tests: &[String], selected: &BTreeSet<usize>) -> io::Result<()> {
+    for (index, test) in tests.iter().enumerate() {
+        let check = if selected.contains(&index) { "x" } else { " " };
+        writeln!(out, "{:>4} [{}] {}", index + 1, check, test)?;
+    }
+    write!(out, "numbers or ranges toggle tests, a selects all, n none, enter runs the checked tests, q quits: ")?;
+    out.flush()
+}
+
+// every test starts checked, None when the user quits; line based so it works in any terminal
+pub fn pick(tests: &[String], input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<Option<Vec<String>>> {
+    let mut selected: BTreeSet<usize> = (0..tests.len()).collect();
+    loop {
+        print_list(out, tests, &selected)?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim() {
+            "" => return Ok(Some(selected.iter().map(|index| tests[*index].clone()).collect())),
+            "q" => return Ok(None),
+            "a" => selected = (0..tests.len()).collect(),
+            "n" => selected.clear(),
+            toggles => match parse_selection(toggles, tests.len()) {
+                Ok(toggled) => selected = selected.symmetric_difference(&toggled).copied().collect(),
+                Err(error) => writeln!(out, "{}", error)?,
+            },
+        }
+        writeln!(out)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_and_ranges() {
+        assert_eq!(parse_selection("1, 3-4", 5), Ok([0, 2, 3].iter().copied().collect()));
+        assert_eq!(parse_selection("4-2", 5), Ok([1, 2, 3].iter().copied().collect()));
+        assert!(parse_selection("6", 5).is_err());
+        assert!(parse_selection("x", 5).is_err());
+    }
+
+    #[test]
+    fn toggled_tests_are_left_out() {
+        let tests = vec!["Project.Door.Opens".to_owned(), "Project.Door.Closes".to_owned(), "Project.Gate.Lifts".to_owned()];
+        let mut out = Vec::new();
+        let picked = pick(&tests, &mut "2\n\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(picked, Some(vec!["Project.Door.Opens".to_owned(), "Project.Gate.Lifts".to_owned()]));
+        assert!(String::from_utf8(out).unwrap().contains("   2 [ ] Project.Door.Closes"));
+        assert_eq!(pick(&tests, &mut "n\n3\nq\n".as_bytes(), &mut Vec::new()).unwrap(), None);
+    }
+}
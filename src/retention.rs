@@ -0,0 +1,106 @@
+use crate::{datetime, history, report};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// with [retention] old report directories under path_to_reports and old history runs are deleted after every
+// run (and by the prune subcommand); a run is kept while it's one of the newest keep_runs or younger than keep_days
+#[derive(Debug, Deserialize)]
+pub struct RetentionConfiguration {
+    pub keep_runs: Option<usize>,
+    pub keep_days: Option<u32>,
+}
+
+fn keep(config: &RetentionConfiguration, newest_first_index: usize, written: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    let by_count = config.keep_runs.map(|keep_runs| newest_first_index < keep_runs);
+    // a run whose age can't be told is never old enough to delete by age
+    let by_age = config.keep_days.map(|keep_days| written.map_or(true, |written| now - written < chrono::Duration::days(keep_days as i64)));
+    match (by_count, by_age) {
+        (None, None) => true,
+        (Some(keep), None) | (None, Some(keep)) => keep,
+        (Some(by_count), Some(by_age)) => by_count || by_age,
+    }
+}
+
+// the directories of old reports nested under path_to_reports; a report written directly into path_to_reports
+// is the current one and the directory itself is never deleted, nor is anything the current run wrote
+// (the merged report of --jobs points into the instance directories)
+pub fn prune_reports(path_to_reports: &str, config: &RetentionConfiguration, now: DateTime<Utc>, run_started_at: Option<DateTime<Utc>>, dry_run: bool) -> Vec<PathBuf> {
+    let root = Path::new(path_to_reports);
+    let mut reports = report::report_files(path_to_reports);
+    reports.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut pruned = Vec::new();
+    for (index, (modified, report_file)) in reports.into_iter().enumerate() {
+        let directory = match report_file.parent() {
+            Some(directory) if directory != root => directory.to_path_buf(),
+            _ => continue,
+        };
+        let modified = DateTime::<Utc>::from(modified);
+        if run_started_at.is_some_and(|run_started_at| modified >= run_started_at) || keep(config, index, Some(modified), now) {
+            continue;
+        }
+        if dry_run || fs::remove_dir_all(&directory).is_ok() {
+            pruned.push(directory);
+        }
+    }
+    pruned
+}
+
+// the number of runs dropped from the history file
+pub fn prune_history(history_file: &Path, config: &RetentionConfiguration, now: DateTime<Utc>, dry_run: bool) -> std::io::Result<usize> {
+    let runs = history::load_history(history_file);
+    let run_count = runs.len();
+    let mut kept: Vec<history::HistoryRun> = runs.into_iter().rev().enumerate()
+        .filter(|(index, run)| keep(config, *index, datetime::parse_timestamp(run.report_created_on.as_str()), now))
+        .map(|(_, run)| run)
+        .collect();
+    kept.reverse();
+    let pruned = run_count - kept.len();
+    if pruned > 0 && !dry_run {
+        history::write_history(history_file, &kept)?;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_runs_or_recent_days_are_kept() {
+        let now = Utc::now();
+        let days_ago = |days: i64| Some(now - chrono::Duration::days(days));
+        let runs = RetentionConfiguration { keep_runs: Some(2), keep_days: None };
+        assert!(keep(&runs, 1, days_ago(100), now));
+        assert!(!keep(&runs, 2, days_ago(0), now));
+        let days = RetentionConfiguration { keep_runs: None, keep_days: Some(7) };
+        assert!(keep(&days, 50, days_ago(6), now));
+        assert!(!keep(&days, 0, days_ago(8), now));
+        assert!(keep(&days, 50, None, now));
+        let both = RetentionConfiguration { keep_runs: Some(2), keep_days: Some(7) };
+        assert!(keep(&both, 5, days_ago(1), now));
+        assert!(keep(&both, 0, days_ago(30), now));
+        assert!(!keep(&both, 5, days_ago(30), now));
+    }
+
+    #[test]
+    fn old_nested_reports_are_deleted() {
+        let path_to_reports = std::env::temp_dir().join(format!("runtests-retention-{}", std::process::id()));
+        for (name, age_days) in [("Old", 30), ("Recent", 1)] {
+            let report_file = path_to_reports.join(name).join("index.json");
+            fs::create_dir_all(report_file.parent().unwrap()).unwrap();
+            fs::write(&report_file, "{}").unwrap();
+            let modified = std::time::SystemTime::now() - std::time::Duration::from_secs(age_days * 24 * 3600);
+            fs::File::options().write(true).open(&report_file).unwrap().set_modified(modified).unwrap();
+        }
+        fs::write(path_to_reports.join("index.json"), "{}").unwrap();
+        let config = RetentionConfiguration { keep_runs: None, keep_days: Some(7) };
+
+        let pruned = prune_reports(path_to_reports.to_str().unwrap(), &config, Utc::now(), None, false);
+        let remaining = (path_to_reports.join("Old").exists(), path_to_reports.join("Recent").exists(), path_to_reports.join("index.json").exists());
+        fs::remove_dir_all(&path_to_reports).unwrap();
+        assert_eq!(pruned, [path_to_reports.join("Old")]);
+        assert_eq!(remaining, (false, true, true));
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{display, remote, TestConfiguration};
+use serde::Deserialize;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunnerKind {
+    #[default]
+    Editor,    // path_to_unrealengine with -game, the default
+    EditorCmd, // UnrealEditor-Cmd next to path_to_unrealengine, no window and the log on stdout
+    Packaged,  // a cooked build's executable
+    Gauntlet,  // RunUAT RunUnreal, gauntlet launches and monitors the process itself
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RunnerConfiguration {
+    #[serde(default)]
+    pub kind: RunnerKind,
+    pub executable: Option<String>,  // required for packaged, overrides the derived UnrealEditor-Cmd path for editor-cmd
+    pub path_to_uat: Option<String>, // RunUAT.bat, required for gauntlet
+    pub platform: Option<String>,
+    pub configuration: Option<String>,
+    pub build: Option<String>, // gauntlet's -build, defaults to editor
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+// Unreal's automation test flags, as accepted by --filter
+pub const FILTER_NAMES: &[&str] = &["smoke", "engine", "product", "perf", "stress", "negative"];
+
+pub fn unreal_filter(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "smoke" => Some("Smoke"),
+        "engine" => Some("Engine"),
+        "product" => Some("Product"),
+        "perf" => Some("Perf"),
+        "stress" => Some("Stress"),
+        "negative" => Some("Negative"),
+        _ => None,
+    }
+}
+
+// what the engine is asked to run, explicit test names (or prefixes) or every test carrying a flag
+pub enum TestSelection {
+    Tests(String),
+    Filter(&'static str),
+}
+
+impl TestSelection {
+    fn exec_command(&self) -> String {
+        match self {
+            TestSelection::Tests(run_tests) => format!("Automation RunTests {}", run_tests),
+            TestSelection::Filter(filter) => format!("Automation RunFilter {}", filter),
+        }
+    }
+}
+
+impl std::fmt::Display for TestSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TestSelection::Tests(run_tests) => write!(f, "{}", run_tests),
+            TestSelection::Filter(filter) => write!(f, "{} filter", filter),
+        }
+    }
+}
+
+// where one engine process writes its report and log, a plain run is a single primary instance
+pub struct Instance {
+    pub report_directory: String,
+    pub log_name: String,
+}
+
+impl Instance {
+    pub fn primary(config: &TestConfiguration) -> Instance {
+        Instance { report_directory: config.path_to_reports.clone(), log_name: "runtests.log".to_owned() }
+    }
+
+    // parallel or isolated processes each get their own report directory and log so they can't overwrite each other
+    pub fn numbered(config: &TestConfiguration, index: usize) -> Instance {
+        Instance {
+            report_directory: Path::new(config.path_to_reports.as_str()).join(format!("instance-{}", index)).display().to_string(),
+            log_name: format!("runtests-{}.log", index),
+        }
+    }
+
+    // kept out of path_to_reports so a throwaway warmup report can never be mistaken for the measured one
+    pub fn warmup(config: &TestConfiguration) -> Instance {
+        Instance {
+            report_directory: crate::project_directory(config).join("Saved").join("AutomationWarmup").display().to_string(),
+            log_name: "runtests-warmup.log".to_owned(),
+        }
+    }
+}
+
+// a way of launching the engine so it runs the automation tests and writes index.json under path_to_reports,
+// everything after the process exits (finding, parsing and reporting) is shared between runners
+pub trait TestRunner {
+    fn name(&self) -> &'static str;
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command;
+
+    fn supports_filters(&self) -> bool {
+        true
+    }
+}
+
+// the engine splits -ExecCmds at commas and runs the commands in order
+pub fn exec_cmds(config: &TestConfiguration, selection: &TestSelection) -> String {
+    let exec_cmds: Vec<String> = config.pre_exec_cmds.iter().cloned()
+        .chain(std::iter::once(selection.exec_command()))
+        .chain(config.post_exec_cmds.iter().cloned())
+        .collect();
+    exec_cmds.join(",")
+}
+
+// the arguments every process that runs the tests in-engine needs
+fn automation_args(config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Vec<String> {
+    vec![
+        format!("-ExecCmds={}", exec_cmds(config, selection)),
+        "-unattended".to_owned(),
+        "-nopause".to_owned(),
+        format!("-testexit={}", config.test_exit),
+        format!("-log={}", instance.log_name),
+        format!("-ReportOutputPath={}", instance.report_directory),
+    ]
+}
+
+pub struct EditorRunner {
+    extra_args: Vec<String>,
+}
+
+impl TestRunner for EditorRunner {
+    fn name(&self) -> &'static str {
+        "editor"
+    }
+
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command {
+        let mut command = Command::new(config.path_to_unrealengine.as_str());
+        command.arg(config.path_to_project.as_str())
+            .args(config.startup_map.iter())
+            .args(automation_args(config, selection, instance))
+            .arg("-game")
+            .args(display::display_args(config.display.as_ref(), selection, &self.extra_args, true))
+            .args(&self.extra_args);
+        command
+    }
+}
+
+pub struct EditorCmdRunner {
+    executable: String,
+    extra_args: Vec<String>,
+}
+
+impl TestRunner for EditorCmdRunner {
+    fn name(&self) -> &'static str {
+        "editor-cmd"
+    }
+
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command {
+        let mut command = Command::new(self.executable.as_str());
+        command.arg(config.path_to_project.as_str())
+            .args(config.startup_map.iter())
+            .args(automation_args(config, selection, instance))
+            .args(display::display_args(config.display.as_ref(), selection, &self.extra_args, true))
+            .args(["-stdout", "-FullStdOutLogOutput"])
+            .args(&self.extra_args);
+        command
+    }
+}
+
+pub struct PackagedRunner {
+    executable: String,
+    extra_args: Vec<String>,
+}
+
+impl TestRunner for PackagedRunner {
+    fn name(&self) -> &'static str {
+        "packaged"
+    }
+
+    // a cooked build already knows its project, so no .uproject argument
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command {
+        let mut command = Command::new(self.executable.as_str());
+        command.args(config.startup_map.iter())
+            .args(automation_args(config, selection, instance))
+            .args(display::display_args(config.display.as_ref(), selection, &self.extra_args, false))
+            .args(&self.extra_args);
+        command
+    }
+}
+
+pub struct GauntletRunner {
+    path_to_uat: String,
+    platform: String,
+    configuration: String,
+    build: String,
+    extra_args: Vec<String>,
+}
+
+impl TestRunner for GauntletRunner {
+    fn name(&self) -> &'static str {
+        "gauntlet"
+    }
+
+    // RunUnreal only takes test names through -runtest
+    fn supports_filters(&self) -> bool {
+        false
+    }
+
+    fn command(&self, config: &TestConfiguration, selection: &TestSelection, instance: &Instance) -> Command {
+        let mut command = Command::new(self.path_to_uat.as_str());
+        command.args([
+            "RunUnreal".to_owned(),
+            format!("-project={}", config.path_to_project),
+            format!("-platform={}", self.platform),
+            format!("-configuration={}", self.configuration),
+            format!("-build={}", self.build),
+            "-test=UE.EditorAutomation".to_owned(),
+            format!("-runtest={}", selection),
+            format!("-ReportExportPath={}", instance.report_directory),
+            "-unattended".to_owned(),
+        ])
+        .args(config.startup_map.iter().map(|startup_map| format!("-map={}", startup_map)))
+        .args(&self.extra_args);
+        command
+    }
+}
+
+// UnrealEditor.exe -> UnrealEditor-Cmd.exe in the same directory
+fn editor_cmd_path(path_to_unrealengine: &str) -> String {
+    let path = Path::new(path_to_unrealengine);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("UnrealEditor");
+    let file_name = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}-Cmd.{}", stem, extension),
+        None => format!("{}-Cmd", stem),
+    };
+    path.with_file_name(file_name).display().to_string()
+}
+
+pub fn runner(config: &TestConfiguration) -> Result<Box<dyn TestRunner>, String> {
+    let test_runner = local_runner(config)?;
+    match &config.remote {
+        Some(remote_config) => Ok(Box::new(remote::RemoteRunner::new(remote_config, test_runner))),
+        None => Ok(test_runner),
+    }
+}
+
+fn local_runner(config: &TestConfiguration) -> Result<Box<dyn TestRunner>, String> {
+    let default_config = RunnerConfiguration::default();
+    let runner_config = config.runner.as_ref().unwrap_or(&default_config);
+    let extra_args = runner_config.extra_args.clone();
+    match runner_config.kind {
+        RunnerKind::Editor => Ok(Box::new(EditorRunner { extra_args })),
+        RunnerKind::EditorCmd => Ok(Box::new(EditorCmdRunner {
+            executable: runner_config.executable.clone().unwrap_or_else(|| editor_cmd_path(config.path_to_unrealengine.as_str())),
+            extra_args,
+        })),
+        RunnerKind::Packaged => match &runner_config.executable {
+            Some(executable) => Ok(Box::new(PackagedRunner { executable: executable.clone(), extra_args })),
+            None => Err("the packaged runner needs [runner] executable".to_owned()),
+        },
+        RunnerKind::Gauntlet => match &runner_config.path_to_uat {
+            Some(path_to_uat) => Ok(Box::new(GauntletRunner {
+                path_to_uat: path_to_uat.clone(),
+                platform: runner_config.platform.clone().unwrap_or_else(|| "Win64".to_owned()),
+                configuration: runner_config.configuration.clone().unwrap_or_else(|| "Development".to_owned()),
+                build: runner_config.build.clone().unwrap_or_else(|| "editor".to_owned()),
+                extra_args,
+            })),
+            None => Err("the gauntlet runner needs [runner] path_to_uat".to_owned()),
+        },
+    }
+}
+
+// a command line the user wrote, ie a sync command or a hook, run through the platform's shell
+pub fn shell_command(command_line: &str) -> Command {
+    let mut command = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    command.args([if cfg!(windows) { "/C" } else { "-c" }, command_line]);
+    command
+}
+
+// a missing or non-executable engine, uat or editor-cmd path is an error for the caller, not a panic
+fn launch_error(runner: &dyn TestRunner, command: &Command, error: std::io::Error) -> String {
+    format!("failed to start {} ({}): {}", command.get_program().to_string_lossy(), runner.name(), error)
+}
+
+// waits for the process, the caller decides which exit codes still mean the report is worth reading
+pub fn run(runner: &dyn TestRunner, config: &TestConfiguration, selection: &TestSelection) -> Result<ExitStatus, String> {
+    eprintln!("running tests: {} ({})", selection, runner.name());
+    // the engine's own output is status as well, stdout is kept for the report formats
+    let mut command = runner.command(config, selection, &Instance::primary(config));
+    let mut run_test_command = command.stdout(std::io::stderr()).spawn().map_err(|error| launch_error(runner, &command, error))?;
+    eprintln!("process started, waiting for process to finish");
+
+    let test_exit_code = run_test_command.wait().map_err(|error| format!("failed to wait for the test process: {}", error))?;
+    eprintln!("done waiting for process");
+    Ok(test_exit_code)
+}
+
+// launches the engine once on warmup_tests so shader compilation and cache fills land outside the measured run,
+// its report is never read
+pub fn run_warmup(runner: &dyn TestRunner, config: &TestConfiguration, warmup_tests: &str) -> Result<ExitStatus, String> {
+    eprintln!("warming up: {} ({})", warmup_tests, runner.name());
+    let selection = TestSelection::Tests(warmup_tests.to_owned());
+    let mut command = runner.command(config, &selection, &Instance::warmup(config));
+    let mut warmup_command = command.stdout(std::io::stderr()).spawn().map_err(|error| launch_error(runner, &command, error))?;
+    let warmup_exit_code = warmup_command.wait().map_err(|error| format!("failed to wait for the warmup process: {}", error))?;
+    eprintln!("warmup finished");
+    Ok(warmup_exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(runner: &str) -> TestConfiguration {
+        toml::from_str(format!(r#"
+            path_to_unrealengine = "Engine/Binaries/Win64/UnrealEditor.exe"
+            path_to_project = "Castle.uproject"
+            path_to_reports = "Reports"
+            run_tests = "Project"
+            test_exit = "Automation Test Queue Empty"
+            ignore_regexes = []
+            {}"#, runner).as_str()).unwrap()
+    }
+
+    fn arguments(command: &Command) -> Vec<String> {
+        command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn editor_cmd_next_to_the_editor() {
+        assert_eq!(editor_cmd_path("Engine/Binaries/Win64/UnrealEditor.exe"), Path::new("Engine/Binaries/Win64/UnrealEditor-Cmd.exe").display().to_string());
+        assert_eq!(editor_cmd_path("UnrealEditor"), "UnrealEditor-Cmd");
+
+        let config = config("[runner]\nkind = \"editor-cmd\"");
+        let command = runner(&config).unwrap().command(&config, &TestSelection::Tests("Project.Door".to_owned()), &Instance::primary(&config));
+        assert_eq!(command.get_program().to_string_lossy(), editor_cmd_path(config.path_to_unrealengine.as_str()));
+        assert_eq!(arguments(&command)[..2], ["Castle.uproject", "-ExecCmds=Automation RunTests Project.Door"]);
+    }
+
+    #[test]
+    fn runners_missing_their_executable() {
+        assert!(runner(&config("[runner]\nkind = \"packaged\"")).is_err());
+        assert!(runner(&config("[runner]\nkind = \"gauntlet\"")).is_err());
+        assert_eq!(runner(&config("")).unwrap().name(), "editor");
+    }
+
+    #[test]
+    fn missing_executables_fail_the_launch() {
+        let config = config("");
+        let test_runner = runner(&config).unwrap();
+        let selection = TestSelection::Tests("Project.Door".to_owned());
+        assert!(run(test_runner.as_ref(), &config, &selection).unwrap_err().starts_with("failed to start Engine/Binaries/Win64/UnrealEditor.exe"));
+        assert!(run_warmup(test_runner.as_ref(), &config, "Project.Warmup").is_err());
+        let launched = crate::instances::run_instances(test_runner.as_ref(), &config, &[selection], 2, std::time::Duration::ZERO);
+        assert!(launched.unwrap_err().starts_with("failed to start instance 0"));
+    }
+
+    #[test]
+    fn gauntlet_defaults() {
+        let config = config("[runner]\nkind = \"gauntlet\"\npath_to_uat = \"RunUAT.bat\"\nextra_args = [\"-verbose\"]");
+        let command = runner(&config).unwrap().command(&config, &TestSelection::Tests("Project.Door".to_owned()), &Instance::primary(&config));
+        let args = arguments(&command);
+        assert_eq!(command.get_program(), "RunUAT.bat");
+        assert!(args.contains(&"-platform=Win64".to_owned()));
+        assert!(args.contains(&"-configuration=Development".to_owned()));
+        assert!(args.contains(&"-build=editor".to_owned()));
+        assert_eq!(args.last().map(String::as_str), Some("-verbose"));
+    }
+
+    #[test]
+    fn filters_run_through_run_filter() {
+        let config = config("");
+        let selection = TestSelection::Filter(unreal_filter("SMOKE").unwrap());
+        let command = runner(&config).unwrap().command(&config, &selection, &Instance::primary(&config));
+        assert_eq!(arguments(&command)[1], "-ExecCmds=Automation RunFilter Smoke");
+        assert_eq!(selection.to_string(), "Smoke filter");
+        assert!(FILTER_NAMES.iter().all(|name| unreal_filter(name).is_some()));
+        assert_eq!(unreal_filter("slow"), None);
+    }
+
+    #[test]
+    fn exec_cmds_around_the_automation_command() {
+        let config = config("pre_exec_cmds = [\"r.ScreenPercentage 100\"]\npost_exec_cmds = [\"stat unit\"]");
+        let command = runner(&config).unwrap().command(&config, &TestSelection::Tests("Project.Door".to_owned()), &Instance::primary(&config));
+        assert_eq!(arguments(&command)[1], "-ExecCmds=r.ScreenPercentage 100,Automation RunTests Project.Door,stat unit");
+    }
+
+    #[test]
+    fn startup_map_follows_the_project() {
+        let config = config("startup_map = \"/Game/Maps/TestArena\"");
+        let command = runner(&config).unwrap().command(&config, &TestSelection::Tests("Project.Door".to_owned()), &Instance::primary(&config));
+        assert_eq!(arguments(&command)[..3], ["Castle.uproject", "/Game/Maps/TestArena", "-ExecCmds=Automation RunTests Project.Door"]);
+    }
+
+    #[test]
+    fn warmup_report_outside_path_to_reports() {
+        let config = config("");
+        let warmup = Instance::warmup(&config);
+        let command = runner(&config).unwrap().command(&config, &TestSelection::Tests("Project.Door".to_owned()), &warmup);
+        let args = arguments(&command);
+        assert_eq!(Path::new(warmup.report_directory.as_str()), Path::new("Saved").join("AutomationWarmup"));
+        assert!(args.contains(&"-log=runtests-warmup.log".to_owned()));
+        assert!(args.contains(&format!("-ReportOutputPath={}", warmup.report_directory)));
+    }
+}
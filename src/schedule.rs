@@ -0,0 +1,133 @@
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use serde::Deserialize;
+
+// [schedule] keeps runtests schedule resident, starting a run whenever cron comes around; a lightweight nightly
+// runner for teams without ci, installed as a service or a logon task
+#[derive(Debug, Deserialize)]
+pub struct ScheduleConfiguration {
+    pub cron: String, // minute hour day-of-month month day-of-week, in local time, ie "0 2 * * 1-5"
+    #[serde(default)]
+    pub args: Vec<String>, // passed to every scheduled run, ie ["--session-name", "nightly"]
+    pub on_start: Option<String>, // shell command before each run
+    pub on_finish: Option<String>, // shell command after each run, {exit_code} and {result} are filled in
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>, // 1-31, index 0 unused
+    months: Vec<bool>, // 1-12, index 0 unused
+    weekdays: Vec<bool>, // 0 is sunday
+    any_day: bool,
+    any_weekday: bool,
+}
+
+// *, 5, 1-5, 1,15, */15 and 1-5/2
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0).ok_or_else(|| format!("invalid step in {}", part))?),
+            None => (part, 1),
+        };
+        let number = |text: &str| text.parse::<u32>().ok().filter(|number| (min..=max).contains(number)).ok_or_else(|| format!("{} isn't between {} and {}", text, min, max));
+        let (first, last) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((first, last)) => (number(first)?, number(last)?),
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if first > last {
+            return Err(format!("{} runs backwards", part));
+        }
+        for value in (first..=last).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<CronSchedule, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("\"{}\" needs 5 fields: minute hour day-of-month month day-of-week", expression));
+        }
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // 7 is sunday as well
+        if weekdays.pop() == Some(true) {
+            weekdays[0] = true;
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    // like cron, a restricted day-of-month and day-of-week match when either does
+    fn matches(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days[time.day() as usize];
+        let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        };
+        self.minutes[time.minute() as usize] && self.hours[time.hour() as usize] && self.months[time.month() as usize] && day_matches
+    }
+
+    // the first matching minute after time, None for schedules like "0 0 31 2 *" that never come around
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        // leap days come around within 4 years plus a week for the weekday
+        let limit = next + Duration::days(4 * 366 + 7);
+        while next < limit {
+            if self.matches(&next) {
+                return Some(next);
+            }
+            next += Duration::minutes(1);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn next_runs() {
+        // 2023-10-06 is a friday
+        let weeknights = CronSchedule::parse("0 2 * * 1-5").unwrap();
+        assert_eq!(weeknights.next_after(at(2023, 10, 5, 2, 0)), Some(at(2023, 10, 6, 2, 0)));
+        assert_eq!(weeknights.next_after(at(2023, 10, 6, 3, 0)), Some(at(2023, 10, 9, 2, 0)));
+        let quarter_hours = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(quarter_hours.next_after(at(2023, 10, 6, 10, 7)), Some(at(2023, 10, 6, 10, 15)));
+        let sundays_and_firsts = CronSchedule::parse("30 1 1 * 7").unwrap();
+        assert_eq!(sundays_and_firsts.next_after(at(2023, 10, 6, 0, 0)), Some(at(2023, 10, 8, 1, 30)));
+        assert_eq!(sundays_and_firsts.next_after(at(2023, 10, 29, 2, 0)), Some(at(2023, 11, 1, 1, 30)));
+        assert_eq!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(at(2023, 10, 6, 0, 0)), None);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(CronSchedule::parse("0 2 * *").is_err());
+        assert!(CronSchedule::parse("60 2 * * *").is_err());
+        assert!(CronSchedule::parse("0 5-2 * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+}
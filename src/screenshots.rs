@@ -0,0 +1,126 @@
+use crate::{EntryType, TestPass, TestResult};
+use colored::*;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ScreenshotTolerance {
+    pub pattern: String, // regex matched against the full test path
+    pub global_difference: f32,
+    pub max_local_difference: Option<f32>,
+}
+
+// Screenshot 'X' test failed, Screenshots were different!  Global Difference = 0.058, Max Local Difference = 0.734
+fn parse_differences(message: &str) -> Option<(f32, f32)> {
+    let re = regex::Regex::new(r"Global Difference = ([0-9.]+), Max Local Difference = ([0-9.]+)").unwrap();
+    let captures = re.captures(message)?;
+    Some((captures[1].parse().ok()?, captures[2].parse().ok()?))
+}
+
+// checked when the config is parsed so a typo fails the config instead of a run
+pub fn check_tolerances(tolerances: &[ScreenshotTolerance]) -> Result<(), String> {
+    for tolerance in tolerances {
+        regex::Regex::new(tolerance.pattern.as_str()).map_err(|error| format!("screenshot tolerance pattern {} is invalid: {}", tolerance.pattern, error))?;
+    }
+    Ok(())
+}
+
+// re-evaluates failed screenshot comparisons against the configured tolerances, comparisons within
+// tolerance are downgraded to warnings and tests left without errors are moved to succeeded with warnings
+pub fn apply_tolerances(test_pass: &mut TestPass, tolerances: &[ScreenshotTolerance]) {
+    if tolerances.is_empty() {
+        return;
+    }
+    let patterns: Vec<(regex::Regex, &ScreenshotTolerance)> = tolerances.iter()
+        .filter_map(|tolerance| Some((regex::Regex::new(tolerance.pattern.as_str()).ok()?, tolerance)))
+        .collect();
+
+    let mut overridden_count = 0;
+    let mut recovered_tests = 0;
+    for test in test_pass.tests.iter_mut() {
+        let tolerance = match patterns.iter().find(|(re, _)| re.is_match(test.full_test_path.as_str())) {
+            Some((_, tolerance)) => tolerance,
+            None => continue,
+        };
+
+        for entry in test.entries.iter_mut() {
+            if let EntryType::Error = entry.event.entry_type {
+                let (global_difference, max_local_difference) = match parse_differences(entry.event.message.as_str()) {
+                    Some(differences) => differences,
+                    None => continue,
+                };
+                let within_local = tolerance.max_local_difference.is_none_or(|max_local| max_local_difference <= max_local);
+                if global_difference <= tolerance.global_difference && within_local {
+                    entry.event.entry_type = EntryType::Warning;
+                    entry.event.message = format!("{} (within configured tolerance {})", entry.event.message, tolerance.global_difference).into();
+                    test.errors -= 1;
+                    test.warnings += 1;
+                    overridden_count += 1;
+                }
+            }
+        }
+
+        let has_errors = test.entries.iter().any(|entry| matches!(entry.event.entry_type, EntryType::Error));
+        if matches!(test.state, TestResult::Fail) && !has_errors {
+            test.state = TestResult::Success;
+            recovered_tests += 1;
+        }
+    }
+
+    test_pass.failed -= recovered_tests;
+    test_pass.succeeded_with_warnings += recovered_tests;
+    if overridden_count > 0 {
+        eprintln!("{}", format!("{} screenshot comparisons passed with a configured tolerance, {} tests no longer failing", overridden_count, recovered_tests).yellow());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pass(tests: &str) -> TestPass {
+        let mut test_pass = crate::test_pass_from_tests(tests);
+        test_pass.failed = 1;
+        test_pass
+    }
+
+    fn failed_test(entries: &str) -> String {
+        format!(r#"{{"testDisplayName": "Screenshot", "fullTestPath": "Project.Rendering.Screenshot", "state": "Fail",
+            "entries": [{}], "warnings": 0, "errors": 1, "artifacts": []}}"#, entries)
+    }
+
+    fn error_entry(message: &str) -> String {
+        format!(r#"{{"event": {{"type": "Error", "message": "{}", "context": "", "artifact": ""}},
+            "filename": "", "lineNumber": 0, "timestamp": ""}}"#, message)
+    }
+
+    fn tolerance(global_difference: f32) -> Vec<ScreenshotTolerance> {
+        vec![ScreenshotTolerance { pattern: "Rendering".to_owned(), global_difference, max_local_difference: None }]
+    }
+
+    const DIFFERENT: &str = "Screenshots were different!  Global Difference = 0.058, Max Local Difference = 0.734";
+
+    #[test]
+    fn comparison_within_tolerance_recovers_the_test() {
+        let mut test_pass = test_pass(failed_test(error_entry(DIFFERENT).as_str()).as_str());
+        apply_tolerances(&mut test_pass, &tolerance(0.1));
+        let test = &test_pass.tests[0];
+        assert!(matches!(test.state, TestResult::Success));
+        assert_eq!((test.errors, test.warnings), (0, 1));
+        assert_eq!((test_pass.failed, test_pass.succeeded_with_warnings), (0, 1));
+    }
+
+    #[test]
+    fn comparison_over_tolerance_stays_failed() {
+        let mut test_pass = test_pass(failed_test(error_entry(DIFFERENT).as_str()).as_str());
+        apply_tolerances(&mut test_pass, &tolerance(0.01));
+        assert!(matches!(test_pass.tests[0].state, TestResult::Fail));
+        assert_eq!(test_pass.failed, 1);
+    }
+
+    #[test]
+    fn invalid_tolerance_patterns_are_reported() {
+        assert!(check_tolerances(&tolerance(0.1)).is_ok());
+        let invalid = vec![ScreenshotTolerance { pattern: "Rendering(".to_owned(), global_difference: 0.1, max_local_difference: None }];
+        assert!(check_tolerances(&invalid).unwrap_err().starts_with("screenshot tolerance pattern Rendering( is invalid"));
+    }
+}
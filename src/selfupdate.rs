@@ -0,0 +1,168 @@
+use crate::{http, platforms, sha256};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// [updates] is where self-update finds releases, build agents otherwise keep whatever runtests was copied there
+#[derive(Debug, Deserialize)]
+pub struct UpdatesConfiguration {
+    #[serde(default = "default_releases_url")]
+    pub releases_url: String, // a github style api endpoint for the latest release
+    #[serde(default)]
+    pub check: bool, // runs print a notice when a newer release is out
+    pub token_env: Option<String>, // environment variable holding a token, for private repositories
+}
+
+impl Default for UpdatesConfiguration {
+    fn default() -> UpdatesConfiguration {
+        UpdatesConfiguration { releases_url: default_releases_url(), check: false, token_env: None }
+    }
+}
+
+pub(crate) fn default_releases_url() -> String {
+    "https://api.github.com/repos/dakitten2358/parsetests/releases/latest".to_owned()
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+// v1.2.3 or 1.2.3-rc1, missing parts count as 0 and anything after a - is ignored
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.split('-').next()?.split('.').map(|part| part.parse::<u64>());
+    let mut next = || parts.next().unwrap_or(Ok(0)).ok();
+    Some((next()?, next()?, next()?))
+}
+
+fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+fn asset_name(platform: &str) -> &'static str {
+    match platform {
+        "windows" => "runtests-windows.exe",
+        "mac" => "runtests-mac",
+        _ => "runtests-linux",
+    }
+}
+
+// <asset>.sha256 holds the hash alone or sha256sum's "hash  name", SHA256SUMS a line like that per asset
+fn expected_hash(listing: &str, name: &str) -> Option<String> {
+    listing.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        let hash = words.next()?;
+        match words.next() {
+            Some(file) if file.trim_start_matches('*') != name => None,
+            _ => Some(hash.to_lowercase()),
+        }
+    })
+}
+
+fn headers(config: &UpdatesConfiguration) -> Vec<(String, String)> {
+    let mut headers = vec![("Accept".to_owned(), "application/vnd.github+json".to_owned())];
+    if let Some(token) = config.token_env.as_ref().and_then(|token_env| std::env::var(token_env).ok()) {
+        headers.push(("Authorization".to_owned(), format!("Bearer {}", token)));
+    }
+    headers
+}
+
+fn latest_release(config: &UpdatesConfiguration) -> Result<Release, String> {
+    let headers = headers(config);
+    let headers: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+    let body = http::send("GET", config.releases_url.as_str(), &headers, None)?;
+    serde_json::from_str(body.as_str()).map_err(|error| format!("unexpected release from {}: {}", config.releases_url, error))
+}
+
+// None when runtests is up to date or the release can't be fetched, a run shouldn't fail over a notice
+pub fn newer_release_notice(config: &UpdatesConfiguration) -> Option<String> {
+    let release = latest_release(config).ok()?;
+    if !is_newer(release.tag_name.as_str(), env!("CARGO_PKG_VERSION")) {
+        return None;
+    }
+    Some(format!("runtests {} is available ({} is installed), runtests self-update installs it", release.tag_name, env!("CARGO_PKG_VERSION")))
+}
+
+#[cfg(windows)]
+fn replace_executable(executable: &Path, download: &Path) -> std::io::Result<()> {
+    // windows won't overwrite a running executable, but it does let it be renamed out of the way
+    let previous = executable.with_extension("old.exe");
+    fs::remove_file(&previous).ok();
+    fs::rename(executable, &previous)?;
+    fs::rename(download, executable)
+}
+
+#[cfg(not(windows))]
+fn replace_executable(executable: &Path, download: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(download, fs::Permissions::from_mode(0o755))?;
+    fs::rename(download, executable)
+}
+
+// the release binary is only installed once its sha256 matches the one published with it
+pub fn self_update(config: &UpdatesConfiguration, check_only: bool) -> Result<String, String> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = latest_release(config)?;
+    if !is_newer(release.tag_name.as_str(), current) {
+        return Ok(format!("runtests {} is up to date", current));
+    }
+    if check_only {
+        return Ok(format!("runtests {} is available, {} is installed", release.tag_name, current));
+    }
+
+    let name = asset_name(platforms::host_platform());
+    let binary = release.assets.iter().find(|asset| asset.name == name)
+        .ok_or_else(|| format!("release {} has no {}", release.tag_name, name))?;
+    let checksum = release.assets.iter().find(|asset| asset.name == format!("{}.sha256", name) || asset.name == "SHA256SUMS")
+        .ok_or_else(|| format!("release {} publishes no sha256 for {}, not installing it", release.tag_name, name))?;
+    let headers = headers(config);
+    let headers: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+    let listing = http::send("GET", checksum.browser_download_url.as_str(), &headers, None)?;
+    let expected = expected_hash(listing.as_str(), name).ok_or_else(|| format!("{} has no hash for {}", checksum.name, name))?;
+
+    let executable = std::env::current_exe().map_err(|error| format!("failed to find the running executable: {}", error))?;
+    // next to the executable so it can be renamed into place
+    let download = executable.with_extension("download");
+    http::download(binary.browser_download_url.as_str(), &headers, &download)?;
+    let (_, actual) = sha256::hash_file(&download).map_err(|error| format!("failed to hash {}: {}", download.display(), error))?;
+    if actual != expected {
+        fs::remove_file(&download).ok();
+        return Err(format!("{} has sha256 {} but the release says {}, not installing it", name, actual, expected));
+    }
+    replace_executable(&executable, &download).map_err(|error| format!("failed to replace {}: {}", executable.display(), error))?;
+    Ok(format!("updated runtests {} to {}", current, release.tag_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_versions() {
+        assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("0.4-rc1"), Some((0, 4, 0)));
+        assert_eq!(parse_version("nightly"), None);
+        assert!(is_newer("v0.10.0", "0.9.2"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn hashes_from_checksum_files() {
+        assert_eq!(expected_hash("ABCD\n", "runtests-linux"), Some("abcd".to_owned()));
+        let sums = "1111  runtests-windows.exe\n2222 *runtests-linux\n";
+        assert_eq!(expected_hash(sums, "runtests-linux"), Some("2222".to_owned()));
+        assert_eq!(expected_hash(sums, "runtests-mac"), None);
+    }
+}
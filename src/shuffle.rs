@@ -0,0 +1,49 @@
+// splitmix64, small and good enough to reorder a test list reproducibly from a seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+pub fn random_seed() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+// fisher-yates, the same seed and input always give the same order
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for index in (1..items.len()).rev() {
+        let other = (rng.next() % (index as u64 + 1)) as usize;
+        items.swap(index, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_order() {
+        let tests: Vec<u32> = (0..20).collect();
+        let mut first = tests.clone();
+        let mut second = tests.clone();
+        shuffle(&mut first, 42);
+        shuffle(&mut second, 42);
+        assert_eq!(first, second);
+        assert_ne!(first, tests);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, tests);
+
+        let mut other_seed = tests.clone();
+        shuffle(&mut other_seed, 43);
+        assert_ne!(other_seed, first);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::sha256::Sha256;
+use crate::{datetime, http, RunMetadata, TestPass, TestResult};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+// with [telemetry] the run is exported as an OTLP trace: the run is the root span, every engine launch and
+// every test a child of it
+#[derive(Debug, Deserialize)]
+pub struct TelemetryConfiguration {
+    pub endpoint: Option<String>, // OTLP/HTTP collector, OTEL_EXPORTER_OTLP_ENDPOINT by default
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+pub(crate) fn default_service_name() -> String {
+    "runtests".to_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineLaunch {
+    pub name: String, // warmup, engine or instance-N
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub exit_code: Option<i32>, // None when terminated by a signal
+}
+
+const STATUS_OK: i32 = 1;
+const STATUS_ERROR: i32 = 2;
+const SPAN_KIND_INTERNAL: i32 = 1;
+
+// ids only need to be unique, hashing the time and pid avoids pulling in a random number generator
+fn trace_id() -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}-{}", crate::shuffle::random_seed(), std::process::id()).as_bytes());
+    hasher.finish()[..32].to_owned()
+}
+
+fn span_id(trace_id: &str, index: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}-{}", trace_id, index).as_bytes());
+    hasher.finish()[..16].to_owned()
+}
+
+fn nanos(time: DateTime<Utc>) -> String {
+    time.timestamp_nanos_opt().unwrap_or_default().to_string()
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    let value = match value {
+        Value::String(text) => json!({ "stringValue": text }),
+        Value::Bool(flag) => json!({ "boolValue": flag }),
+        Value::Number(number) if number.is_i64() => json!({ "intValue": number.to_string() }),
+        Value::Number(number) => json!({ "doubleValue": number }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+struct Span {
+    name: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    ok: bool,
+    attributes: Vec<Value>,
+}
+
+// tests are placed at their first timestamped entry, tests without one are laid end to end from the run's start
+fn test_spans(test_pass: &TestPass, run_start: DateTime<Utc>) -> Vec<Span> {
+    let mut next_start = run_start;
+    let platform = test_pass.devices.as_ref().and_then(|devices| devices.first()).map(|device| device.platform.clone());
+    test_pass.tests.iter()
+        .filter(|test| matches!(test.state, TestResult::Success | TestResult::Fail))
+        .map(|test| {
+            let start = test.entries.iter().filter_map(|entry| datetime::parse_timestamp(entry.timestamp.as_str())).min().unwrap_or(next_start);
+            let end = start + chrono::Duration::milliseconds((test.duration * 1000.0) as i64);
+            next_start = end;
+            let mut attributes = vec![
+                attribute("test.path", json!(test.full_test_path)),
+                attribute("test.state", json!(format!("{:?}", test.state))),
+                attribute("test.duration", json!(test.duration)),
+                attribute("test.errors", json!(test.errors)),
+                attribute("test.warnings", json!(test.warnings)),
+            ];
+            if let Some(platform) = &platform {
+                attributes.push(attribute("test.platform", json!(platform)));
+            }
+            Span { name: test.full_test_path.clone(), start, end, ok: test.state == TestResult::Success, attributes }
+        })
+        .collect()
+}
+
+pub fn trace(config: &TelemetryConfiguration, test_pass: &TestPass, run_metadata: &RunMetadata) -> Value {
+    let end = Utc::now();
+    let start = run_metadata.started_at.unwrap_or_else(|| end - chrono::Duration::milliseconds((test_pass.total_duration * 1000.0) as i64));
+    let mut root_attributes = vec![
+        attribute("runtests.succeeded", json!(test_pass.succeeded)),
+        attribute("runtests.failed", json!(test_pass.failed)),
+        attribute("runtests.crashed", json!(run_metadata.crashed)),
+    ];
+    if let Some(session_name) = &run_metadata.session_name {
+        root_attributes.push(attribute("runtests.session_name", json!(session_name)));
+    }
+    if let Some(git_info) = &run_metadata.git {
+        root_attributes.push(attribute("vcs.commit", json!(git_info.commit)));
+    }
+    if let Some(changelist) = &run_metadata.changelist {
+        root_attributes.push(attribute("vcs.changelist", json!(changelist)));
+    }
+    for (key, value) in run_metadata.meta.iter() {
+        root_attributes.push(attribute(format!("runtests.meta.{}", key).as_str(), json!(value)));
+    }
+
+    let mut spans = vec![Span { name: "runtests".to_owned(), start, end, ok: test_pass.failed == 0 && !run_metadata.crashed, attributes: root_attributes }];
+    spans.extend(run_metadata.launches.iter().map(|launch| Span {
+        name: launch.name.clone(),
+        start: launch.started_at,
+        end: launch.finished_at,
+        ok: launch.exit_code == Some(0),
+        attributes: launch.exit_code.map(|exit_code| attribute("process.exit_code", json!(exit_code))).into_iter().collect(),
+    }));
+    spans.extend(test_spans(test_pass, start));
+
+    let trace_id = trace_id();
+    let root_id = span_id(trace_id.as_str(), 0);
+    let spans: Vec<Value> = spans.into_iter().enumerate()
+        .map(|(index, span)| {
+            let mut value = json!({
+                "traceId": trace_id,
+                "spanId": span_id(trace_id.as_str(), index),
+                "name": span.name,
+                "kind": SPAN_KIND_INTERNAL,
+                "startTimeUnixNano": nanos(span.start),
+                "endTimeUnixNano": nanos(span.end),
+                "attributes": span.attributes,
+                "status": { "code": if span.ok { STATUS_OK } else { STATUS_ERROR } },
+            });
+            if index > 0 {
+                value["parentSpanId"] = json!(root_id);
+            }
+            value
+        })
+        .collect();
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [attribute("service.name", json!(config.service_name))] },
+            "scopeSpans": [{ "scope": { "name": "runtests" }, "spans": spans }],
+        }]
+    })
+}
+
+pub fn export_trace(config: &TelemetryConfiguration, trace: &Value) -> Result<(), String> {
+    let endpoint = config.endpoint.clone().or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+        .ok_or_else(|| "no endpoint and OTEL_EXPORTER_OTLP_ENDPOINT is unset".to_owned())?;
+    http::send_json("POST", format!("{}/v1/traces", endpoint.trim_end_matches('/')).as_str(), &[], trace)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_launches_and_tests_are_spans() {
+        let mut test_pass = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "duration": 2, "entries": [], "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Fail", "duration": 1, "entries": [], "warnings": 0, "errors": 1, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Skipped", "state": "NotRun", "entries": [], "warnings": 0, "errors": 0, "artifacts": []}"#);
+        test_pass.succeeded = 1;
+        test_pass.failed = 1;
+        test_pass.not_run = 1;
+        test_pass.total_duration = 5.0;
+        let started_at = DateTime::parse_from_rfc3339("2023-10-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let run_metadata = RunMetadata {
+            started_at: Some(started_at),
+            launches: vec![EngineLaunch { name: "engine".to_owned(), started_at, finished_at: started_at + chrono::Duration::seconds(5), exit_code: Some(0) }],
+            ..RunMetadata::default()
+        };
+        let config = TelemetryConfiguration { endpoint: None, service_name: default_service_name() };
+
+        let trace = trace(&config, &test_pass, &run_metadata);
+        let spans = trace["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        let names: Vec<&str> = spans.iter().map(|span| span["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["runtests", "engine", "Project.Door.Opens", "Project.Door.Closes"]);
+        assert!(spans[0].get("parentSpanId").is_none());
+        assert!(spans[1..].iter().all(|span| span["parentSpanId"] == spans[0]["spanId"]));
+        assert_eq!(spans[0]["status"]["code"], STATUS_ERROR);
+        assert_eq!(spans[2]["status"]["code"], STATUS_OK);
+        // the second test starts where the first one ended
+        assert_eq!(spans[3]["startTimeUnixNano"], spans[2]["endTimeUnixNano"]);
+        assert_eq!(spans[0]["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(spans[1]["spanId"].as_str().unwrap().len(), 16);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{pipeline, TestPass};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// fewer files than this read too quickly for a progress line to be worth it
+const PROGRESS_MIN_FILES: usize = 200;
+
+// what a test says about itself beyond its display name, for jumping to the implementation and for grouping
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestInfo {
+    pub source_file: Option<String>, // where the test is defined
+    pub source_file_line: Option<i32>,
+    pub owner: Option<String>,
+    pub open_command: Option<String>, // the console command that opens the test's map or asset
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// a per-test json written next to index.json, anything without a fullTestPath isn't one
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestRecord {
+    full_test_path: String,
+    #[serde(flatten)]
+    info: TestInfo,
+}
+
+// the report itself, an archive's manifest and the run manifest and cache runtests keeps next to the reports
+const NOT_TEST_RECORDS: &[&str] = &["index.json", "manifest.json", "run-manifest.json", "runtests-cache.json"];
+
+// the instance-N directories of a parallel run and the merged report combining them, a report directory
+// holding them has reports of another run below it
+fn is_instance_directory(name: &str) -> bool {
+    name == "merged" || name.strip_prefix("instance-").is_some_and(|index| !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+fn collect_json_files(directory: &Path, found: &mut Vec<PathBuf>) {
+    let read_dir = match fs::read_dir(directory) {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+    for dir_entry in read_dir.flatten() {
+        let path = dir_entry.path();
+        let name = dir_entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !is_instance_directory(name.as_ref()) {
+                collect_json_files(&path, found);
+            }
+        } else if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json")) && !NOT_TEST_RECORDS.contains(&name.as_ref()) {
+            found.push(path);
+        }
+    }
+}
+
+fn read_record(path: &Path) -> Option<TestRecord> {
+    let contents = fs::read(path).ok()?;
+    let contents = contents.strip_prefix(b"\xef\xbb\xbf").unwrap_or(&contents);
+    serde_json::from_slice(contents).ok()
+}
+
+// fills in the info of tests that have a per-test json under the report directory, info that
+// came with index.json (a merged report carries it) is kept; the files are read on `workers` threads,
+// one at a time is slow on a mapped report share
+pub fn read_test_info(test_pass: &mut TestPass, report_directory: &Path, workers: usize) {
+    let mut files = Vec::new();
+    collect_json_files(report_directory, &mut files);
+    let show_progress = files.len() >= PROGRESS_MIN_FILES && std::io::stderr().is_terminal();
+    let read = AtomicUsize::new(0);
+    let records = pipeline::map_bounded(&files, workers, |file| {
+        let record = read_record(file);
+        let count = read.fetch_add(1, Ordering::Relaxed) + 1;
+        if show_progress && (count % 50 == 0 || count == files.len()) {
+            eprint!("\rreading per-test json {}/{}", count, files.len());
+        }
+        record
+    });
+    if show_progress {
+        eprintln!();
+    }
+    let mut records: HashMap<String, TestInfo> = records.into_iter()
+        .flatten()
+        .map(|record| (record.full_test_path, record.info))
+        .collect();
+    if records.is_empty() {
+        return;
+    }
+    for test in test_pass.tests.iter_mut().filter(|test| test.info.is_none()) {
+        test.info = records.remove(&test.full_test_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_test_json_next_to_the_index() {
+        let directory = std::env::temp_dir().join(format!("runtests-testinfo-{}", std::process::id()));
+        fs::create_dir_all(directory.join("Tests")).unwrap();
+        let mut index = crate::test_pass_from_tests(r#"
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Opens", "state": "Success", "entries": [], "warnings": 0, "errors": 0, "artifacts": []},
+                {"testDisplayName": "", "fullTestPath": "Project.Door.Closes", "state": "Success", "entries": [], "warnings": 0, "errors": 0, "artifacts": [],
+                    "info": {"owner": "gameplay"}}"#);
+        index.succeeded = 2;
+        fs::write(directory.join("index.json"), serde_json::to_string(&index).unwrap()).unwrap();
+        fs::write(directory.join("Tests").join("Opens.json"),
+            r#"{"fullTestPath": "Project.Door.Opens", "sourceFile": "Source/Tests/DoorTest.cpp", "sourceFileLine": 42, "owner": "level design", "tags": ["[Door]"]}"#).unwrap();
+        fs::write(directory.join("Tests").join("Closes.json"), r#"{"fullTestPath": "Project.Door.Closes", "owner": "someone else"}"#).unwrap();
+        fs::write(directory.join("Tests").join("metrics.json"), r#"{"FrameTime": 16.2}"#).unwrap();
+
+        let mut test_pass = crate::report::read_test_pass(&directory.join("index.json")).unwrap();
+        read_test_info(&mut test_pass, &directory, 4);
+        fs::remove_dir_all(&directory).unwrap();
+
+        let opens = test_pass.tests[0].info.as_ref().unwrap();
+        assert_eq!(opens.source_file.as_deref(), Some("Source/Tests/DoorTest.cpp"));
+        assert_eq!(opens.source_file_line, Some(42));
+        assert_eq!(opens.owner.as_deref(), Some("level design"));
+        assert_eq!(opens.tags, ["[Door]"]);
+        assert_eq!(test_pass.tests[1].info.as_ref().unwrap().owner.as_deref(), Some("gameplay"));
+    }
+
+    #[test]
+    fn runtests_files_and_other_runs_are_skipped() {
+        let directory = std::env::temp_dir().join(format!("runtests-testinfo-skipped-{}", std::process::id()));
+        for subdirectory in ["Tests", "merged", "instance-0", "instance-notes"] {
+            fs::create_dir_all(directory.join(subdirectory)).unwrap();
+        }
+        for file in ["index.json", "run-manifest.json", "runtests-cache.json", "Tests/Opens.json", "merged/Opens.json", "instance-0/Opens.json", "instance-notes/Opens.json"] {
+            fs::write(directory.join(file), "{}").unwrap();
+        }
+        let mut found = Vec::new();
+        collect_json_files(&directory, &mut found);
+        let mut found: Vec<String> = found.iter().map(|path| path.strip_prefix(&directory).unwrap().to_string_lossy().replace('\\', "/")).collect();
+        found.sort();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(found, ["Tests/Opens.json", "instance-notes/Opens.json"]);
+    }
+}
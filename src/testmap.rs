@@ -0,0 +1,201 @@
+use crate::testinfo::TestInfo;
+use crate::{filters, instances, TestPass};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// map-tests finds where tests are defined without running them: the automation test and spec macros under
+// Source/ (the project's and its plugins') and the maps under Content/ that place functional tests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestLocation {
+    pub test: String, // the path the test is registered under, tests of a complex test or spec are below it
+    pub file: String,
+    pub line: Option<i32>, // none for maps
+}
+
+const SOURCE_EXTENSIONS: &[&str] = &["cpp", "h", "inl"];
+
+// IMPLEMENT_SIMPLE_AUTOMATION_TEST(FDoorTest, "Project.Door.Opens", flags), the CUSTOM_ variants take a base
+// class before the name, and BEGIN_DEFINE_SPEC / DEFINE_SPEC(FDoorSpec, "Project.Door", flags)
+const TEST_MACRO: &str = r#"\b(?:IMPLEMENT_(?:CUSTOM_)?(?:SIMPLE|COMPLEX)_AUTOMATION_TEST|(?:BEGIN_)?DEFINE_SPEC)\s*\(\s*\w+\s*,\s*(?:\w+\s*,\s*)?"([^"]+)""#;
+
+fn collect_files(directory: &Path, extensions: &[&str], found: &mut Vec<PathBuf>) {
+    for dir_entry in fs::read_dir(directory).into_iter().flatten().flatten() {
+        let path = dir_entry.path();
+        if path.is_dir() {
+            collect_files(&path, extensions, found);
+        } else if path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extensions.iter().any(|wanted| extension.eq_ignore_ascii_case(wanted))) {
+            found.push(path);
+        }
+    }
+}
+
+pub fn scan_source(source: &str, file: &str) -> Vec<TestLocation> {
+    let regex = regex::Regex::new(TEST_MACRO).unwrap();
+    regex.captures_iter(source)
+        .map(|captures| {
+            let start = captures.get(0).map_or(0, |whole| whole.start());
+            let line = source[..start].matches('\n').count() as i32 + 1;
+            TestLocation { test: captures[1].to_owned(), file: file.to_owned(), line: Some(line) }
+        })
+        .collect()
+}
+
+// a map places functional tests when its package names the FunctionalTest class; they're registered under
+// Project.Functional Tests. followed by the map's path below Content/ with dots
+pub fn functional_test_prefix(content_directory: &Path, map: &Path) -> Option<String> {
+    let relative = map.strip_prefix(content_directory).ok()?.with_extension("");
+    let parts: Vec<String> = relative.components().map(|component| component.as_os_str().to_string_lossy().into_owned()).collect();
+    if parts.is_empty() { None } else { Some(format!("Project.Functional Tests.{}", parts.join("."))) }
+}
+
+fn places_functional_tests(map: &Path) -> bool {
+    fs::read(map).is_ok_and(|contents| contents.windows(b"FunctionalTest".len()).any(|window| window == b"FunctionalTest"))
+}
+
+// the source directories of the project and of each of its plugins
+fn source_roots(project_directory: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![project_directory.join("Source")];
+    let mut plugin_descriptors = Vec::new();
+    collect_files(&project_directory.join("Plugins"), &["uplugin"], &mut plugin_descriptors);
+    roots.extend(plugin_descriptors.iter().filter_map(|descriptor| descriptor.parent()).map(|plugin| plugin.join("Source")));
+    roots
+}
+
+// a map from functional_test_maps as /Game/Maps/FTEST_Door, Maps/FTEST_Door or Maps/FTEST_Door.umap
+fn configured_map(content_directory: &Path, map: &str) -> PathBuf {
+    let relative = map.trim_start_matches("/Game/").trim_start_matches('/');
+    content_directory.join(relative).with_extension("umap")
+}
+
+// functional tests only show up in the engine's test list once their map is loaded, so they're found in the maps
+// themselves: the ones listed in functional_test_maps, or every map under Content/ that places one
+pub fn functional_test_maps(project_directory: &Path, configured: &[String]) -> Vec<TestLocation> {
+    let content_directory = project_directory.join("Content");
+    let maps: Vec<PathBuf> = if configured.is_empty() {
+        let mut maps = Vec::new();
+        collect_files(&content_directory, &["umap"], &mut maps);
+        maps.retain(|map| places_functional_tests(map));
+        maps
+    } else {
+        configured.iter().map(|map| configured_map(&content_directory, map.as_str())).collect()
+    };
+    maps.iter()
+        .filter_map(|map| functional_test_prefix(&content_directory, map).map(|test| TestLocation { test, file: map.display().to_string(), line: None }))
+        .collect()
+}
+
+// why the config would never run or report the tests of a functional test map: no run_tests selection reaches
+// them, or include_tests / exclude_tests drop them from the report
+pub fn filter_warnings(functional_tests: &[TestLocation], run_tests: &str, include_tests: &[String], exclude_tests: &[String]) -> Vec<String> {
+    let selections = instances::individual_tests(run_tests);
+    let mut warnings = Vec::new();
+    for location in functional_tests {
+        let test = location.test.as_str();
+        if !selections.iter().any(|selection| test.starts_with(selection.as_str()) || selection.starts_with(test)) {
+            warnings.push(format!("run_tests doesn't select {} ({})", test, location.file));
+        }
+        let included = include_tests.is_empty() || include_tests.iter().any(|pattern| filters::pattern_matches(pattern, test) || pattern.starts_with(test));
+        if !included || exclude_tests.iter().any(|pattern| filters::pattern_matches(pattern, test)) {
+            warnings.push(format!("include_tests / exclude_tests leave out {} ({})", test, location.file));
+        }
+    }
+    warnings
+}
+
+pub fn map_tests(project_directory: &Path) -> Vec<TestLocation> {
+    let mut locations = Vec::new();
+    for root in source_roots(project_directory) {
+        let mut files = Vec::new();
+        collect_files(&root, SOURCE_EXTENSIONS, &mut files);
+        for file in files {
+            if let Ok(source) = fs::read_to_string(&file) {
+                locations.extend(scan_source(source.as_str(), file.display().to_string().as_str()));
+            }
+        }
+    }
+    locations.extend(functional_test_maps(project_directory, &[]));
+    locations.sort_by(|a, b| a.test.cmp(&b.test));
+    locations
+}
+
+pub fn save_test_map(path: &Path, locations: &[TestLocation]) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(locations).expect("failed to serialize the test map"))
+}
+
+// a missing or unreadable map is an empty one, the report just doesn't learn anything from it
+pub fn load_test_map(path: &Path) -> Vec<TestLocation> {
+    fs::read(path).ok().and_then(|contents| serde_json::from_slice(&contents).ok()).unwrap_or_default()
+}
+
+// the most specific registration covering a test, Project.Door covers Project.Door.Opens but not Project.Doorway
+pub fn locate<'a>(locations: &'a [TestLocation], test: &str) -> Option<&'a TestLocation> {
+    locations.iter()
+        .filter(|location| test == location.test || test.strip_prefix(location.test.as_str()).is_some_and(|rest| rest.starts_with('.')))
+        .max_by_key(|location| location.test.len())
+}
+
+// tests defined in any of the files, for picking what to run after changing them
+pub fn tests_in_files<'a>(locations: &'a [TestLocation], files: &[&str]) -> Vec<&'a TestLocation> {
+    let normalize = |path: &str| path.replace('\\', "/").to_lowercase();
+    let files: Vec<String> = files.iter().map(|file| normalize(file)).collect();
+    locations.iter().filter(|location| files.iter().any(|file| normalize(location.file.as_str()).ends_with(file.as_str()))).collect()
+}
+
+// tests whose report didn't say where they're defined get it from the map, what the report says is kept
+pub fn apply_test_map(test_pass: &mut TestPass, locations: &[TestLocation]) {
+    for test in test_pass.tests.iter_mut() {
+        if test.info.as_ref().is_some_and(|info| info.source_file.is_some()) {
+            continue;
+        }
+        if let Some(location) = locate(locations, test.full_test_path.as_str()) {
+            let info = test.info.get_or_insert_with(TestInfo::default);
+            info.source_file = Some(location.file.clone());
+            info.source_file_line = location.line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn macros_map_tests_to_their_line() {
+        let source = r#"#include "Misc/AutomationTest.h"
+
+IMPLEMENT_SIMPLE_AUTOMATION_TEST(FDoorOpensTest, "Project.Door.Opens", EAutomationTestFlags::EditorContext | EAutomationTestFlags::ProductFilter)
+IMPLEMENT_CUSTOM_COMPLEX_AUTOMATION_TEST(FGateTest, FCastleTestBase, "Project.Gate", EAutomationTestFlags::EditorContext)
+
+BEGIN_DEFINE_SPEC(FDoorSpec, "Project.Door.Spec",
+    EAutomationTestFlags::ProductFilter)
+END_DEFINE_SPEC(FDoorSpec)"#;
+        let locations = scan_source(source, "Source/Castle/DoorTests.cpp");
+        let found: Vec<(&str, Option<i32>)> = locations.iter().map(|location| (location.test.as_str(), location.line)).collect();
+        assert_eq!(found, [("Project.Door.Opens", Some(3)), ("Project.Gate", Some(4)), ("Project.Door.Spec", Some(6))]);
+
+        assert_eq!(locate(&locations, "Project.Door.Spec.Opens.When Unlocked").map(|location| location.test.as_str()), Some("Project.Door.Spec"));
+        assert_eq!(locate(&locations, "Project.Gate.Lifts").and_then(|location| location.line), Some(4));
+        assert!(locate(&locations, "Project.Gateway").is_none());
+        assert_eq!(tests_in_files(&locations, &["Castle\\DoorTests.cpp"]).len(), 3);
+        assert_eq!(functional_test_prefix(Path::new("F:/Castle/Content"), Path::new("F:/Castle/Content/Maps/Startup.umap")).as_deref(), Some("Project.Functional Tests.Maps.Startup"));
+    }
+
+    #[test]
+    fn filters_that_miss_functional_test_maps() {
+        let maps = functional_test_maps(Path::new("F:/Castle"), &["/Game/Maps/FTEST_Door".to_owned(), "Maps/FTEST_Gate.umap".to_owned()]);
+        let tests: Vec<&str> = maps.iter().map(|location| location.test.as_str()).collect();
+        assert_eq!(tests, ["Project.Functional Tests.Maps.FTEST_Door", "Project.Functional Tests.Maps.FTEST_Gate"]);
+
+        assert!(filter_warnings(&maps, "Project.", &[], &[]).is_empty());
+        assert!(filter_warnings(&maps, "Project.Functional", &[], &[]).is_empty());
+        assert_eq!(filter_warnings(&maps, "Project.Door", &[], &[]).len(), 2);
+        let excluded = filter_warnings(&maps, "Project.", &["Project.Functional Tests.Maps.FTEST_Door.Opens".to_owned()], &["*FTEST_Door*".to_owned()]);
+        assert_eq!(excluded.len(), 2);
+        assert!(excluded.iter().all(|warning| warning.starts_with("include_tests / exclude_tests")));
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+// [test_names] shortens the paths the console prints, full_test_path is often 80+ characters and wraps in CI logs
+#[derive(Debug, Default, Deserialize)]
+pub struct TestNamesConfiguration {
+    pub strip_prefix: Option<String>, // ie "Project.Functional Tests.", or auto for the segments every test shares
+    pub last_segments: Option<usize>, // only the last N dot separated segments
+    #[serde(default)]
+    pub align: bool, // segments shared with the test above are blanked so tests line up under their parents
+}
+
+pub struct TestNames {
+    strip_prefix: String,
+    last_segments: Option<usize>,
+    align: bool,
+    previous: Vec<String>,
+}
+
+// the leading segments all paths share, always leaving every path at least its last segment
+fn common_prefix<'a>(mut paths: impl Iterator<Item = &'a str>) -> String {
+    let first: Vec<&str> = match paths.next() {
+        Some(first) => first.split('.').collect(),
+        None => return String::new(),
+    };
+    let mut shared = first.len() - 1;
+    for path in paths {
+        let segments: Vec<&str> = path.split('.').collect();
+        shared = shared.min(segments.len() - 1);
+        shared = first.iter().zip(segments.iter()).take(shared).take_while(|(a, b)| a == b).count();
+    }
+    first[..shared].iter().map(|segment| format!("{}.", segment)).collect()
+}
+
+impl TestNames {
+    pub fn new<'a>(config: Option<&TestNamesConfiguration>, paths: impl Iterator<Item = &'a str>) -> TestNames {
+        let config = match config {
+            Some(config) => config,
+            None => return TestNames { strip_prefix: String::new(), last_segments: None, align: false, previous: Vec::new() },
+        };
+        let strip_prefix = match config.strip_prefix.as_deref() {
+            Some("auto") => common_prefix(paths),
+            Some(strip_prefix) => strip_prefix.to_owned(),
+            None => String::new(),
+        };
+        TestNames { strip_prefix, last_segments: config.last_segments, align: config.align, previous: Vec::new() }
+    }
+
+    // call in the order the tests are printed, align compares each path with the one before
+    pub fn display(&mut self, full_test_path: &str) -> String {
+        let stripped = full_test_path.strip_prefix(self.strip_prefix.as_str()).filter(|stripped| !stripped.is_empty()).unwrap_or(full_test_path);
+        let mut segments: Vec<&str> = stripped.split('.').collect();
+        if let Some(last_segments) = self.last_segments.filter(|last_segments| *last_segments > 0) {
+            segments.drain(..segments.len().saturating_sub(last_segments));
+        }
+        if !self.align {
+            return segments.join(".");
+        }
+        let shared = segments.iter().zip(self.previous.iter()).take(segments.len() - 1).take_while(|(a, b)| **a == b.as_str()).count();
+        let indent: usize = segments[..shared].iter().map(|segment| segment.chars().count() + 1).sum();
+        let display = format!("{}{}", " ".repeat(indent), segments[shared..].join("."));
+        self.previous = segments.iter().map(|segment| segment.to_string()).collect();
+        display
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_segments_and_alignment() {
+        let paths = ["Project.Functional Tests.Door.Opens", "Project.Functional Tests.Door.Closes", "Project.Functional Tests.Gate.Lifts"];
+        let config = TestNamesConfiguration { strip_prefix: Some("auto".to_owned()), last_segments: None, align: true };
+        let mut test_names = TestNames::new(Some(&config), paths.iter().copied());
+        let displayed: Vec<String> = paths.iter().map(|path| test_names.display(path)).collect();
+        assert_eq!(displayed, ["Door.Opens", "     Closes", "Gate.Lifts"]);
+
+        let config = TestNamesConfiguration { strip_prefix: Some("Project.".to_owned()), last_segments: Some(2), align: false };
+        let mut test_names = TestNames::new(Some(&config), paths.iter().copied());
+        assert_eq!(test_names.display(paths[0]), "Door.Opens");
+        assert_eq!(test_names.display("Project."), "Project.");
+        assert_eq!(TestNames::new(None, paths.iter().copied()).display(paths[2]), paths[2]);
+        assert_eq!(common_prefix(["Project.Door", "Project.Door.Opens"].iter().copied()), "Project.");
+    }
+}
@@ -0,0 +1,236 @@
+use crate::lang::{self, Lang, Message};
+use crate::{width, Entry, EntryType, TestResult};
+use colored::*;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ThemeConfiguration {
+    pub preset: Option<String>, // default, minimal or high-contrast, the fields below override it
+    pub success_color: Option<String>,
+    pub fail_color: Option<String>,
+    pub warning_color: Option<String>,
+    pub info_color: Option<String>,
+    pub error_color: Option<String>,
+    pub expected_color: Option<String>,
+    pub path_color: Option<String>,
+    pub success_label: Option<String>,
+    pub fail_label: Option<String>,
+    pub warning_label: Option<String>,
+    pub info_label: Option<String>,
+    pub error_label: Option<String>,
+    pub expected_label: Option<String>,
+    pub spacer_width: Option<usize>,
+    pub bold: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Style {
+    color: Option<Color>, // None leaves the terminal's own color
+    bold: bool,
+    dimmed: bool,
+}
+
+impl Style {
+    fn new(color: Option<&str>, bold: bool) -> Style {
+        Style { color: color.and_then(parse_color), bold, dimmed: false }
+    }
+
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let painted = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        match (self.bold, self.dimmed) {
+            (true, _) => painted.bold(),
+            (false, true) => painted.dimmed(),
+            (false, false) => painted,
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Option<Color> {
+    match name.trim().to_lowercase().replace('_', " ").as_str() {
+        "" | "none" | "normal" => None,
+        name => Some(Color::from(name)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub success: Style,
+    pub fail: Style,
+    pub warning: Style,
+    pub info: Style,
+    pub error: Style,
+    pub expected: Style,
+    pub path: Style,
+    pub ignored: Style,
+    success_label: String,
+    fail_label: String,
+    warning_label: String,
+    info_label: String,
+    error_label: String,
+    expected_label: String,
+    ignored_label: String,
+    spacer_width: usize,
+}
+
+impl Theme {
+    fn preset(name: &str, lang: Lang) -> Theme {
+        let localized = [Message::SuccessLabel, Message::FailLabel, Message::WarningLabel, Message::InfoLabel, Message::ErrorLabel, Message::ExpectedLabel]
+            .map(|message| lang::text(lang, message));
+        let (colors, labels, spacer_width, bold) = match name {
+            "minimal" => (
+                [None, Some("red"), None, None, Some("red"), None, None],
+                ["ok", "FAIL", "warn", "info", "err", "exp"],
+                6,
+                false,
+            ),
+            "high-contrast" => (
+                [Some("bright green"), Some("bright red"), Some("bright yellow"), Some("bright white"), Some("bright red"), Some("bright cyan"), Some("bright white")],
+                localized,
+                13,
+                true,
+            ),
+            _ => (
+                [Some("bright green"), Some("red"), Some("yellow"), Some("white"), Some("red"), Some("cyan"), Some("white")],
+                localized,
+                13,
+                false,
+            ),
+        };
+        Theme {
+            success: Style::new(colors[0], bold),
+            fail: Style::new(colors[1], bold),
+            warning: Style::new(colors[2], bold),
+            info: Style::new(colors[3], false),
+            error: Style::new(colors[4], bold),
+            expected: Style::new(colors[5], false),
+            path: Style::new(colors[6], false),
+            ignored: Style { color: None, bold: false, dimmed: true },
+            success_label: labels[0].to_owned(),
+            fail_label: labels[1].to_owned(),
+            warning_label: labels[2].to_owned(),
+            info_label: labels[3].to_owned(),
+            error_label: labels[4].to_owned(),
+            expected_label: labels[5].to_owned(),
+            ignored_label: lang::text(lang, Message::IgnoredLabel).to_owned(),
+            spacer_width,
+        }
+    }
+
+    // the labels follow --lang unless the config sets its own, the minimal preset's abbreviations stay as they are
+    pub fn from_config(config: Option<&ThemeConfiguration>, lang: Lang) -> Theme {
+        let default_config = ThemeConfiguration::default();
+        let config = config.unwrap_or(&default_config);
+        let mut theme = Theme::preset(config.preset.as_deref().unwrap_or("default"), lang);
+        let bold = config.bold.unwrap_or(false);
+
+        let overrides = [
+            (&config.success_color, &mut theme.success),
+            (&config.fail_color, &mut theme.fail),
+            (&config.warning_color, &mut theme.warning),
+            (&config.info_color, &mut theme.info),
+            (&config.error_color, &mut theme.error),
+            (&config.expected_color, &mut theme.expected),
+            (&config.path_color, &mut theme.path),
+        ];
+        for (color, style) in overrides {
+            if let Some(color) = color {
+                style.color = parse_color(color);
+            }
+            style.bold |= bold;
+        }
+
+        let label_overrides = [
+            (&config.success_label, &mut theme.success_label),
+            (&config.fail_label, &mut theme.fail_label),
+            (&config.warning_label, &mut theme.warning_label),
+            (&config.info_label, &mut theme.info_label),
+            (&config.error_label, &mut theme.error_label),
+            (&config.expected_label, &mut theme.expected_label),
+        ];
+        for (label, theme_label) in label_overrides {
+            if let Some(label) = label {
+                *theme_label = label.clone();
+            }
+        }
+
+        if let Some(spacer_width) = config.spacer_width {
+            theme.spacer_width = spacer_width;
+        }
+        theme
+    }
+
+    // indentation matching the width of a label column
+    pub fn spacer(&self) -> String {
+        " ".repeat(self.spacer_width)
+    }
+
+    // padded by terminal columns, a japanese label is twice as wide as its character count
+    fn label(&self, label: &str, style: &Style) -> ColoredString {
+        let padding = self.spacer_width.saturating_sub(1).saturating_sub(width::display_width(label));
+        style.paint(format!("{}{} ", " ".repeat(padding), label).as_str())
+    }
+
+    pub fn test_status(&self, state: TestResult) -> ColoredString {
+        match state {
+            TestResult::Success => self.label(self.success_label.as_str(), &self.success),
+            TestResult::Fail => self.label(self.fail_label.as_str(), &self.fail),
+            _ => self.label(self.warning_label.as_str(), &self.warning),
+        }
+    }
+
+    pub fn ignored_label(&self) -> ColoredString {
+        self.label(self.ignored_label.as_str(), &self.ignored)
+    }
+
+    pub fn entry_label(&self, entry: &Entry) -> ColoredString {
+        match entry.event.entry_type {
+            _ if entry.expected => self.label(self.expected_label.as_str(), &self.expected),
+            EntryType::Info => self.label(self.info_label.as_str(), &self.info),
+            EntryType::Warning => self.label(self.warning_label.as_str(), &self.warning),
+            EntryType::Error => self.label(self.error_label.as_str(), &self.error),
+        }
+    }
+
+    // tests that neither passed nor failed are highlighted as warnings
+    pub fn test_path(&self, state: TestResult, path: &str) -> ColoredString {
+        match state {
+            TestResult::Success | TestResult::Fail => self.path.paint(path),
+            _ => self.warning.paint(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_right_aligned_to_the_spacer() {
+        let theme = Theme::from_config(None, Lang::En);
+        assert_eq!(&*theme.test_status(TestResult::Fail), "        Fail ");
+        assert_eq!(theme.spacer().len(), 13);
+        let theme = Theme::from_config(None, Lang::Ja);
+        assert_eq!(&*theme.test_status(TestResult::Fail), "        失敗 ");
+        assert_eq!(&*theme.ignored_label(), "        無視 ");
+    }
+
+    #[test]
+    fn config_overrides_the_preset() {
+        let config = ThemeConfiguration {
+            preset: Some("minimal".to_owned()),
+            fail_color: Some("bright_magenta".to_owned()),
+            fail_label: Some("FAILED".to_owned()),
+            spacer_width: Some(8),
+            bold: Some(true),
+            ..ThemeConfiguration::default()
+        };
+        let theme = Theme::from_config(Some(&config), Lang::Ja);
+        assert_eq!(&*theme.test_status(TestResult::Fail), " FAILED ");
+        assert_eq!(theme.fail.color, Some(Color::BrightMagenta));
+        assert!(theme.fail.bold);
+        assert_eq!(parse_color("none"), None);
+    }
+}
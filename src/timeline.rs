@@ -0,0 +1,29 @@
+use crate::datetime::{self, DisplayZone};
+use crate::theme::Theme;
+use crate::{should_ignore_message, TestPass};
+use std::io::{self, Write};
+
+// every entry from every test in timestamp order, useful when one test's side effects (a gc spike,
+// a leaked actor) make the next one fail
+pub fn write_timeline(out: &mut dyn Write, test_pass: &TestPass, ignore_regexes: &[String], theme: &Theme, zone: &DisplayZone) -> io::Result<()> {
+    let mut timeline: Vec<(&str, &crate::Entry)> = test_pass.tests.iter()
+        .flat_map(|test| test.entries.iter().map(move |entry| (test.full_test_path.as_str(), entry)))
+        .filter(|(_, entry)| !should_ignore_message(entry.event.message.as_str(), ignore_regexes))
+        .collect();
+    // entries whose timestamp can't be parsed keep their relative order at the end
+    timeline.sort_by_cached_key(|(_, entry)| {
+        let timestamp = datetime::parse_timestamp(entry.timestamp.as_str());
+        (timestamp.is_none(), timestamp)
+    });
+
+    writeln!(out, "timeline")?;
+    let mut previous_test = "";
+    for (test_path, entry) in timeline {
+        if test_path != previous_test {
+            writeln!(out, "{}", theme.path.paint(test_path))?;
+            previous_test = test_path;
+        }
+        writeln!(out, "{} {}{}", datetime::format_timestamp(entry.timestamp.as_str(), zone), theme.entry_label(entry), entry.event.message)?;
+    }
+    Ok(())
+}
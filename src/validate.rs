@@ -0,0 +1,138 @@
+use crate::theme::Theme;
+use crate::{diskspace, engine, remote, runner, testmap, TestConfiguration, TestResult};
+use std::fs;
+use std::path::Path;
+
+struct Checklist<'a> {
+    theme: &'a Theme,
+    failures: usize,
+}
+
+impl<'a> Checklist<'a> {
+    fn check(&mut self, description: &str, result: Result<String, String>) {
+        match result {
+            Ok(detail) if detail.is_empty() => println!("{}{}", self.theme.test_status(TestResult::Success), description),
+            Ok(detail) => println!("{}{}: {}", self.theme.test_status(TestResult::Success), description, detail),
+            Err(error) => {
+                self.failures += 1;
+                println!("{}{}: {}", self.theme.test_status(TestResult::Fail), description, error);
+            }
+        }
+    }
+}
+
+fn file_exists(path: &str) -> Result<String, String> {
+    if Path::new(path).is_file() {
+        Ok(path.to_owned())
+    } else {
+        Err(format!("{} does not exist", path))
+    }
+}
+
+// creates the directory if needed and writes (then removes) a probe file
+fn directory_writable(path: &str) -> Result<String, String> {
+    let directory = Path::new(path);
+    fs::create_dir_all(directory).map_err(|error| format!("failed to create {}: {}", path, error))?;
+    let probe = directory.join(".runtests-write-probe");
+    fs::write(&probe, b"").map_err(|error| format!("{} is not writable: {}", path, error))?;
+    fs::remove_file(&probe).ok();
+    Ok(path.to_owned())
+}
+
+fn regexes_compile<S: AsRef<str>>(patterns: &[S]) -> Result<String, String> {
+    let invalid: Vec<String> = patterns.iter()
+        .filter_map(|pattern| regex::Regex::new(pattern.as_ref()).err().map(|error| format!("{} ({})", pattern.as_ref(), error)))
+        .collect();
+    if invalid.is_empty() {
+        Ok(format!("{} patterns", patterns.len()))
+    } else {
+        Err(invalid.join(", "))
+    }
+}
+
+// checks everything a run depends on without starting the engine, returns the number of failed checks
+pub fn validate(config_file_path: &str, theme: &Theme) -> usize {
+    let mut checklist = Checklist { theme, failures: 0 };
+
+    let config_toml = match fs::read(config_file_path) {
+        Ok(bytes) => crate::utf_from_bytes(&bytes),
+        Err(error) => {
+            checklist.check("config file", Err(format!("failed to read {}: {}", config_file_path, error)));
+            return checklist.failures;
+        }
+    };
+    let config: TestConfiguration = match crate::parse_config(config_toml.as_str(), config_file_path, None) {
+        Ok((config, _)) => config,
+        Err(error) => {
+            checklist.check("config file", Err(format!("failed to parse {}: {}", config_file_path, error)));
+            return checklist.failures;
+        }
+    };
+    checklist.check("config file", Ok(config_file_path.to_owned()));
+
+    // with [remote] the paths are on the remote machine, only the connection can be checked from here
+    match &config.remote {
+        Some(remote_config) => checklist.check("remote host", remote::check_connection(remote_config)),
+        None => {
+            if let Some(engine_discovery) = &config.engine_discovery {
+                checklist.check("engine association", engine_discovery.clone());
+            }
+            checklist.check("engine executable", file_exists(config.path_to_unrealengine.as_str()));
+            checklist.check("project file", file_exists(config.path_to_project.as_str()));
+            checklist.check("report directory", directory_writable(config.path_to_reports.as_str()));
+            match engine::read_build_version(config.path_to_unrealengine.as_str()) {
+                Ok(version) => {
+                    checklist.check("engine version", Ok(version.to_string()));
+                    for warning in engine::compatibility_warnings(&version, &config) {
+                        println!("{}{}", theme.spacer(), theme.warning.paint(warning.as_str()));
+                    }
+                }
+                Err(error) => checklist.check("engine version", Err(error)),
+            }
+            let functional_tests = testmap::functional_test_maps(crate::project_directory(&config), &config.functional_test_maps);
+            for location in functional_tests.iter().filter(|_| !config.functional_test_maps.is_empty()) {
+                checklist.check("functional test map", file_exists(location.file.as_str()));
+            }
+            for warning in testmap::filter_warnings(&functional_tests, config.run_tests.as_str(), &config.include_tests, &config.exclude_tests) {
+                println!("{}{}", theme.spacer(), theme.warning.paint(warning.as_str()));
+            }
+        }
+    }
+
+    if let Some(min_free_disk_gb) = config.min_free_disk_gb {
+        for directory in crate::cli::disk_space_directories(&config) {
+            let free_space = diskspace::check_free_space(&directory, min_free_disk_gb)
+                .map(|free_gb| free_gb.map_or_else(|| "unknown, not checked".to_owned(), |free_gb| format!("{:.1} GB free on {}", free_gb, directory.display())));
+            checklist.check("disk space", free_space);
+        }
+    }
+
+    checklist.check("ignore_regexes", regexes_compile(&config.ignore_regexes));
+    if let Some(markers) = &config.expected_error_markers {
+        checklist.check("expected_error_markers", regexes_compile(markers));
+    }
+    if !config.screenshot_tolerances.is_empty() {
+        let patterns: Vec<&str> = config.screenshot_tolerances.iter().map(|tolerance| tolerance.pattern.as_str()).collect();
+        checklist.check("screenshot_tolerances", regexes_compile(&patterns));
+    }
+
+    checklist.check("runner", runner::runner(&config).map(|test_runner| test_runner.name().to_owned()));
+    if let Some(build_config) = &config.build {
+        checklist.check("build tool", file_exists(build_config.path_to_build_tool.as_str()));
+    }
+
+    checklist.failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_patterns_are_listed() {
+        assert_eq!(regexes_compile(&["Log.*", "^Ensure"]), Ok("2 patterns".to_owned()));
+        let error = regexes_compile(&["Log.*", "(", "["]).unwrap_err();
+        assert!(error.starts_with("( ("));
+        assert!(error.contains(", [ ("));
+    }
+}
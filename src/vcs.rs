@@ -0,0 +1,177 @@
+use colored::*;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Deserialize)]
+pub struct P4Configuration {
+    pub path: String, // depot or workspace path, ie //depot/CastleAdventure/...
+    pub port: Option<String>,
+    pub user: Option<String>,
+    pub client: Option<String>,
+}
+
+fn p4_command(config: &P4Configuration) -> Command {
+    let mut p4_command = Command::new("p4");
+    if let Some(port) = &config.port {
+        p4_command.args(["-p", port.as_str()]);
+    }
+    if let Some(user) = &config.user {
+        p4_command.args(["-u", user.as_str()]);
+    }
+    if let Some(client) = &config.client {
+        p4_command.args(["-c", client.as_str()]);
+    }
+    p4_command
+}
+
+// returns the most recent changelist synced to the workspace for the configured path
+pub fn current_changelist(config: &P4Configuration) -> Option<String> {
+    let mut p4_command = p4_command(config);
+    p4_command.args(["changes", "-m1", format!("{}#have", config.path).as_str()]);
+
+    let output = match p4_command.output() {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("{}{}", "failed to run p4: ".yellow(), error);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        eprintln!("{}{}", "p4 changes failed: ".yellow(), String::from_utf8_lossy(&output.stderr).trim());
+        return None;
+    }
+
+    changelist_number(&String::from_utf8_lossy(&output.stdout))
+}
+
+// the stream the workspace is on, ie //Castle/Release-1.2, None for classic workspaces
+pub fn current_stream(config: &P4Configuration) -> Option<String> {
+    let output = p4_command(config).args(["-ztag", "-F", "%Stream%", "client", "-o"]).output().ok()?;
+    let stream = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if !output.status.success() || stream.is_empty() {
+        return None;
+    }
+    Some(stream)
+}
+
+// Change 12345 on 2023/10/01 by user@client 'description'
+fn changelist_number(changes: &str) -> Option<String> {
+    let mut words = changes.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("Change"), Some(changelist)) => Some(changelist.to_owned()),
+        _ => None,
+    }
+}
+
+// the submitted changelists after good up to and including bad, oldest first
+pub fn changelists_between(config: &P4Configuration, good: &str, bad: &str) -> Result<Vec<String>, String> {
+    let output = p4_command(config)
+        .args(["changes", "-s", "submitted", format!("{}@{},@{}", config.path, good, bad).as_str()])
+        .output()
+        .map_err(|error| format!("failed to run p4: {}", error))?;
+    if !output.status.success() {
+        return Err(format!("p4 changes failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(changelist_numbers(&String::from_utf8_lossy(&output.stdout), good))
+}
+
+// p4 changes lists the newest first and includes good itself
+fn changelist_numbers(changes: &str, good: &str) -> Vec<String> {
+    let mut changelists: Vec<String> = changes.lines().filter_map(changelist_number).filter(|changelist| changelist != good).collect();
+    changelists.reverse();
+    changelists
+}
+
+#[derive(Debug)]
+pub struct GitInfo {
+    pub commit: String,
+    pub branch: String,
+    pub dirty: bool,
+}
+
+fn git_output(directory: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(directory).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+// returns None when the directory isn't inside a git repo (or git isn't installed)
+pub fn current_git_info(directory: &Path) -> Option<GitInfo> {
+    let commit = git_output(directory, &["rev-parse", "HEAD"])?;
+    let branch = git_output(directory, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+    let dirty = git_output(directory, &["status", "--porcelain"]).is_some_and(|status| !status.is_empty());
+    Some(GitInfo { commit, branch, dirty })
+}
+
+// the commits after good up to and including bad that descend from good, oldest first
+pub fn commits_between(directory: &Path, good: &str, bad: &str) -> Result<Vec<String>, String> {
+    let range = format!("{}..{}", good, bad);
+    git_output(directory, &["rev-list", "--reverse", "--ancestry-path", range.as_str()])
+        .map(|commits| commits.lines().map(str::to_owned).collect())
+        .ok_or_else(|| format!("git rev-list {} failed in {}", range, directory.display()))
+}
+
+impl std::fmt::Display for GitInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({})", &self.commit[..self.commit.len().min(10)], self.branch)?;
+        if self.dirty {
+            write!(f, " dirty")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changelist_number_from_p4_changes() {
+        assert_eq!(changelist_number("Change 12345 on 2023/10/01 by user@client 'Fix the doors'\n"), Some("12345".to_owned()));
+        assert_eq!(changelist_number(""), None);
+        assert_eq!(changelist_number("//depot/Castle/...#have - no such file(s)."), None);
+    }
+
+    #[test]
+    fn changelists_oldest_first_without_good() {
+        let changes = "Change 12347 on 2023/10/02 by user@client 'Gate'\nChange 12346 on 2023/10/01 by user@client 'Door'\nChange 12345 on 2023/10/01 by user@client 'Wall'\n";
+        assert_eq!(changelist_numbers(changes, "12345"), ["12346", "12347"]);
+    }
+
+    fn git(directory: &Path, args: &[&str]) {
+        let status = Command::new("git").arg("-C").arg(directory)
+            .args(["-c", "user.name=runtests", "-c", "user.email=runtests@localhost", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn git_info_of_a_repository() {
+        let directory = std::env::temp_dir().join(format!("runtests-vcs-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        git(&directory, &["init", "-q"]);
+        std::fs::write(directory.join("Castle.uproject"), "{}").unwrap();
+        git(&directory, &["add", "."]);
+        git(&directory, &["commit", "-q", "-m", "castle"]);
+        let clean = current_git_info(&directory).unwrap();
+        std::fs::write(directory.join("Castle.uproject"), "{ }").unwrap();
+        let dirty = current_git_info(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(clean.commit.len(), 40);
+        assert_eq!((clean.branch.as_str(), clean.dirty), ("main", false));
+        assert!(dirty.dirty);
+        assert_eq!(dirty.to_string(), format!("{} (main) dirty", &clean.commit[..10]));
+    }
+
+    #[test]
+    fn no_git_info_outside_a_repository() {
+        assert!(current_git_info(Path::new("/")).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// time the engine spent starting up before the first test, mostly shader compilation and
+// derived data cache fills on a cold machine; it isn't part of any test's duration
+#[derive(Debug, Clone, Serialize)]
+pub struct Warmup {
+    pub seconds: f32,
+    pub shader_compile_seconds: Option<f32>, // from the first to the last shader compilation line before the first test
+    pub ddc_misses: usize,
+}
+
+// engine log lines start with [2023.10.01-12.34.56:789][frame], the log is written in one timezone so only differences matter
+fn log_timestamp(line: &str) -> Option<NaiveDateTime> {
+    let timestamp = line.strip_prefix('[')?.get(..23)?;
+    NaiveDateTime::parse_from_str(timestamp, "%Y.%m.%d-%H.%M.%S:%3f").ok()
+}
+
+fn seconds_between(start: NaiveDateTime, end: NaiveDateTime) -> f32 {
+    (end - start).num_milliseconds() as f32 / 1000.0
+}
+
+// reads the engine log up to the first started test, None if the log is missing or no test started
+pub fn read_warmup(log_path: &Path) -> Option<Warmup> {
+    let file = File::open(log_path).ok()?;
+    let test_started = regex::Regex::new(r"Test Started\. Name=").unwrap();
+    let shader_compile = regex::Regex::new(r"(?i)compiling shaders|shaders left to compile|shader jobs").unwrap();
+    let ddc_miss = regex::Regex::new(r"(?i)LogDerivedDataCache.*\bmiss").unwrap();
+
+    let mut first_timestamp = None;
+    let mut shader_span: Option<(NaiveDateTime, NaiveDateTime)> = None;
+    let mut ddc_misses = 0;
+    for line in BufReader::new(file).split(b'\n').map_while(Result::ok) {
+        let line = String::from_utf8_lossy(&line);
+        let timestamp = log_timestamp(&line);
+        if first_timestamp.is_none() {
+            first_timestamp = timestamp;
+        }
+
+        if test_started.is_match(&line) {
+            let warmup = seconds_between(first_timestamp?, timestamp?);
+            return Some(Warmup {
+                seconds: warmup,
+                shader_compile_seconds: shader_span.map(|(start, end)| seconds_between(start, end)),
+                ddc_misses,
+            });
+        }
+        if shader_compile.is_match(&line) {
+            if let Some(timestamp) = timestamp {
+                shader_span = Some(shader_span.map_or((timestamp, timestamp), |(start, _)| (start, timestamp)));
+            }
+        }
+        if ddc_miss.is_match(&line) {
+            ddc_misses += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warmup_until_the_first_test() {
+        let log = "\
+[2023.10.01-12.00.00:000][  0]LogInit: Display: Running engine for game: Castle
+[2023.10.01-12.00.05:000][  0]LogShaderCompilers: Display: Compiling shaders
+[2023.10.01-12.00.10:500][  0]LogDerivedDataCache: Display: Cache miss on ShaderMap
+[2023.10.01-12.00.20:250][  0]LogShaderCompilers: Display: 12 Shaders left to compile
+[2023.10.01-12.00.30:000][ 10]LogAutomationController: Display: Test Started. Name={Door Opens}
+[2023.10.01-12.00.40:000][ 20]LogDerivedDataCache: Display: Cache miss on Texture
+";
+        let log_path = std::env::temp_dir().join(format!("runtests-warmup-{}.log", std::process::id()));
+        std::fs::write(&log_path, log).unwrap();
+        let warmup = read_warmup(&log_path);
+        std::fs::write(&log_path, log.lines().take(3).collect::<Vec<&str>>().join("\n")).unwrap();
+        let no_test = read_warmup(&log_path);
+        std::fs::remove_file(&log_path).unwrap();
+
+        let warmup = warmup.unwrap();
+        assert_eq!(warmup.seconds, 30.0);
+        assert_eq!(warmup.shader_compile_seconds, Some(15.25));
+        assert_eq!(warmup.ddc_misses, 1);
+        assert!(no_test.is_none());
+    }
+}
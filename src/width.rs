@@ -0,0 +1,103 @@
+use std::io::IsTerminal;
+use std::process::Command;
+
+// the console's width, None when stdout isn't a terminal (CI logs don't wrap, --width asks for it there)
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|columns| columns.trim().parse().ok()) {
+        return Some(columns);
+    }
+    query_width()
+}
+
+#[cfg(windows)]
+fn query_width() -> Option<usize> {
+    let output = Command::new("powershell").args(["-NoProfile", "-Command", "$Host.UI.RawUI.WindowSize.Width"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+// stty size prints "rows columns" for the terminal on its stdin
+#[cfg(not(windows))]
+fn query_width() -> Option<usize> {
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let output = Command::new("stty").arg("size").stdin(tty).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).split_whitespace().nth(1)?.parse().ok()
+}
+
+// breaks at spaces where it can, words longer than a line are split; every line of a multi-line message wraps on its own
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for text_line in text.lines() {
+        let mut line = String::new();
+        for word in text_line.split(' ') {
+            let line_width = line.chars().count();
+            if line_width > 0 {
+                if line_width + 1 + word.chars().count() <= width {
+                    line.push(' ');
+                    line.push_str(word);
+                    continue;
+                }
+                lines.push(std::mem::take(&mut line));
+            }
+            let mut chars: Vec<char> = word.chars().collect();
+            while chars.len() > width {
+                lines.push(chars.drain(..width).collect());
+            }
+            line = chars.into_iter().collect();
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+// terminal columns, east asian wide characters (kana, kanji, hangul, fullwidth forms) take two
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(|character| match character as u32 {
+        0x1100..=0x115f | 0x2e80..=0x303e | 0x3041..=0x33ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3 | 0xf900..=0xfaff | 0xfe30..=0xfe4f | 0xff00..=0xff60 | 0xffe0..=0xffe6 => 2,
+        _ => 1,
+    }).sum()
+}
+
+// paths keep their start and their file name, the middle gives way
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width || width < 5 {
+        return text.to_owned();
+    }
+    let tail = (width - 3) / 2 + (width - 3) % 2;
+    let head = width - 3 - tail;
+    format!("{}...{}", chars[..head].iter().collect::<String>(), chars[chars.len() - tail..].iter().collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_wrap_at_spaces() {
+        assert_eq!(wrap("LogTemp: the door failed to open in time", 16), ["LogTemp: the", "door failed to", "open in time"]);
+        assert_eq!(wrap("D:/build/Castle/Source/Door.cpp", 12), ["D:/build/Cas", "tle/Source/D", "oor.cpp"]);
+        assert_eq!(wrap("first\nsecond line", 6), ["first", "second", "line"]);
+        assert_eq!(wrap("", 10), [""]);
+    }
+
+    #[test]
+    fn paths_lose_their_middle() {
+        assert_eq!(truncate_middle("D:/build/Castle/Source/Door.cpp:12", 20), "D:/build...or.cpp:12");
+        assert_eq!(truncate_middle("Door.cpp:12", 20), "Door.cpp:12");
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        assert_eq!(display_width("Fail"), 4);
+        assert_eq!(display_width("失敗"), 4);
+        assert_eq!(display_width("エラー"), 6);
+    }
+}